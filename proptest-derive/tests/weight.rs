@@ -46,3 +46,48 @@ fn asserting_arbitrary() {
     assert_arbitrary::<T3>();
     assert_arbitrary::<T4>();
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Arbitrary)]
+enum Inner {
+    #[proptest(weight = 1000)]
+    X,
+    Y,
+    Z,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Arbitrary)]
+enum Outer {
+    #[proptest(weight = 1000)]
+    A,
+    B(Inner),
+}
+
+#[test]
+fn union_arms_cover_every_variant() {
+    use proptest::prelude::*;
+    use proptest::test_runner::{TestRunner, UnionArmCoverage};
+    use std::cell::RefCell;
+
+    let mut runner = TestRunner::new(ProptestConfig {
+        cases: 4,
+        union_arms: UnionArmCoverage::Strict,
+        failure_persistence: None,
+        ..ProptestConfig::default()
+    });
+    let values = RefCell::new(Vec::new());
+    runner
+        .run(&any::<Outer>(), |v| {
+            values.borrow_mut().push(v);
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(
+        vec![
+            Outer::A,
+            Outer::B(Inner::X),
+            Outer::B(Inner::Y),
+            Outer::B(Inner::Z),
+        ],
+        values.into_inner()
+    );
+}
@@ -0,0 +1,125 @@
+//! Compares shrinking a long failing `Vec` by deleting chunks of elements, as
+//! `collection::vec()` does, against deleting one element at a time.
+
+use std::cell::Cell;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::strategy::ValueTree;
+use proptest::test_runner::{Config, TestRunner};
+
+fn fails(v: &[u32]) -> bool {
+    v.iter().any(|&x| x >= 990)
+}
+
+fn runner() -> TestRunner {
+    TestRunner::new_with_rng(
+        Config {
+            max_shrink_iters: 1_000_000,
+            failure_persistence: None,
+            ..Config::default()
+        },
+        TestRunner::deterministic().new_rng(),
+    )
+}
+
+/// A failing case with at least 9,000 elements.
+fn failing_case(
+    runner: &mut TestRunner,
+) -> impl ValueTree<Value = Vec<u32>> + Clone {
+    let input = vec(0u32..1000, 0..=10_000);
+    loop {
+        let case = input.new_tree(runner).unwrap();
+        if case.current().len() >= 9000 && fails(&case.current()) {
+            return case;
+        }
+    }
+}
+
+/// Shrink `case` with the test runner, returning the number of test calls.
+fn shrink(
+    runner: &mut TestRunner,
+    case: impl ValueTree<Value = Vec<u32>>,
+) -> usize {
+    let calls = Cell::new(0);
+    let _ = runner.run_one(case, |v| {
+        calls.set(calls.get() + 1);
+        prop_assert!(!fails(&v));
+        Ok(())
+    });
+    calls.get()
+}
+
+/// `ValueTree` which shrinks a `Vec` by deleting one element at a time, as
+/// collections used to.
+#[derive(Clone)]
+struct OneAtATime {
+    current: Vec<u32>,
+    prev: Option<Vec<u32>>,
+    ix: usize,
+}
+
+impl ValueTree for OneAtATime {
+    type Value = Vec<u32>;
+
+    fn current(&self) -> Vec<u32> {
+        self.current.clone()
+    }
+
+    fn simplify(&mut self) -> bool {
+        if self.ix >= self.current.len() {
+            return false;
+        }
+        self.prev = Some(self.current.clone());
+        self.current.remove(self.ix);
+        true
+    }
+
+    fn complicate(&mut self) -> bool {
+        match self.prev.take() {
+            Some(prev) => {
+                self.current = prev;
+                self.ix += 1;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn shrink_benchmark(c: &mut Criterion) {
+    let mut runner = runner();
+    let case = failing_case(&mut runner);
+    let one_at_a_time = OneAtATime {
+        current: case.current(),
+        prev: None,
+        ix: 0,
+    };
+    println!(
+        "Shrinking {} elements took {} test calls deleting chunks, \
+         against {} deleting one element at a time",
+        case.current().len(),
+        shrink(&mut runner, case.clone()),
+        shrink(&mut runner, one_at_a_time.clone())
+    );
+
+    c.bench_function("shrink vec deleting chunks", {
+        let mut runner = runner.clone();
+        move |b| {
+            b.iter_with_setup(
+                || case.clone(),
+                |case| black_box(shrink(&mut runner, case)),
+            )
+        }
+    });
+    c.bench_function("shrink vec deleting one element at a time", move |b| {
+        b.iter_with_setup(
+            || one_at_a_time.clone(),
+            |case| black_box(shrink(&mut runner, case)),
+        )
+    });
+}
+
+criterion_group!(benches, shrink_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,40 @@
+//-
+// Copyright 2021 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Detects standard library items which are newer than the minimum supported
+//! Rust version, so that proptest can support them when they are available.
+
+use std::env;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=RUSTC");
+    println!("cargo:rustc-check-cfg=cfg(proptest_saturating)");
+
+    // `core::num::Saturating` was stabilised in Rust 1.74.
+    if matches!(rustc_minor_version(), Some(minor) if minor >= 74) {
+        println!("cargo:rustc-cfg=proptest_saturating");
+    }
+}
+
+/// Returns the minor version of the compiler, e.g. `74` for
+/// `rustc 1.74.0 (79e9716c9 2023-11-13)`.
+fn rustc_minor_version() -> Option<u32> {
+    let rustc = env::var_os("RUSTC")?;
+    let output = Command::new(rustc).arg("--version").output().ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+    version
+        .split_whitespace()
+        .nth(1)?
+        .split('.')
+        .nth(1)?
+        .parse()
+        .ok()
+}
@@ -11,6 +11,7 @@
 
 use core::num::*;
 
+use crate::num;
 use crate::strategy::*;
 
 arbitrary!(ParseFloatError; "".parse::<f32>().unwrap_err());
@@ -23,6 +24,23 @@ arbitrary!(TryFromIntError; {
 });
 
 wrap_ctor!(Wrapping, Wrapping);
+#[cfg(proptest_saturating)]
+wrap_ctor!(Saturating, Saturating);
+
+arbitrary!(NonZeroI8, num::non_zero_i8::Any; num::non_zero_i8::ANY);
+arbitrary!(NonZeroI16, num::non_zero_i16::Any; num::non_zero_i16::ANY);
+arbitrary!(NonZeroI32, num::non_zero_i32::Any; num::non_zero_i32::ANY);
+arbitrary!(NonZeroI64, num::non_zero_i64::Any; num::non_zero_i64::ANY);
+#[cfg(not(target_arch = "wasm32"))]
+arbitrary!(NonZeroI128, num::non_zero_i128::Any; num::non_zero_i128::ANY);
+arbitrary!(NonZeroIsize, num::non_zero_isize::Any; num::non_zero_isize::ANY);
+arbitrary!(NonZeroU8, num::non_zero_u8::Any; num::non_zero_u8::ANY);
+arbitrary!(NonZeroU16, num::non_zero_u16::Any; num::non_zero_u16::ANY);
+arbitrary!(NonZeroU32, num::non_zero_u32::Any; num::non_zero_u32::ANY);
+arbitrary!(NonZeroU64, num::non_zero_u64::Any; num::non_zero_u64::ANY);
+#[cfg(not(target_arch = "wasm32"))]
+arbitrary!(NonZeroU128, num::non_zero_u128::Any; num::non_zero_u128::ANY);
+arbitrary!(NonZeroUsize, num::non_zero_usize::Any; num::non_zero_usize::ANY);
 
 arbitrary!(FpCategory,
     TupleUnion<(WA<Just<Self>>, WA<Just<Self>>, WA<Just<Self>>,
@@ -45,6 +63,18 @@ mod test {
         parse_float_error => ParseFloatError,
         parse_int_error => ParseIntError,
         wrapping => Wrapping<u8>,
+        non_zero_i8 => NonZeroI8,
+        non_zero_i16 => NonZeroI16,
+        non_zero_i32 => NonZeroI32,
+        non_zero_i64 => NonZeroI64,
+        non_zero_i128 => NonZeroI128,
+        non_zero_isize => NonZeroIsize,
+        non_zero_u8 => NonZeroU8,
+        non_zero_u16 => NonZeroU16,
+        non_zero_u32 => NonZeroU32,
+        non_zero_u64 => NonZeroU64,
+        non_zero_u128 => NonZeroU128,
+        non_zero_usize => NonZeroUsize,
         fp_category => FpCategory
     );
 
@@ -52,4 +82,9 @@ mod test {
     no_panic_test!(
         try_from_int_error => TryFromIntError
     );
+
+    #[cfg(proptest_saturating)]
+    no_panic_test!(
+        saturating => Saturating<u8>
+    );
 }
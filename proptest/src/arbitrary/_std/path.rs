@@ -9,15 +9,36 @@
 
 //! Arbitrary implementations for `std::path`.
 
+use crate::std_facade::Box;
 use std::path::*;
+use std::rc::Rc;
+use std::sync::Arc;
 
-// TODO: Figure out PathBuf and then Box/Rc/Box<Path>.
+use crate::arbitrary::*;
+use crate::path::{paths, PathParams, PathStrategy};
+use crate::strategy::*;
 
 arbitrary!(StripPrefixError; Path::new("").strip_prefix("a").unwrap_err());
 
+arbitrary!(PathBuf, PathStrategy, PathParams; args => paths(args));
+
+macro_rules! dst_wrapped {
+    ($($w: ident),*) => {
+        $(arbitrary!($w<Path>, MapInto<StrategyFor<PathBuf>, Self>, PathParams;
+            a => any_with::<PathBuf>(a).prop_map_into()
+        );)*
+    };
+}
+
+dst_wrapped!(Box, Rc, Arc);
+
 #[cfg(test)]
 mod test {
     no_panic_test!(
-        strip_prefix_error => StripPrefixError
+        strip_prefix_error => StripPrefixError,
+        path_buf => PathBuf,
+        box_path => Box<Path>,
+        rc_path => Rc<Path>,
+        arc_path => Arc<Path>
     );
 }
@@ -25,7 +25,6 @@ use bit_set::BitSet;
 use rand::{self, seq::IteratorRandom, Rng};
 
 use crate::collection::SizeRange;
-use crate::num::sample_uniform_incl;
 use crate::strategy::*;
 use crate::test_runner::*;
 
@@ -272,11 +271,7 @@ impl<T: BitSetLike> Strategy for SampledBitSetStrategy<T> {
 
     fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
         let mut bits = T::new_bitset(self.bits.end_excl());
-        let count = sample_uniform_incl(
-            runner,
-            self.size.start(),
-            self.size.end_incl(),
-        );
+        let count = self.size.sample(runner);
         if bits.len() < count {
             panic!("not enough bits to sample");
         }
@@ -30,6 +30,19 @@ impl Strategy for Any {
     fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
         Ok(BoolValueTree::new(runner.rng().gen()))
     }
+
+    enumerable_strategy!();
+}
+
+impl Enumerable for Any {
+    fn domain_size(&self) -> Option<u64> {
+        Some(2)
+    }
+
+    fn new_tree_at(&self, index: u64, _: &mut TestRunner) -> NewTree<Self> {
+        assert!(index < 2, "Index out of range of the domain");
+        Ok(BoolValueTree::new(1 == index))
+    }
 }
 
 /// Generates boolean values by picking `true` with the given `probability`
@@ -52,6 +65,26 @@ impl Strategy for Weighted {
     fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
         Ok(BoolValueTree::new(runner.rng().gen_bool(self.0)))
     }
+
+    enumerable_strategy!();
+}
+
+impl Enumerable for Weighted {
+    fn domain_size(&self) -> Option<u64> {
+        if self.0 <= 0.0 || self.0 >= 1.0 {
+            Some(1)
+        } else {
+            Some(2)
+        }
+    }
+
+    fn new_tree_at(&self, index: u64, _: &mut TestRunner) -> NewTree<Self> {
+        assert!(
+            index < self.domain_size().unwrap(),
+            "Index out of range of the domain"
+        );
+        Ok(BoolValueTree::new(self.0 >= 1.0 || 1 == index))
+    }
 }
 
 /// The `ValueTree` to shrink booleans to false.
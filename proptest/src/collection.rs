@@ -9,18 +9,22 @@
 
 //! Strategies for generating `std::collections` of values.
 
-use core::cmp::Ord;
+use core::cmp::{self, Ord};
+use core::convert::TryFrom;
 use core::hash::Hash;
 use core::ops::{Add, Range, RangeInclusive, RangeTo, RangeToInclusive};
 use core::usize;
 
 use crate::std_facade::{
-    fmt, BTreeMap, BTreeSet, BinaryHeap, LinkedList, Vec, VecDeque,
+    fmt, Arc, BTreeMap, BTreeSet, BinaryHeap, LinkedList, Vec, VecDeque,
 };
 
 #[cfg(feature = "std")]
 use crate::std_facade::{HashMap, HashSet};
 
+use rand::seq::index;
+use rand::Rng;
+
 use crate::bits::{BitSetLike, VarBitSet};
 use crate::num::sample_uniform_incl;
 use crate::strategy::*;
@@ -37,9 +41,43 @@ use crate::tuple::TupleValueTree;
 /// A value like `0..=std::usize::MAX` will still be accepted but will silently
 /// truncate the maximum to `std::usize::MAX - 1`.
 ///
+/// Sizes are chosen uniformly from the range by default; see
+/// `with_distribution()` for other choices.
+///
 /// The `Default` is `0..100`.
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
-pub struct SizeRange(Range<usize>);
+pub struct SizeRange {
+    range: Range<usize>,
+    distribution: SizeDistribution,
+}
+
+/// How sizes are chosen from a `SizeRange`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SizeDistribution {
+    /// Every size in the range is equally likely. This is the default.
+    Uniform,
+    /// Sizes are biased towards the minimum on a logarithmic scale.
+    ///
+    /// The distance from the minimum is chosen by first picking one of the
+    /// ranges `[0, 0]`, `[1, 2]`, `[3, 6]`, `[7, 14]`, ... (as far as the
+    /// range allows) with equal probability, then a distance within it
+    /// uniformly. For example, with `0..=10_000`, sizes below 8 are about as
+    /// likely as sizes from 1023 up, so most values are small but huge ones
+    /// are still generated regularly.
+    LogUniform,
+    /// The minimum and the maximum are each chosen a quarter of the time, and
+    /// the other half of the time sizes are chosen uniformly.
+    ///
+    /// This is useful to exercise both empty (or minimal) and full
+    /// collections often.
+    Bimodal,
+}
+
+impl Default for SizeDistribution {
+    fn default() -> Self {
+        SizeDistribution::Uniform
+    }
+}
 
 /// Creates a `SizeRange` from some value that is convertible into it.
 pub fn size_range(from: impl Into<SizeRange>) -> SizeRange {
@@ -77,8 +115,93 @@ impl SizeRange {
         self.with(Default::default())
     }
 
+    /// Returns this range with sizes chosen according to `distribution`.
+    ///
+    /// Since collection strategies and the `Arbitrary` implementations of
+    /// collections take a `SizeRange`, this can be used with either:
+    ///
+    /// ```
+    /// use proptest::collection::{size_range, vec, SizeDistribution};
+    /// use proptest::prelude::*;
+    ///
+    /// let mostly_small = size_range(0..10_000)
+    ///     .with_distribution(SizeDistribution::LogUniform);
+    /// let strategy = vec(any::<u8>(), mostly_small.clone());
+    /// let same = any_with::<Vec<u8>>(mostly_small.lift());
+    /// # let _ = (strategy, same);
+    /// ```
+    pub fn with_distribution(self, distribution: SizeDistribution) -> Self {
+        SizeRange {
+            distribution,
+            ..self
+        }
+    }
+
+    /// Returns how sizes are chosen from this range.
+    pub fn distribution(&self) -> SizeDistribution {
+        self.distribution
+    }
+
+    /// Scales the maximum of this range down according to `size`, keeping
+    /// the minimum.
+    ///
+    /// `size` is usually the size of a `TestRunner` (see
+    /// `TestRunner::size()`). At `MAX_SIZE` (or above), the range is
+    /// unchanged. Below that, the distance between the minimum and the
+    /// maximum is scaled proportionally, rounding up, so at a size of 0 only
+    /// the minimum remains.
+    ///
+    /// ```
+    /// use proptest::collection::size_range;
+    /// use proptest::test_runner::MAX_SIZE;
+    ///
+    /// assert_eq!(size_range(0..=1000), size_range(0..=1000).scaled(MAX_SIZE));
+    /// assert_eq!(size_range(0..=10), size_range(0..=1000).scaled(1));
+    /// assert_eq!(size_range(5..=5), size_range(5..=1000).scaled(0));
+    /// ```
+    pub fn scaled(&self, size: u32) -> Self {
+        if size >= MAX_SIZE {
+            return self.clone();
+        }
+
+        let (start, end) = self.start_end_incl();
+        let span = ((end - start) as u128 * u128::from(size)
+            + u128::from(MAX_SIZE)
+            - 1)
+            / u128::from(MAX_SIZE);
+        size_range(start..=start + span as usize)
+            .with_distribution(self.distribution)
+    }
+
+    /// Samples a size from this range according to its distribution, scaled
+    /// according to the size of `runner`.
+    pub(crate) fn sample(&self, runner: &mut TestRunner) -> usize {
+        let (start, end) = self.scaled(runner.size()).start_end_incl();
+        match self.distribution {
+            SizeDistribution::Uniform => {
+                sample_uniform_incl(runner, start, end)
+            }
+            SizeDistribution::LogUniform => {
+                let span = end - start;
+                // The number of buckets `[2^k - 1, 2^(k+1) - 2]` which start
+                // within `[0, span]`.
+                let buckets =
+                    (128 - (span as u128 + 1).leading_zeros()) as usize;
+                let k = sample_uniform_incl(runner, 0, buckets - 1);
+                let low = (1usize << k) - 1;
+                let high = cmp::min(span, low.saturating_mul(2));
+                start + sample_uniform_incl(runner, low, high)
+            }
+            SizeDistribution::Bimodal => match runner.rng().gen_range(0..4) {
+                0 => start,
+                1 => end,
+                _ => sample_uniform_incl(runner, start, end),
+            },
+        }
+    }
+
     pub(crate) fn start(&self) -> usize {
-        self.0.start
+        self.range.start
     }
 
     /// Extract the ends `[low, high]` of a `SizeRange`.
@@ -87,15 +210,15 @@ impl SizeRange {
     }
 
     pub(crate) fn end_incl(&self) -> usize {
-        self.0.end - 1
+        self.range.end - 1
     }
 
     pub(crate) fn end_excl(&self) -> usize {
-        self.0.end
+        self.range.end
     }
 
     pub(crate) fn iter(&self) -> impl Iterator<Item = usize> {
-        self.0.clone().into_iter()
+        self.range.clone().into_iter()
     }
 
     pub(crate) fn is_empty(&self) -> bool {
@@ -142,7 +265,10 @@ impl From<RangeTo<usize>> for SizeRange {
 /// Given `low .. high`, then a size range `[low, high)` is the result.
 impl From<Range<usize>> for SizeRange {
     fn from(r: Range<usize>) -> Self {
-        SizeRange(r)
+        SizeRange {
+            range: r,
+            distribution: SizeDistribution::Uniform,
+        }
     }
 }
 
@@ -166,7 +292,7 @@ impl Generic for SizeRange {
 
     /// Converts the `SizeRange` into `Range<usize>`.
     fn into(self) -> Self::Repr {
-        self.0
+        self.range
     }
 
     /// Converts `RangeInclusive<usize>` into `SizeRange`.
@@ -175,7 +301,8 @@ impl Generic for SizeRange {
     }
 }
 
-/// Adds `usize` to both start and end of the bounds.
+/// Adds `usize` to both start and end of the bounds, keeping the
+/// distribution.
 ///
 /// Panics if adding to either end overflows `usize`.
 impl Add<usize> for SizeRange {
@@ -184,6 +311,7 @@ impl Add<usize> for SizeRange {
     fn add(self, rhs: usize) -> Self::Output {
         let (start, end) = self.start_end_incl();
         size_range((start + rhs)..=(end + rhs))
+            .with_distribution(self.distribution)
     }
 }
 
@@ -308,283 +436,1876 @@ where
     BinaryHeapStrategy(statics::Map::new(vec(element, size), VecToBinHeap))
 }
 
-mapfn! {
-    {#[cfg(feature = "std")]}
-    [] fn VecToHashSet[<T : fmt::Debug + Hash + Eq>](vec: Vec<T>)
-                                                     -> HashSet<T> {
-        vec.into_iter().collect()
+/// Decides whether the elements of a collection are distinct, for
+/// collections which must not contain duplicates.
+trait Distinct<T>: Clone + fmt::Debug {
+    /// The elements, or keys of elements, seen so far.
+    type Seen: Default;
+
+    /// Record `value` in `seen`, returning whether it was not seen before.
+    fn insert(&self, seen: &mut Self::Seen, value: T) -> bool;
+
+    /// Sort `values`, which are paired with their positions, into the order
+    /// in which they should be shrunk.
+    ///
+    /// Shrinking smaller elements first means that larger ones rarely shrink
+    /// into duplicates of elements which could still be made smaller. Without
+    /// an ordering, the values are left as they are.
+    fn sort_for_shrinking(&self, _values: &mut [(usize, T)]) {}
+
+    /// Whether `a` and `b` are duplicates of each other.
+    fn same(&self, a: &T, b: &T) -> bool;
+}
+
+/// Elements are distinct if they are unequal according to `Ord`.
+#[derive(Clone, Copy, Debug)]
+struct ByOrd;
+
+impl<T: Ord> Distinct<T> for ByOrd {
+    type Seen = BTreeSet<T>;
+
+    fn insert(&self, seen: &mut BTreeSet<T>, value: T) -> bool {
+        seen.insert(value)
+    }
+
+    fn sort_for_shrinking(&self, values: &mut [(usize, T)]) {
+        values.sort_by(|a, b| a.1.cmp(&b.1));
+    }
+
+    fn same(&self, a: &T, b: &T) -> bool {
+        a == b
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct MinSize(usize);
+/// Elements are distinct if they are unequal according to `Hash` and `Eq`.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug)]
+struct ByHash;
 
 #[cfg(feature = "std")]
-impl<T: Eq + Hash> statics::FilterFn<HashSet<T>> for MinSize {
-    fn apply(&self, set: &HashSet<T>) -> bool {
-        set.len() >= self.0
+impl<T: Hash + Eq> Distinct<T> for ByHash {
+    type Seen = HashSet<T>;
+
+    fn insert(&self, seen: &mut HashSet<T>, value: T) -> bool {
+        seen.insert(value)
+    }
+
+    fn same(&self, a: &T, b: &T) -> bool {
+        a == b
     }
 }
 
-opaque_strategy_wrapper! {
-    {#[cfg(feature = "std")]}
-    /// Strategy to create `HashSet`s with a length in a certain range.
-    ///
-    /// Created by the `hash_set()` function in the same module.
-    #[derive(Clone, Debug)]
-    pub struct HashSetStrategy[<T>][where T : Strategy, T::Value : Hash + Eq](
-        statics::Filter<statics::Map<VecStrategy<T>, VecToHashSet>, MinSize>)
-        -> HashSetValueTree<T::Tree>;
-    /// `ValueTree` corresponding to `HashSetStrategy`.
-    #[derive(Clone, Debug)]
-    pub struct HashSetValueTree[<T>][where T : ValueTree, T::Value : Hash + Eq](
-        statics::Filter<statics::Map<VecValueTree<T>, VecToHashSet>, MinSize>)
-        -> HashSet<T::Value>;
+/// Map entries are distinct if their keys are unequal according to `Ord`.
+#[derive(Clone, Copy, Debug)]
+struct KeysByOrd;
+
+impl<K: Ord, V> Distinct<(K, V)> for KeysByOrd {
+    type Seen = BTreeSet<K>;
+
+    fn insert(&self, seen: &mut BTreeSet<K>, (key, _): (K, V)) -> bool {
+        seen.insert(key)
+    }
+
+    fn sort_for_shrinking(&self, values: &mut [(usize, (K, V))]) {
+        values.sort_by(|a, b| (a.1).0.cmp(&(b.1).0));
+    }
+
+    fn same(&self, a: &(K, V), b: &(K, V)) -> bool {
+        a.0 == b.0
+    }
 }
 
-/// Create a strategy to generate `HashSet`s containing elements drawn from
-/// `element` and with a size range given by `size`.
-///
-/// This strategy will implicitly do local rejects to ensure that the `HashSet`
-/// has at least the minimum number of elements, in case `element` should
-/// produce duplicate values.
+/// Map entries are distinct if their keys are unequal according to `Hash`
+/// and `Eq`.
 #[cfg(feature = "std")]
-pub fn hash_set<T: Strategy>(
-    element: T,
-    size: impl Into<SizeRange>,
-) -> HashSetStrategy<T>
-where
-    T::Value: Hash + Eq,
-{
-    let size = size.into();
-    HashSetStrategy(statics::Filter::new(
-        statics::Map::new(vec(element, size.clone()), VecToHashSet),
-        "HashSet minimum size".into(),
-        MinSize(size.start()),
-    ))
-}
+#[derive(Clone, Copy, Debug)]
+struct KeysByHash;
 
-mapfn! {
-    [] fn VecToBTreeSet[<T : fmt::Debug + Ord>](vec: Vec<T>)
-                                                -> BTreeSet<T> {
-        vec.into_iter().collect()
+#[cfg(feature = "std")]
+impl<K: Hash + Eq, V> Distinct<(K, V)> for KeysByHash {
+    type Seen = HashSet<K>;
+
+    fn insert(&self, seen: &mut HashSet<K>, (key, _): (K, V)) -> bool {
+        seen.insert(key)
     }
-}
 
-impl<T: Ord> statics::FilterFn<BTreeSet<T>> for MinSize {
-    fn apply(&self, set: &BTreeSet<T>) -> bool {
-        set.len() >= self.0
+    fn same(&self, a: &(K, V), b: &(K, V)) -> bool {
+        a.0 == b.0
     }
 }
 
-opaque_strategy_wrapper! {
-    /// Strategy to create `BTreeSet`s with a length in a certain range.
-    ///
-    /// Created by the `btree_set()` function in the same module.
-    #[derive(Clone, Debug)]
-    pub struct BTreeSetStrategy[<T>][where T : Strategy, T::Value : Ord](
-        statics::Filter<statics::Map<VecStrategy<T>, VecToBTreeSet>, MinSize>)
-        -> BTreeSetValueTree<T::Tree>;
-    /// `ValueTree` corresponding to `BTreeSetStrategy`.
-    #[derive(Clone, Debug)]
-    pub struct BTreeSetValueTree[<T>][where T : ValueTree, T::Value : Ord](
-        statics::Filter<statics::Map<VecValueTree<T>, VecToBTreeSet>, MinSize>)
-        -> BTreeSet<T::Value>;
-}
+/// Elements are distinct if the keys computed by the function are unequal.
+struct ByKey<F>(Arc<F>);
 
-/// Create a strategy to generate `BTreeSet`s containing elements drawn from
-/// `element` and with a size range given by `size`.
-///
-/// This strategy will implicitly do local rejects to ensure that the
-/// `BTreeSet` has at least the minimum number of elements, in case `element`
-/// should produce duplicate values.
-pub fn btree_set<T: Strategy>(
-    element: T,
-    size: impl Into<SizeRange>,
-) -> BTreeSetStrategy<T>
-where
-    T::Value: Ord,
-{
-    let size = size.into();
-    BTreeSetStrategy(statics::Filter::new(
-        statics::Map::new(vec(element, size.clone()), VecToBTreeSet),
-        "BTreeSet minimum size".into(),
-        MinSize(size.start()),
-    ))
+impl<F> Clone for ByKey<F> {
+    fn clone(&self) -> Self {
+        ByKey(Arc::clone(&self.0))
+    }
 }
 
-mapfn! {
-    {#[cfg(feature = "std")]}
-    [] fn VecToHashMap[<K : fmt::Debug + Hash + Eq, V : fmt::Debug>]
-        (vec: Vec<(K, V)>) -> HashMap<K, V>
-    {
-        vec.into_iter().collect()
+impl<F> fmt::Debug for ByKey<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("ByKey").field(&"<function>").finish()
     }
 }
 
-#[cfg(feature = "std")]
-impl<K: Hash + Eq, V> statics::FilterFn<HashMap<K, V>> for MinSize {
-    fn apply(&self, map: &HashMap<K, V>) -> bool {
-        map.len() >= self.0
+impl<T, K: Ord, F: Fn(&T) -> K> Distinct<T> for ByKey<F> {
+    type Seen = BTreeSet<K>;
+
+    fn insert(&self, seen: &mut BTreeSet<K>, value: T) -> bool {
+        seen.insert((self.0)(&value))
+    }
+
+    fn sort_for_shrinking(&self, values: &mut [(usize, T)]) {
+        values.sort_by_cached_key(|v| (self.0)(&v.1));
+    }
+
+    fn same(&self, a: &T, b: &T) -> bool {
+        (self.0)(a) == (self.0)(b)
     }
 }
 
-opaque_strategy_wrapper! {
-    {#[cfg(feature = "std")]}
-    /// Strategy to create `HashMap`s with a length in a certain range.
-    ///
-    /// Created by the `hash_map()` function in the same module.
-    #[derive(Clone, Debug)]
-    pub struct HashMapStrategy[<K, V>]
-        [where K : Strategy, V : Strategy, K::Value : Hash + Eq](
-            statics::Filter<statics::Map<VecStrategy<(K,V)>,
-            VecToHashMap>, MinSize>)
-        -> HashMapValueTree<K::Tree, V::Tree>;
-    /// `ValueTree` corresponding to `HashMapStrategy`.
-    #[derive(Clone, Debug)]
-    pub struct HashMapValueTree[<K, V>]
-        [where K : ValueTree, V : ValueTree, K::Value : Hash + Eq](
-            statics::Filter<statics::Map<VecValueTree<TupleValueTree<(K, V)>>,
-            VecToHashMap>, MinSize>)
-        -> HashMap<K::Value, V::Value>;
+/// Domains of up to this many times the maximum size of a collection of
+/// distinct elements are sampled without replacement by default, since
+/// drawing elements until they are distinct would waste many draws on
+/// duplicates.
+const SAMPLE_DOMAIN_FACTOR: u64 = 4;
+
+/// Creates the element of a `DistinctVecStrategy` at an index of a domain
+/// of known size, so that elements can be drawn at distinct indices instead
+/// of being drawn until they are distinct.
+struct SampleDomain<T: Strategy> {
+    size: usize,
+    new_tree_at: fn(&T, u64, &mut TestRunner) -> NewTree<T>,
 }
 
-/// Create a strategy to generate `HashMap`s containing keys and values drawn
-/// from `key` and `value` respectively, and with a size within the given
-/// range.
-///
-/// This strategy will implicitly do local rejects to ensure that the `HashMap`
-/// has at least the minimum number of elements, in case `key` should produce
-/// duplicate values.
-#[cfg(feature = "std")]
-pub fn hash_map<K: Strategy, V: Strategy>(
-    key: K,
-    value: V,
-    size: impl Into<SizeRange>,
-) -> HashMapStrategy<K, V>
-where
-    K::Value: Hash + Eq,
-{
-    let size = size.into();
-    HashMapStrategy(statics::Filter::new(
-        statics::Map::new(vec((key, value), size.clone()), VecToHashMap),
-        "HashMap minimum size".into(),
-        MinSize(size.start()),
-    ))
+impl<T: Strategy> SampleDomain<T> {
+    fn new(
+        size: Option<u64>,
+        new_tree_at: fn(&T, u64, &mut TestRunner) -> NewTree<T>,
+    ) -> Option<Self> {
+        size.and_then(|size| usize::try_from(size).ok())
+            .map(|size| SampleDomain { size, new_tree_at })
+    }
 }
 
-mapfn! {
-    [] fn VecToBTreeMap[<K : fmt::Debug + Ord, V : fmt::Debug>]
-        (vec: Vec<(K, V)>) -> BTreeMap<K, V>
-    {
-        vec.into_iter().collect()
+impl<T: Strategy> Clone for SampleDomain<T> {
+    fn clone(&self) -> Self {
+        *self
     }
 }
 
-impl<K: Ord, V> statics::FilterFn<BTreeMap<K, V>> for MinSize {
-    fn apply(&self, map: &BTreeMap<K, V>) -> bool {
-        map.len() >= self.0
+impl<T: Strategy> Copy for SampleDomain<T> {}
+
+impl<T: Strategy> fmt::Debug for SampleDomain<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SampleDomain")
+            .field("size", &self.size)
+            .finish()
     }
 }
 
-opaque_strategy_wrapper! {
-    /// Strategy to create `BTreeMap`s with a length in a certain range.
-    ///
-    /// Created by the `btree_map()` function in the same module.
-    #[derive(Clone, Debug)]
-    pub struct BTreeMapStrategy[<K, V>]
-        [where K : Strategy, V : Strategy, K::Value : Ord](
-            statics::Filter<statics::Map<VecStrategy<(K,V)>,
-            VecToBTreeMap>, MinSize>)
-        -> BTreeMapValueTree<K::Tree, V::Tree>;
-    /// `ValueTree` corresponding to `BTreeMapStrategy`.
-    #[derive(Clone, Debug)]
-    pub struct BTreeMapValueTree[<K, V>]
-        [where K : ValueTree, V : ValueTree, K::Value : Ord](
-            statics::Filter<statics::Map<VecValueTree<TupleValueTree<(K, V)>>,
-            VecToBTreeMap>, MinSize>)
-        -> BTreeMap<K::Value, V::Value>;
+/// Creates a map entry whose key is at `index` of the domain of the key
+/// strategy, with a value drawn as usual.
+fn new_entry_at<K: Enumerable, V: Strategy>(
+    entry: &(K, V),
+    index: u64,
+    runner: &mut TestRunner,
+) -> NewTree<(K, V)> {
+    Ok(TupleValueTree::new((
+        entry.0.new_tree_at(index, runner)?,
+        entry.1.new_tree(runner)?,
+    )))
 }
 
-/// Create a strategy to generate `BTreeMap`s containing keys and values drawn
-/// from `key` and `value` respectively, and with a size within the given
-/// range.
-///
-/// This strategy will implicitly do local rejects to ensure that the
-/// `BTreeMap` has at least the minimum number of elements, in case `key`
-/// should produce duplicate values.
-pub fn btree_map<K: Strategy, V: Strategy>(
-    key: K,
-    value: V,
-    size: impl Into<SizeRange>,
-) -> BTreeMapStrategy<K, V>
-where
-    K::Value: Ord,
-{
-    let size = size.into();
-    BTreeMapStrategy(statics::Filter::new(
-        statics::Map::new(vec((key, value), size.clone()), VecToBTreeMap),
-        "BTreeMap minimum size".into(),
-        MinSize(size.start()),
-    ))
+/// Like `new_entry_at()`, for a key strategy which is one of this crate's
+/// `Enumerable` strategies but not known to be one statically.
+fn enumerated_entry_at<K: Strategy, V: Strategy>(
+    entry: &(K, V),
+    index: u64,
+    runner: &mut TestRunner,
+) -> NewTree<(K, V)> {
+    Ok(TupleValueTree::new((
+        enumerated_tree_at(&entry.0, index, runner)?,
+        entry.1.new_tree(runner)?,
+    )))
 }
 
-#[derive(Clone, Copy, Debug)]
-enum Shrink {
-    DeleteElement(usize),
-    ShrinkElement(usize),
+/// Strategy to create `Vec`s without duplicate elements, as decided by `D`.
+///
+/// Elements which duplicate one already generated are discarded and drawn
+/// again. For elements from a small domain, such as a narrow range or a
+/// `sample::select` over a few values, this amounts to sampling the domain
+/// without replacement: once most of the domain has been drawn, further
+/// draws mostly produce duplicates, and the `Vec` is accepted with fewer
+/// elements than intended as long as it still reaches the minimum size.
+///
+/// With `sample` set, elements are instead created at distinct indices of
+/// the domain, chosen with `rand::seq::index::sample()`, so that no draws are
+/// wasted on duplicates. This is the default for domains of at most
+/// `SAMPLE_DOMAIN_FACTOR` times the maximum size.
+#[derive(Clone, Debug)]
+struct DistinctVecStrategy<T: Strategy, D> {
+    element: T,
+    size: SizeRange,
+    distinct: D,
+    whence: Reason,
+    sample: Option<SampleDomain<T>>,
 }
 
-/// `ValueTree` corresponding to `VecStrategy`.
+/// `ValueTree` corresponding to `DistinctVecStrategy`.
+///
+/// Shrinking an element into a duplicate of another is treated like a
+/// simplification which made the test pass, so the element is complicated
+/// back to a distinct value before shrinking continues.
+///
+/// The elements of `source` are kept in the order given by
+/// `Distinct::sort_for_shrinking`; `positions` maps each of them back to its
+/// position in the generated `Vec`.
 #[derive(Clone, Debug)]
-pub struct VecValueTree<T: ValueTree> {
-    elements: Vec<T>,
-    included_elements: VarBitSet,
-    min_size: usize,
-    shrink: Shrink,
-    prev_shrink: Option<Shrink>,
+struct DistinctVecValueTree<T: ValueTree, D> {
+    source: VecValueTree<T>,
+    positions: Vec<usize>,
+    distinct: D,
 }
 
-impl<T: Strategy> Strategy for VecStrategy<T> {
-    type Tree = VecValueTree<T::Tree>;
-    type Value = Vec<T::Value>;
+impl<T: Strategy, D: Distinct<T::Value>> DistinctVecStrategy<T, D> {
+    fn new(
+        element: T,
+        size: impl Into<SizeRange>,
+        distinct: D,
+        whence: &'static str,
+    ) -> Self {
+        let size = size.into();
+        size.assert_nonempty();
+        DistinctVecStrategy {
+            element,
+            size,
+            distinct,
+            whence: whence.into(),
+            sample: None,
+        }
+    }
 
-    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
-        let (start, end) = self.size.start_end_incl();
-        let max_size = sample_uniform_incl(runner, start, end);
-        let mut elements = Vec::with_capacity(max_size);
-        while elements.len() < max_size {
-            elements.push(self.element.new_tree(runner)?);
+    /// Draw up to `len` distinct elements, giving up once duplicates have
+    /// been drawn more often than the domain of `element` is likely to
+    /// still have values left.
+    fn draw(
+        &self,
+        len: usize,
+        runner: &mut TestRunner,
+    ) -> Result<Vec<T::Tree>, Reason> {
+        if let Some(sample) = self.sample {
+            return self.draw_sampled(sample, len, runner);
         }
 
-        Ok(VecValueTree {
-            elements,
-            included_elements: VarBitSet::saturated(max_size),
-            min_size: start,
-            shrink: Shrink::DeleteElement(0),
-            prev_shrink: None,
-        })
+        let max_duplicates = len.saturating_mul(8).saturating_add(64);
+        let mut duplicates = 0;
+        let mut seen = D::Seen::default();
+        let mut elements = Vec::with_capacity(len);
+        while elements.len() < len && duplicates <= max_duplicates {
+            let element = self.element.new_tree(runner)?;
+            if self.distinct.insert(&mut seen, element.current()) {
+                elements.push(element);
+            } else {
+                duplicates += 1;
+            }
+        }
+        Ok(elements)
     }
-}
 
-impl<T: Strategy> Strategy for Vec<T> {
-    type Tree = VecValueTree<T::Tree>;
-    type Value = Vec<T::Value>;
+    /// Draw up to `len` elements at distinct indices of the domain.
+    ///
+    /// Elements are still checked for duplicates, since e.g. a mapped
+    /// strategy can produce the same value at two indices.
+    fn draw_sampled(
+        &self,
+        sample: SampleDomain<T>,
+        len: usize,
+        runner: &mut TestRunner,
+    ) -> Result<Vec<T::Tree>, Reason> {
+        let len = cmp::min(len, sample.size);
+        let mut seen = D::Seen::default();
+        let mut elements = Vec::with_capacity(len);
+        for ix in index::sample(runner.rng(), sample.size, len) {
+            let element =
+                (sample.new_tree_at)(&self.element, ix as u64, runner)?;
+            if self.distinct.insert(&mut seen, element.current()) {
+                elements.push(element);
+            }
+        }
+        Ok(elements)
+    }
+}
 
-    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
-        let len = self.len();
-        let elements = self
-            .iter()
-            .map(|t| t.new_tree(runner))
-            .collect::<Result<Vec<_>, Reason>>()?;
+impl<T: Strategy, D> DistinctVecStrategy<T, D> {
+    /// Whether a domain of `domain_size` values is small enough to sample by
+    /// default.
+    fn is_small_domain(&self, domain_size: Option<u64>) -> bool {
+        let max_size = u64::try_from(self.size.end_incl()).unwrap_or(u64::MAX);
+        let limit = max_size.saturating_mul(SAMPLE_DOMAIN_FACTOR);
+        matches!(domain_size, Some(size) if size <= limit)
+    }
 
-        Ok(VecValueTree {
-            elements,
-            included_elements: VarBitSet::saturated(len),
-            min_size: len,
-            shrink: Shrink::ShrinkElement(0),
-            prev_shrink: None,
-        })
+    /// Sample the domain of `element` if it is small.
+    fn sample_small_domain(mut self) -> Self {
+        let domain_size = self.element.enumerable_domain_size();
+        if self.is_small_domain(domain_size) {
+            self.sample = SampleDomain::new(domain_size, enumerated_tree_at);
+        }
+        self
+    }
+}
+
+impl<K: Strategy, V: Strategy, D> DistinctVecStrategy<(K, V), D> {
+    /// Sample the domain of the key strategy if it is small.
+    fn sample_small_key_domain(mut self) -> Self {
+        let domain_size = self.element.0.enumerable_domain_size();
+        if self.is_small_domain(domain_size) {
+            self.sample = SampleDomain::new(domain_size, enumerated_entry_at);
+        }
+        self
+    }
+}
+
+impl<T: Enumerable, D> DistinctVecStrategy<T, D> {
+    fn sample_domain(&mut self) {
+        self.sample =
+            SampleDomain::new(self.element.domain_size(), T::new_tree_at);
+    }
+}
+
+impl<K: Enumerable, V: Strategy, D> DistinctVecStrategy<(K, V), D> {
+    fn sample_keys(&mut self) {
+        self.sample =
+            SampleDomain::new(self.element.0.domain_size(), new_entry_at);
+    }
+}
+
+impl<T: Strategy, D: Distinct<T::Value>> Strategy
+    for DistinctVecStrategy<T, D>
+{
+    type Tree = DistinctVecValueTree<T::Tree, D>;
+    type Value = Vec<T::Value>;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let start = self.size.start();
+        loop {
+            let elements = self.draw(self.size.sample(runner), runner)?;
+            if elements.len() < start {
+                runner.reject_local(self.whence.clone())?;
+                continue;
+            }
+
+            let mut order = elements
+                .iter()
+                .map(ValueTree::current)
+                .enumerate()
+                .collect::<Vec<_>>();
+            self.distinct.sort_for_shrinking(&mut order);
+            let positions =
+                order.into_iter().map(|(pos, _)| pos).collect::<Vec<_>>();
+
+            let mut elements =
+                elements.into_iter().map(Some).collect::<Vec<_>>();
+            let elements = positions
+                .iter()
+                .map(|&pos| elements[pos].take().expect("duplicate position"))
+                .collect::<Vec<_>>();
+
+            let len = elements.len();
+            return Ok(DistinctVecValueTree {
+                source: VecValueTree {
+                    elements,
+                    included_elements: VarBitSet::saturated(len),
+                    min_size: start,
+                    shrink: Shrink::delete_halves(len),
+                    prev_shrink: None,
+                    deleted: Vec::new(),
+                },
+                positions,
+                distinct: self.distinct.clone(),
+            });
+        }
+    }
+}
+
+impl<T: ValueTree, D: Distinct<T::Value>> DistinctVecValueTree<T, D> {
+    /// Complicate the element changed by the last shrink step until it no
+    /// longer duplicates another element.
+    ///
+    /// Deleting elements, or restoring deleted ones, cannot introduce
+    /// duplicates, so only a changed element needs to be compared against
+    /// the others.
+    fn ensure_distinct(&mut self) {
+        while let Some(Shrink::ShrinkElement(ix)) = self.source.prev_shrink {
+            if !self.is_duplicate(ix) {
+                return;
+            }
+
+            if !self.source.complicate() {
+                panic!(
+                    "Unable to complicate collection of unique elements \
+                     back into distinct elements"
+                );
+            }
+        }
+    }
+
+    /// Whether the element at `ix` duplicates another included element.
+    fn is_duplicate(&self, ix: usize) -> bool {
+        let source = &self.source;
+        let value = source.elements[ix].current();
+        source.elements.iter().enumerate().any(|(other, element)| {
+            other != ix
+                && source.included_elements.test(other)
+                && self.distinct.same(&value, &element.current())
+        })
+    }
+}
+
+impl<T: ValueTree, D: Distinct<T::Value>> ValueTree
+    for DistinctVecValueTree<T, D>
+{
+    type Value = Vec<T::Value>;
+
+    fn current(&self) -> Vec<T::Value> {
+        let source = &self.source;
+        let mut values =
+            (0..source.elements.len()).map(|_| None).collect::<Vec<_>>();
+        for (ix, element) in source.elements.iter().enumerate() {
+            if source.included_elements.test(ix) {
+                values[self.positions[ix]] = Some(element.current());
+            }
+        }
+        values.into_iter().flatten().collect()
+    }
+
+    fn simplify(&mut self) -> bool {
+        if self.source.simplify() {
+            self.ensure_distinct();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn complicate(&mut self) -> bool {
+        if self.source.complicate() {
+            self.ensure_distinct();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+opaque_strategy_wrapper! {
+    /// Strategy to create `Vec`s of unique elements with a length in a
+    /// certain range.
+    ///
+    /// Created by the `unique_vec()` function in the same module.
+    #[derive(Clone, Debug)]
+    pub struct UniqueVecStrategy[<T>][where T : Strategy, T::Value : Ord](
+        DistinctVecStrategy<T, ByOrd>)
+        -> UniqueVecValueTree<T::Tree>;
+    /// `ValueTree` corresponding to `UniqueVecStrategy`.
+    #[derive(Clone, Debug)]
+    pub struct UniqueVecValueTree[<T>][where T : ValueTree, T::Value : Ord](
+        DistinctVecValueTree<T, ByOrd>)
+        -> Vec<T::Value>;
+}
+
+impl<T: Enumerable> UniqueVecStrategy<T>
+where
+    T::Value: Ord,
+{
+    /// Create the elements at distinct indices of the domain of `element`,
+    /// chosen uniformly at random, instead of drawing elements until they
+    /// are distinct.
+    ///
+    /// This samples the domain without replacement, so even a `Vec` of every
+    /// value in the domain is generated without drawing a single duplicate.
+    /// Each value of the domain is equally likely to be chosen, regardless of
+    /// any weights in `element`. If the domain is too large to count, this
+    /// has no effect.
+    ///
+    /// Domains of the `Enumerable` strategies of this crate, such as ranges
+    /// of integers, `bool`, `Just` and `sample::select()`, are already
+    /// sampled this way by default when they have at most four times as many
+    /// values as the maximum size; this forces it for larger domains and for
+    /// other `Enumerable` strategies.
+    pub fn sample_domain(mut self) -> Self {
+        self.0.sample_domain();
+        self
+    }
+}
+
+/// Create a strategy to generate `Vec`s containing distinct elements drawn
+/// from `element` and with a size range given by `size`.
+///
+/// Elements which duplicate one already in the `Vec` are drawn again rather
+/// than rejecting the whole `Vec`, and shrinking never turns one element into
+/// a duplicate of another. If `element` has too few distinct values to fill
+/// the chosen size, the `Vec` is accepted with fewer elements; only when it
+/// cannot reach the minimum size does this strategy do local rejects.
+///
+/// If `element` has a small domain, as described in
+/// `UniqueVecStrategy::sample_domain()`, the elements are instead created at
+/// distinct indices of that domain, without regard to any weights.
+///
+/// ```
+/// use proptest::collection::unique_vec;
+/// use proptest::strategy::{Strategy, ValueTree};
+/// use proptest::test_runner::TestRunner;
+///
+/// let mut runner = TestRunner::deterministic();
+/// let v = unique_vec(0..10u8, 10)
+///     .new_tree(&mut runner)
+///     .unwrap()
+///     .current();
+/// let mut sorted = v.clone();
+/// sorted.sort();
+/// assert_eq!((0..10).collect::<Vec<_>>(), sorted);
+/// ```
+pub fn unique_vec<T: Strategy>(
+    element: T,
+    size: impl Into<SizeRange>,
+) -> UniqueVecStrategy<T>
+where
+    T::Value: Ord,
+{
+    UniqueVecStrategy(
+        DistinctVecStrategy::new(
+            element,
+            size,
+            ByOrd,
+            "unique_vec minimum size",
+        )
+        .sample_small_domain(),
+    )
+}
+
+/// Strategy to create `Vec`s of elements with unique keys and a length in a
+/// certain range.
+///
+/// Created by the `unique_by_key()` function in the same module.
+#[must_use = "strategies do nothing unless used"]
+#[derive(Clone)]
+pub struct UniqueByKeyStrategy<T: Strategy, F>(
+    DistinctVecStrategy<T, ByKey<F>>,
+);
+
+impl<T: Enumerable, F> UniqueByKeyStrategy<T, F> {
+    /// Create the elements at distinct indices of the domain of `element`,
+    /// as with `UniqueVecStrategy::sample_domain()`.
+    pub fn sample_domain(mut self) -> Self {
+        self.0.sample_domain();
+        self
+    }
+}
+
+impl<T: Strategy + fmt::Debug, F> fmt::Debug for UniqueByKeyStrategy<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("UniqueByKeyStrategy").field(&self.0).finish()
+    }
+}
+
+/// `ValueTree` corresponding to `UniqueByKeyStrategy`.
+#[derive(Clone)]
+pub struct UniqueByKeyValueTree<T: ValueTree, F>(
+    DistinctVecValueTree<T, ByKey<F>>,
+);
+
+impl<T: ValueTree + fmt::Debug, F> fmt::Debug for UniqueByKeyValueTree<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("UniqueByKeyValueTree")
+            .field(&self.0)
+            .finish()
+    }
+}
+
+impl<T: Strategy, K: Ord, F: Fn(&T::Value) -> K> Strategy
+    for UniqueByKeyStrategy<T, F>
+{
+    type Tree = UniqueByKeyValueTree<T::Tree, F>;
+    type Value = Vec<T::Value>;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        self.0.new_tree(runner).map(UniqueByKeyValueTree)
+    }
+}
+
+impl<T: ValueTree, K: Ord, F: Fn(&T::Value) -> K> ValueTree
+    for UniqueByKeyValueTree<T, F>
+{
+    type Value = Vec<T::Value>;
+
+    delegate_vt_0!();
+}
+
+/// Create a strategy to generate `Vec`s containing elements drawn from
+/// `element` such that no two elements have the same key according to `key`,
+/// and with a size range given by `size`.
+///
+/// This behaves like `unique_vec()`, but only requires the keys to be
+/// distinct, which is useful for generating e.g. records with unique ids.
+///
+/// ```
+/// use proptest::collection::unique_by_key;
+/// use proptest::prelude::*;
+///
+/// proptest! {
+///     fn ids_are_unique(
+///         users in unique_by_key((0..5u32, "[a-z]+"), 1..=5, |u| u.0)
+///     ) {
+///         let mut ids: Vec<_> = users.iter().map(|u| u.0).collect();
+///         ids.sort();
+///         ids.dedup();
+///         prop_assert_eq!(users.len(), ids.len());
+///     }
+/// }
+/// #
+/// # fn main() { ids_are_unique(); }
+/// ```
+pub fn unique_by_key<T: Strategy, K: Ord, F: Fn(&T::Value) -> K>(
+    element: T,
+    size: impl Into<SizeRange>,
+    key: F,
+) -> UniqueByKeyStrategy<T, F> {
+    UniqueByKeyStrategy(
+        DistinctVecStrategy::new(
+            element,
+            size,
+            ByKey(Arc::new(key)),
+            "unique_by_key minimum size",
+        )
+        .sample_small_domain(),
+    )
+}
+
+mapfn! {
+    {#[cfg(feature = "std")]}
+    [] fn VecToHashSet[<T : fmt::Debug + Hash + Eq>](vec: Vec<T>)
+                                                     -> HashSet<T> {
+        vec.into_iter().collect()
+    }
+}
+
+opaque_strategy_wrapper! {
+    {#[cfg(feature = "std")]}
+    /// Strategy to create `HashSet`s with a length in a certain range.
+    ///
+    /// Created by the `hash_set()` function in the same module.
+    #[derive(Clone, Debug)]
+    pub struct HashSetStrategy[<T>][where T : Strategy, T::Value : Hash + Eq](
+        statics::Map<DistinctVecStrategy<T, ByHash>, VecToHashSet>)
+        -> HashSetValueTree<T::Tree>;
+    /// `ValueTree` corresponding to `HashSetStrategy`.
+    #[derive(Clone, Debug)]
+    pub struct HashSetValueTree[<T>][where T : ValueTree, T::Value : Hash + Eq](
+        statics::Map<DistinctVecValueTree<T, ByHash>, VecToHashSet>)
+        -> HashSet<T::Value>;
+}
+
+#[cfg(feature = "std")]
+impl<T: Enumerable> HashSetStrategy<T>
+where
+    T::Value: Hash + Eq,
+{
+    /// Create the elements at distinct indices of the domain of `element`,
+    /// as with `UniqueVecStrategy::sample_domain()`.
+    pub fn sample_domain(mut self) -> Self {
+        self.0.source_mut().sample_domain();
+        self
+    }
+}
+
+/// Create a strategy to generate `HashSet`s containing elements drawn from
+/// `element` and with a size range given by `size`.
+///
+/// Duplicate values are drawn again rather than being dropped, and shrinking
+/// never merges two elements. If `element` has too few distinct values to
+/// fill the chosen size, the `HashSet` is generated with fewer elements; only
+/// when it cannot reach the minimum size does this strategy do local rejects.
+/// Small domains are sampled without replacement, as with `unique_vec()`.
+#[cfg(feature = "std")]
+pub fn hash_set<T: Strategy>(
+    element: T,
+    size: impl Into<SizeRange>,
+) -> HashSetStrategy<T>
+where
+    T::Value: Hash + Eq,
+{
+    HashSetStrategy(statics::Map::new(
+        DistinctVecStrategy::new(element, size, ByHash, "HashSet minimum size")
+            .sample_small_domain(),
+        VecToHashSet,
+    ))
+}
+
+mapfn! {
+    [] fn VecToBTreeSet[<T : fmt::Debug + Ord>](vec: Vec<T>)
+                                                -> BTreeSet<T> {
+        vec.into_iter().collect()
+    }
+}
+
+opaque_strategy_wrapper! {
+    /// Strategy to create `BTreeSet`s with a length in a certain range.
+    ///
+    /// Created by the `btree_set()` function in the same module.
+    #[derive(Clone, Debug)]
+    pub struct BTreeSetStrategy[<T>][where T : Strategy, T::Value : Ord](
+        statics::Map<DistinctVecStrategy<T, ByOrd>, VecToBTreeSet>)
+        -> BTreeSetValueTree<T::Tree>;
+    /// `ValueTree` corresponding to `BTreeSetStrategy`.
+    #[derive(Clone, Debug)]
+    pub struct BTreeSetValueTree[<T>][where T : ValueTree, T::Value : Ord](
+        statics::Map<DistinctVecValueTree<T, ByOrd>, VecToBTreeSet>)
+        -> BTreeSet<T::Value>;
+}
+
+impl<T: Enumerable> BTreeSetStrategy<T>
+where
+    T::Value: Ord,
+{
+    /// Create the elements at distinct indices of the domain of `element`,
+    /// as with `UniqueVecStrategy::sample_domain()`.
+    ///
+    /// ```
+    /// use proptest::collection::btree_set;
+    /// use proptest::strategy::{Strategy, ValueTree};
+    /// use proptest::test_runner::TestRunner;
+    ///
+    /// let mut runner = TestRunner::deterministic();
+    /// let set = btree_set(0..100u8, 100)
+    ///     .sample_domain()
+    ///     .new_tree(&mut runner)
+    ///     .unwrap()
+    ///     .current();
+    /// assert_eq!(100, set.len());
+    /// ```
+    pub fn sample_domain(mut self) -> Self {
+        self.0.source_mut().sample_domain();
+        self
+    }
+}
+
+/// Create a strategy to generate `BTreeSet`s containing elements drawn from
+/// `element` and with a size range given by `size`.
+///
+/// Duplicate values are drawn again rather than being dropped, and shrinking
+/// never merges two elements. If `element` has too few distinct values to
+/// fill the chosen size, the `BTreeSet` is generated with fewer elements; only
+/// when it cannot reach the minimum size does this strategy do local rejects.
+/// Small domains are sampled without replacement, as with `unique_vec()`.
+pub fn btree_set<T: Strategy>(
+    element: T,
+    size: impl Into<SizeRange>,
+) -> BTreeSetStrategy<T>
+where
+    T::Value: Ord,
+{
+    BTreeSetStrategy(statics::Map::new(
+        DistinctVecStrategy::new(element, size, ByOrd, "BTreeSet minimum size")
+            .sample_small_domain(),
+        VecToBTreeSet,
+    ))
+}
+
+mapfn! {
+    {#[cfg(feature = "std")]}
+    [] fn VecToHashMap[<K : fmt::Debug + Hash + Eq, V : fmt::Debug>]
+        (vec: Vec<(K, V)>) -> HashMap<K, V>
+    {
+        vec.into_iter().collect()
+    }
+}
+
+opaque_strategy_wrapper! {
+    {#[cfg(feature = "std")]}
+    /// Strategy to create `HashMap`s with a length in a certain range.
+    ///
+    /// Created by the `hash_map()` function in the same module.
+    #[derive(Clone, Debug)]
+    pub struct HashMapStrategy[<K, V>]
+        [where K : Strategy, V : Strategy, K::Value : Hash + Eq](
+            statics::Map<DistinctVecStrategy<(K, V), KeysByHash>, VecToHashMap>)
+        -> HashMapValueTree<K::Tree, V::Tree>;
+    /// `ValueTree` corresponding to `HashMapStrategy`.
+    #[derive(Clone, Debug)]
+    pub struct HashMapValueTree[<K, V>]
+        [where K : ValueTree, V : ValueTree, K::Value : Hash + Eq](
+            statics::Map<
+                DistinctVecValueTree<TupleValueTree<(K, V)>, KeysByHash>,
+                VecToHashMap>)
+        -> HashMap<K::Value, V::Value>;
+}
+
+#[cfg(feature = "std")]
+impl<K: Enumerable, V: Strategy> HashMapStrategy<K, V>
+where
+    K::Value: Hash + Eq,
+{
+    /// Create the keys at distinct indices of the domain of `key`, as with
+    /// `UniqueVecStrategy::sample_domain()`. Values are drawn as usual.
+    pub fn sample_domain(mut self) -> Self {
+        self.0.source_mut().sample_keys();
+        self
+    }
+}
+
+/// Create a strategy to generate `HashMap`s containing keys and values drawn
+/// from `key` and `value` respectively, and with a size within the given
+/// range.
+///
+/// Duplicate keys are drawn again rather than being dropped, and shrinking
+/// never merges two entries. If `key` has too few distinct keys to fill the
+/// chosen size, the `HashMap` is generated with fewer entries; only when it
+/// cannot reach the minimum size does this strategy do local rejects. Small
+/// key domains are sampled without replacement, as with `unique_vec()`.
+#[cfg(feature = "std")]
+pub fn hash_map<K: Strategy, V: Strategy>(
+    key: K,
+    value: V,
+    size: impl Into<SizeRange>,
+) -> HashMapStrategy<K, V>
+where
+    K::Value: Hash + Eq,
+{
+    HashMapStrategy(statics::Map::new(
+        DistinctVecStrategy::new(
+            (key, value),
+            size,
+            KeysByHash,
+            "HashMap minimum size",
+        )
+        .sample_small_key_domain(),
+        VecToHashMap,
+    ))
+}
+
+mapfn! {
+    [] fn VecToBTreeMap[<K : fmt::Debug + Ord, V : fmt::Debug>]
+        (vec: Vec<(K, V)>) -> BTreeMap<K, V>
+    {
+        vec.into_iter().collect()
+    }
+}
+
+opaque_strategy_wrapper! {
+    /// Strategy to create `BTreeMap`s with a length in a certain range.
+    ///
+    /// Created by the `btree_map()` function in the same module.
+    #[derive(Clone, Debug)]
+    pub struct BTreeMapStrategy[<K, V>]
+        [where K : Strategy, V : Strategy, K::Value : Ord](
+            statics::Map<DistinctVecStrategy<(K, V), KeysByOrd>, VecToBTreeMap>)
+        -> BTreeMapValueTree<K::Tree, V::Tree>;
+    /// `ValueTree` corresponding to `BTreeMapStrategy`.
+    #[derive(Clone, Debug)]
+    pub struct BTreeMapValueTree[<K, V>]
+        [where K : ValueTree, V : ValueTree, K::Value : Ord](
+            statics::Map<
+                DistinctVecValueTree<TupleValueTree<(K, V)>, KeysByOrd>,
+                VecToBTreeMap>)
+        -> BTreeMap<K::Value, V::Value>;
+}
+
+impl<K: Enumerable, V: Strategy> BTreeMapStrategy<K, V>
+where
+    K::Value: Ord,
+{
+    /// Create the keys at distinct indices of the domain of `key`, as with
+    /// `UniqueVecStrategy::sample_domain()`. Values are drawn as usual.
+    pub fn sample_domain(mut self) -> Self {
+        self.0.source_mut().sample_keys();
+        self
+    }
+}
+
+/// Create a strategy to generate `BTreeMap`s containing keys and values drawn
+/// from `key` and `value` respectively, and with a size within the given
+/// range.
+///
+/// Duplicate keys are drawn again rather than being dropped, and shrinking
+/// never merges two entries. If `key` has too few distinct keys to fill the
+/// chosen size, the `BTreeMap` is generated with fewer entries; only when it
+/// cannot reach the minimum size does this strategy do local rejects. Small
+/// key domains are sampled without replacement, as with `unique_vec()`.
+pub fn btree_map<K: Strategy, V: Strategy>(
+    key: K,
+    value: V,
+    size: impl Into<SizeRange>,
+) -> BTreeMapStrategy<K, V>
+where
+    K::Value: Ord,
+{
+    BTreeMapStrategy(statics::Map::new(
+        DistinctVecStrategy::new(
+            (key, value),
+            size,
+            KeysByOrd,
+            "BTreeMap minimum size",
+        )
+        .sample_small_key_domain(),
+        VecToBTreeMap,
+    ))
+}
+
+//==============================================================================
+// Sorted and sum-constrained sequences
+//==============================================================================
+
+#[derive(Clone, Copy, Debug)]
+struct IsSorted;
+
+impl<T: Ord> statics::FilterFn<Vec<T>> for IsSorted {
+    fn apply(&self, vec: &Vec<T>) -> bool {
+        vec.windows(2).all(|pair| pair[0] <= pair[1])
+    }
+}
+
+/// Strategy wrapping `VecStrategy` which sorts the elements it generates.
+#[derive(Clone, Debug)]
+struct SortedVec<T: Strategy>(VecStrategy<T>);
+
+impl<T: Strategy> Strategy for SortedVec<T>
+where
+    T::Value: Ord,
+{
+    type Tree = statics::Filter<VecValueTree<T::Tree>, IsSorted>;
+    type Value = Vec<T::Value>;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let mut tree = self.0.new_tree(runner)?;
+        tree.elements.sort_by_cached_key(ValueTree::current);
+        Ok(statics::Filter::new(tree, "unused".into(), IsSorted))
+    }
+}
+
+opaque_strategy_wrapper! {
+    /// Strategy to create sorted `Vec`s with a length in a certain range.
+    ///
+    /// Created by the `sorted_vec()` function in the same module.
+    #[derive(Clone, Debug)]
+    pub struct SortedVecStrategy[<T>][where T : Strategy, T::Value : Ord](
+        SortedVec<T>)
+        -> SortedVecValueTree<T::Tree>;
+    /// `ValueTree` corresponding to `SortedVecStrategy`.
+    #[derive(Clone, Debug)]
+    pub struct SortedVecValueTree[<T>][where T : ValueTree, T::Value : Ord](
+        statics::Filter<VecValueTree<T>, IsSorted>)
+        -> Vec<T::Value>;
+}
+
+/// Create a strategy to generate `Vec`s of non-decreasing elements drawn from
+/// `element` and with a size range given by `size`.
+///
+/// The elements are sorted when the `Vec` is generated rather than every time
+/// a value is produced, so each element keeps its position while shrinking.
+/// Elements are shrunk starting with the smallest, and a simplification which
+/// would move an element below its predecessor is undone, so the `Vec` stays
+/// sorted throughout.
+pub fn sorted_vec<T: Strategy>(
+    element: T,
+    size: impl Into<SizeRange>,
+) -> SortedVecStrategy<T>
+where
+    T::Value: Ord,
+{
+    SortedVecStrategy(SortedVec(vec(element, size)))
+}
+
+/// Integer types which can be generated by `strictly_increasing()` and
+/// `partition()`.
+///
+/// This trait is implemented for all primitive integer types up to 64 bits
+/// wide, and cannot be implemented outside this crate.
+pub trait SequenceInteger: Copy + Ord + fmt::Debug {
+    #[doc(hidden)]
+    fn to_i128(self) -> i128;
+    #[doc(hidden)]
+    fn from_i128(value: i128) -> Self;
+}
+
+macro_rules! sequence_integer {
+    ($($typ:ty),*) => { $(
+        impl SequenceInteger for $typ {
+            fn to_i128(self) -> i128 {
+                self as i128
+            }
+
+            fn from_i128(value: i128) -> Self {
+                value as $typ
+            }
+        }
+    )* };
+}
+
+sequence_integer!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+/// Choose `n` distinct offsets in `0..width`, in increasing order, using
+/// Floyd's algorithm so that `width` may be much larger than `n`.
+fn sample_offsets(runner: &mut TestRunner, width: u64, n: usize) -> Vec<i128> {
+    let mut chosen = BTreeSet::new();
+    for j in width - n as u64..width {
+        let offset = runner.rng().gen_range(0..=j);
+        if !chosen.insert(offset) {
+            chosen.insert(j);
+        }
+    }
+    chosen.into_iter().map(i128::from).collect()
+}
+
+/// Binary search for one element of a sequence, between a lower bound and the
+/// value the element had when the search started.
+#[derive(Clone, Copy, Debug)]
+struct ElementSearch {
+    ix: usize,
+    lower: i128,
+    offset: crate::num::u64::BinarySearch,
+}
+
+impl ElementSearch {
+    fn new(ix: usize, lower: i128, value: i128) -> Self {
+        ElementSearch {
+            ix,
+            lower,
+            offset: crate::num::u64::BinarySearch::new((value - lower) as u64),
+        }
+    }
+
+    fn current(&self) -> i128 {
+        self.lower + i128::from(self.offset.current())
+    }
+}
+
+/// Strategy to create `Vec`s of strictly increasing integers.
+///
+/// Created by the `strictly_increasing()` function in the same module.
+#[must_use = "strategies do nothing unless used"]
+#[derive(Clone, Debug)]
+pub struct StrictlyIncreasingStrategy<T> {
+    start: T,
+    width: u64,
+    size: SizeRange,
+}
+
+/// `ValueTree` corresponding to `StrictlyIncreasingStrategy`.
+#[derive(Clone, Debug)]
+pub struct StrictlyIncreasingValueTree<T> {
+    start: T,
+    values: Vec<i128>,
+    included: VarBitSet,
+    min_size: usize,
+    shrink: Shrink,
+    prev_shrink: Option<Shrink>,
+    /// The indices of the elements deleted by the last `DeleteChunk`.
+    deleted: Vec<usize>,
+    search: Option<ElementSearch>,
+}
+
+/// Create a strategy to generate `Vec`s of strictly increasing integers drawn
+/// from `range`, with a length given by `len`.
+///
+/// The values are drawn without replacement, so `range` only needs to be as
+/// wide as the maximum length. Shrinking deletes values and moves each
+/// remaining value down towards its predecessor, or towards the start of
+/// `range` for the first value, without reordering them.
+///
+/// ## Panics
+///
+/// Panics if `range` is narrower than the minimum length.
+///
+/// ```
+/// use proptest::collection::strictly_increasing;
+/// use proptest::prelude::*;
+///
+/// proptest! {
+///     fn timestamps_increase(
+///         ts in strictly_increasing(1_000_000..2_000_000u64, 1..100)
+///     ) {
+///         prop_assert!(ts.windows(2).all(|w| w[0] < w[1]));
+///     }
+/// }
+/// #
+/// # fn main() { timestamps_increase(); }
+/// ```
+pub fn strictly_increasing<T: SequenceInteger>(
+    range: Range<T>,
+    len: impl Into<SizeRange>,
+) -> StrictlyIncreasingStrategy<T> {
+    let size = len.into();
+    size.assert_nonempty();
+    let width = cmp::max(0, range.end.to_i128() - range.start.to_i128());
+    assert!(
+        width >= size.start() as i128,
+        "Range {:?} has fewer than {} values",
+        range,
+        size.start()
+    );
+
+    StrictlyIncreasingStrategy {
+        start: range.start,
+        width: width as u64,
+        size,
+    }
+}
+
+impl<T: SequenceInteger> Strategy for StrictlyIncreasingStrategy<T> {
+    type Tree = StrictlyIncreasingValueTree<T>;
+    type Value = Vec<T>;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let len = cmp::min(self.size.sample(runner) as u64, self.width);
+        let start = self.start.to_i128();
+        let values = sample_offsets(runner, self.width, len as usize)
+            .into_iter()
+            .map(|offset| start + offset)
+            .collect::<Vec<_>>();
+
+        let len = values.len();
+        Ok(StrictlyIncreasingValueTree {
+            start: self.start,
+            values,
+            included: VarBitSet::saturated(len),
+            min_size: self.size.start(),
+            shrink: Shrink::delete_halves(len),
+            prev_shrink: None,
+            deleted: Vec::new(),
+            search: None,
+        })
+    }
+}
+
+impl<T: SequenceInteger> StrictlyIncreasingValueTree<T> {
+    /// The lowest value the element at `ix` can take while staying above the
+    /// included element before it.
+    fn lower_bound(&self, ix: usize) -> i128 {
+        (0..ix)
+            .rev()
+            .find(|&prev| self.included.test(prev))
+            .map(|prev| self.values[prev] + 1)
+            .unwrap_or_else(|| self.start.to_i128())
+    }
+}
+
+impl<T: SequenceInteger> ValueTree for StrictlyIncreasingValueTree<T> {
+    type Value = Vec<T>;
+
+    fn current(&self) -> Vec<T> {
+        self.values
+            .iter()
+            .enumerate()
+            .filter(|&(ix, _)| self.included.test(ix))
+            .map(|(_, &value)| T::from_i128(value))
+            .collect()
+    }
+
+    fn simplify(&mut self) -> bool {
+        // Delete elements the same way as `VecValueTree`; since the remaining
+        // elements keep their values, they stay strictly increasing.
+        while let Shrink::DeleteChunk { start, len } = self.shrink {
+            if self.included.count() == self.min_size {
+                self.shrink = Shrink::ShrinkElement(0);
+                break;
+            }
+
+            if start >= self.values.len() {
+                self.shrink = if len > 1 {
+                    Shrink::delete_halves(len)
+                } else {
+                    Shrink::ShrinkElement(0)
+                };
+                continue;
+            }
+
+            let end = cmp::min(start + len, self.values.len());
+            self.shrink = Shrink::DeleteChunk { start: end, len };
+
+            let included =
+                (start..end).filter(|&ix| self.included.test(ix)).count();
+            if 0 == included || self.included.count() - included < self.min_size
+            {
+                continue;
+            }
+
+            self.deleted.clear();
+            for ix in start..end {
+                if self.included.test(ix) {
+                    self.included.clear(ix);
+                    self.deleted.push(ix);
+                }
+            }
+            self.prev_shrink = Some(Shrink::DeleteChunk { start, len });
+            return true;
+        }
+
+        // Then move each element down towards its predecessor. Elements after
+        // it are not affected, since they are already above its current
+        // value.
+        while let Shrink::ShrinkElement(ix) = self.shrink {
+            if ix >= self.values.len() {
+                return false;
+            }
+
+            if !self.included.test(ix) {
+                self.shrink = Shrink::ShrinkElement(ix + 1);
+                continue;
+            }
+
+            let mut search = match self.search {
+                Some(search) if search.ix == ix => search,
+                _ => ElementSearch::new(
+                    ix,
+                    self.lower_bound(ix),
+                    self.values[ix],
+                ),
+            };
+            if search.offset.simplify() {
+                self.values[ix] = search.current();
+                self.search = Some(search);
+                self.prev_shrink = Some(self.shrink);
+                return true;
+            }
+
+            self.search = None;
+            self.shrink = Shrink::ShrinkElement(ix + 1);
+        }
+
+        panic!("Unexpected shrink state");
+    }
+
+    fn complicate(&mut self) -> bool {
+        match self.prev_shrink {
+            None => false,
+            Some(Shrink::DeleteChunk { .. }) => {
+                for &ix in &self.deleted {
+                    self.included.set(ix);
+                }
+                self.prev_shrink = None;
+                true
+            }
+            Some(Shrink::ShrinkElement(_)) => {
+                if let Some(ref mut search) = self.search {
+                    if search.offset.complicate() {
+                        self.values[search.ix] = search.current();
+                        return true;
+                    }
+                }
+
+                self.prev_shrink = None;
+                false
+            }
+        }
+    }
+}
+
+/// Strategy to create `Vec`s of non-negative integers with a fixed sum.
+///
+/// Created by the `partition()` function in the same module.
+#[must_use = "strategies do nothing unless used"]
+#[derive(Clone, Debug)]
+pub struct PartitionStrategy<T> {
+    total: T,
+    parts: usize,
+}
+
+/// `ValueTree` corresponding to `PartitionStrategy`.
+#[derive(Clone, Debug)]
+pub struct PartitionValueTree<T> {
+    total: T,
+    values: Vec<i128>,
+    /// The next part to shrink.
+    next: usize,
+    search: Option<ElementSearch>,
+}
+
+/// Create a strategy to generate `Vec`s of `parts` non-negative integers
+/// which sum to exactly `total`.
+///
+/// Every way of splitting `total` into `parts` is equally likely. Shrinking
+/// moves weight from each part in turn into the last part, so the simplest
+/// value is `parts - 1` zeroes followed by `total`.
+///
+/// ## Panics
+///
+/// Panics if `total` is negative, if `parts` is zero while `total` is not,
+/// or if `total + parts` does not fit in a `u64`.
+///
+/// ```
+/// use proptest::collection::partition;
+/// use proptest::prelude::*;
+///
+/// proptest! {
+///     fn weights_sum_to_100(weights in partition(100u32, 4)) {
+///         prop_assert_eq!(4, weights.len());
+///         prop_assert_eq!(100, weights.iter().sum::<u32>());
+///     }
+/// }
+/// #
+/// # fn main() { weights_sum_to_100(); }
+/// ```
+pub fn partition<T: SequenceInteger>(
+    total: T,
+    parts: usize,
+) -> PartitionStrategy<T> {
+    let sum = total.to_i128();
+    assert!(sum >= 0, "Cannot partition negative total {:?}", total);
+    assert!(
+        parts > 0 || 0 == sum,
+        "Cannot partition {:?} into zero parts",
+        total
+    );
+    assert!(
+        sum + parts as i128 <= i128::from(u64::MAX),
+        "Cannot partition {:?} into {} parts",
+        total,
+        parts
+    );
+
+    PartitionStrategy { total, parts }
+}
+
+impl<T: SequenceInteger> Strategy for PartitionStrategy<T> {
+    type Tree = PartitionValueTree<T>;
+    type Value = Vec<T>;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let total = self.total.to_i128();
+        let values = if 0 == self.parts {
+            Vec::new()
+        } else {
+            // Place `parts - 1` bars among `total` stars; each part is the
+            // number of stars between two bars.
+            let bars = self.parts - 1;
+            let width = total + bars as i128;
+            let mut prev = -1;
+            let mut values = Vec::with_capacity(self.parts);
+            for bar in sample_offsets(runner, width as u64, bars) {
+                values.push(bar - prev - 1);
+                prev = bar;
+            }
+            values.push(width - prev - 1);
+            values
+        };
+
+        Ok(PartitionValueTree {
+            total: self.total,
+            values,
+            next: 0,
+            search: None,
+        })
+    }
+}
+
+impl<T: SequenceInteger> PartitionValueTree<T> {
+    /// Set the part being searched to its current value, balancing the sum
+    /// with the last part.
+    fn apply_search(&mut self) {
+        if let Some(search) = self.search {
+            let last = self.values.len() - 1;
+            self.values[search.ix] = search.current();
+            self.values[last] =
+                self.total.to_i128() - self.values[..last].iter().sum::<i128>();
+        }
+    }
+}
+
+impl<T: SequenceInteger> ValueTree for PartitionValueTree<T> {
+    type Value = Vec<T>;
+
+    fn current(&self) -> Vec<T> {
+        self.values
+            .iter()
+            .map(|&value| T::from_i128(value))
+            .collect()
+    }
+
+    fn simplify(&mut self) -> bool {
+        while self.next + 1 < self.values.len() {
+            let ix = self.next;
+            let mut search = match self.search {
+                Some(search) if search.ix == ix => search,
+                _ => ElementSearch::new(ix, 0, self.values[ix]),
+            };
+            if search.offset.simplify() {
+                self.search = Some(search);
+                self.apply_search();
+                return true;
+            }
+
+            self.search = None;
+            self.next += 1;
+        }
+
+        false
+    }
+
+    fn complicate(&mut self) -> bool {
+        let complicated = match self.search {
+            Some(ref mut search) => search.offset.complicate(),
+            None => false,
+        };
+        if complicated {
+            self.apply_search();
+        }
+        complicated
+    }
+}
+
+//==============================================================================
+// Equal-length and shape-dependent collections
+//==============================================================================
+
+/// Tuples which `vecs_same_len()` can split a `Vec` of into a tuple of
+/// `Vec`s.
+///
+/// This is implemented for tuples of up to 12 elements.
+pub trait UnzipVec: Sized {
+    /// The tuple of `Vec`s, one for each component of `Self`.
+    type Vecs: fmt::Debug;
+
+    /// Split `vec` into one `Vec` per component, keeping the order of the
+    /// elements.
+    fn unzip_vec(vec: Vec<Self>) -> Self::Vecs;
+}
+
+macro_rules! unzip_vec {
+    ($($fld:tt : $typ:ident),*) => {
+        impl<$($typ : fmt::Debug),*> UnzipVec for ($($typ,)*) {
+            type Vecs = ($(Vec<$typ>,)*);
+
+            fn unzip_vec(vec: Vec<Self>) -> Self::Vecs {
+                let mut vecs = ($(Vec::<$typ>::with_capacity(vec.len()),)*);
+                for element in vec {
+                    $(vecs.$fld.push(element.$fld);)*
+                }
+                vecs
+            }
+        }
+    };
+}
+
+unzip_vec!(0: A);
+unzip_vec!(0: A, 1: B);
+unzip_vec!(0: A, 1: B, 2: C);
+unzip_vec!(0: A, 1: B, 2: C, 3: D);
+unzip_vec!(0: A, 1: B, 2: C, 3: D, 4: E);
+unzip_vec!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F);
+unzip_vec!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G);
+unzip_vec!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H);
+unzip_vec!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I);
+unzip_vec!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I, 9: J);
+unzip_vec!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I, 9: J, 10: K);
+unzip_vec!(
+    0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I, 9: J, 10: K, 11: L
+);
+
+mapfn! {
+    [] fn Unzipped[<T : UnzipVec>](vec: Vec<T>) -> T::Vecs {
+        T::unzip_vec(vec)
+    }
+}
+
+opaque_strategy_wrapper! {
+    /// Strategy to create tuples of `Vec`s which all have the same length.
+    ///
+    /// Created by the `vecs_same_len()` function in the same module.
+    #[derive(Clone, Debug)]
+    pub struct VecsSameLenStrategy[<T>]
+        [where T : Strategy, T::Value : UnzipVec](
+        statics::Map<VecStrategy<T>, Unzipped>)
+        -> VecsSameLenValueTree<T::Tree>;
+    /// `ValueTree` corresponding to `VecsSameLenStrategy`.
+    #[derive(Clone, Debug)]
+    pub struct VecsSameLenValueTree[<T>]
+        [where T : ValueTree, T::Value : UnzipVec](
+        statics::Map<VecValueTree<T>, Unzipped>)
+        -> <T::Value as UnzipVec>::Vecs;
+}
+
+/// Create a strategy to generate tuples of `Vec`s which all have the same
+/// length, within the range given by `size`, and whose elements are drawn
+/// from the corresponding strategies in the tuple `elements`.
+///
+/// This is equivalent to splitting a `vec()` of tuples, so shrinking deletes
+/// the element at the same index from every `Vec` at once, without needing
+/// `prop_flat_map` over the length.
+///
+/// ```
+/// use proptest::collection::vecs_same_len;
+/// use proptest::prelude::*;
+///
+/// proptest! {
+///     fn zip_keeps_everything(
+///         (keys, values) in vecs_same_len((0..100u32, ".*"), 0..10)
+///     ) {
+///         prop_assert_eq!(keys.len(), values.len());
+///         prop_assert_eq!(keys.len(), keys.iter().zip(&values).count());
+///     }
+/// }
+/// #
+/// # fn main() { zip_keeps_everything(); }
+/// ```
+pub fn vecs_same_len<T: Strategy>(
+    elements: T,
+    size: impl Into<SizeRange>,
+) -> VecsSameLenStrategy<T>
+where
+    T::Value: UnzipVec,
+{
+    VecsSameLenStrategy(statics::Map::new(vec(elements, size), Unzipped))
+}
+
+/// Strategy to create `Vec`s of rows which all have the same length.
+///
+/// Created by the `matrix()` function in the same module.
+#[must_use = "strategies do nothing unless used"]
+#[derive(Clone, Debug)]
+pub struct MatrixStrategy<T: Strategy> {
+    element: T,
+    rows: SizeRange,
+    cols: SizeRange,
+}
+
+/// `ValueTree` corresponding to `MatrixStrategy`.
+#[derive(Clone, Debug)]
+pub struct MatrixValueTree<T: ValueTree> {
+    /// The elements of all rows, including deleted rows and columns.
+    elements: Vec<Vec<T>>,
+    rows: Dimension,
+    cols: Dimension,
+    shrink: MatrixShrink,
+    prev_shrink: Option<MatrixShrink>,
+}
+
+/// The rows or columns of a `MatrixValueTree`, or any other sequence of
+/// items some of which may have been deleted.
+#[derive(Clone, Debug)]
+pub(crate) struct Dimension {
+    pub(crate) included: VarBitSet,
+    len: usize,
+    min_size: usize,
+    /// The indices deleted by the last chunk deletion in this dimension.
+    deleted: Vec<usize>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum MatrixShrink {
+    /// Delete chunks of rows, in the same way as `Shrink::DeleteChunk`.
+    DeleteRows { start: usize, len: usize },
+    /// Delete chunks of columns from every row at once.
+    DeleteCols { start: usize, len: usize },
+    /// Shrink the element at the given row and column.
+    ShrinkElement(usize, usize),
+}
+
+impl Dimension {
+    pub(crate) fn new(len: usize, min_size: usize) -> Self {
+        Dimension {
+            included: VarBitSet::saturated(len),
+            len,
+            min_size,
+            deleted: Vec::new(),
+        }
+    }
+
+    /// Try to delete the next chunk of `*chunk` indices starting at `*start`,
+    /// advancing both the same way `VecValueTree` does.
+    ///
+    /// Returns whether anything was deleted. Once deleting single indices
+    /// has reached the end, or nothing more can be deleted without going
+    /// under the minimum size, returns `false` without changing anything.
+    pub(crate) fn delete_next(
+        &mut self,
+        start: &mut usize,
+        chunk: &mut usize,
+    ) -> bool {
+        loop {
+            if self.included.count() == self.min_size {
+                return false;
+            }
+
+            if *start >= self.len {
+                if *chunk > 1 {
+                    *start = 0;
+                    *chunk /= 2;
+                    continue;
+                }
+                return false;
+            }
+
+            let end = cmp::min(*start + *chunk, self.len);
+            let included =
+                (*start..end).filter(|&ix| self.included.test(ix)).count();
+            let from = *start;
+            *start = end;
+            if 0 == included || self.included.count() - included < self.min_size
+            {
+                continue;
+            }
+
+            self.deleted.clear();
+            for ix in from..end {
+                if self.included.test(ix) {
+                    self.included.clear(ix);
+                    self.deleted.push(ix);
+                }
+            }
+            return true;
+        }
+    }
+
+    /// Restore the indices deleted by the last call to `delete_next()`.
+    pub(crate) fn undelete(&mut self) {
+        for &ix in &self.deleted {
+            self.included.set(ix);
+        }
+    }
+
+    /// The first included index at or after `ix`.
+    pub(crate) fn next_included(&self, ix: usize) -> Option<usize> {
+        (ix..self.len).find(|&ix| self.included.test(ix))
+    }
+}
+
+/// Create a strategy to generate matrices, as `Vec`s of rows, with a number
+/// of rows given by `rows` and a number of columns given by `cols`. Every row
+/// has the same length, and the elements are drawn from `element`.
+///
+/// Shrinking first deletes whole rows, then deletes columns from every row at
+/// once, and finally shrinks the remaining elements, so the rows always have
+/// the same length.
+///
+/// ```
+/// use proptest::collection::matrix;
+/// use proptest::prelude::*;
+///
+/// proptest! {
+///     fn rows_have_same_len(m in matrix(0..10i32, 1..5, 0..5)) {
+///         prop_assert!(m.iter().all(|row| row.len() == m[0].len()));
+///     }
+/// }
+/// #
+/// # fn main() { rows_have_same_len(); }
+/// ```
+pub fn matrix<T: Strategy>(
+    element: T,
+    rows: impl Into<SizeRange>,
+    cols: impl Into<SizeRange>,
+) -> MatrixStrategy<T> {
+    let rows = rows.into();
+    let cols = cols.into();
+    rows.assert_nonempty();
+    cols.assert_nonempty();
+    MatrixStrategy {
+        element,
+        rows,
+        cols,
+    }
+}
+
+impl<T: Strategy> Strategy for MatrixStrategy<T> {
+    type Tree = MatrixValueTree<T::Tree>;
+    type Value = Vec<Vec<T::Value>>;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let rows = self.rows.sample(runner);
+        let cols = self.cols.sample(runner);
+        let mut elements = Vec::with_capacity(rows);
+        while elements.len() < rows {
+            let mut row = Vec::with_capacity(cols);
+            while row.len() < cols {
+                row.push(self.element.new_tree(runner)?);
+            }
+            elements.push(row);
+        }
+
+        Ok(MatrixValueTree {
+            elements,
+            rows: Dimension::new(rows, self.rows.start()),
+            cols: Dimension::new(cols, self.cols.start()),
+            shrink: MatrixShrink::DeleteRows {
+                start: 0,
+                len: cmp::max(1, rows / 2),
+            },
+            prev_shrink: None,
+        })
+    }
+}
+
+impl<T: ValueTree> ValueTree for MatrixValueTree<T> {
+    type Value = Vec<Vec<T::Value>>;
+
+    fn current(&self) -> Vec<Vec<T::Value>> {
+        self.elements
+            .iter()
+            .enumerate()
+            .filter(|&(row, _)| self.rows.included.test(row))
+            .map(|(_, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|&(col, _)| self.cols.included.test(col))
+                    .map(|(_, element)| element.current())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn simplify(&mut self) -> bool {
+        if let MatrixShrink::DeleteRows { mut start, mut len } = self.shrink {
+            let deleted = self.rows.delete_next(&mut start, &mut len);
+            if deleted {
+                self.shrink = MatrixShrink::DeleteRows { start, len };
+                self.prev_shrink = Some(self.shrink);
+                return true;
+            }
+
+            self.shrink = MatrixShrink::DeleteCols {
+                start: 0,
+                len: cmp::max(1, self.cols.len / 2),
+            };
+        }
+
+        if let MatrixShrink::DeleteCols { mut start, mut len } = self.shrink {
+            let deleted = self.cols.delete_next(&mut start, &mut len);
+            if deleted {
+                self.shrink = MatrixShrink::DeleteCols { start, len };
+                self.prev_shrink = Some(self.shrink);
+                return true;
+            }
+
+            self.shrink = MatrixShrink::ShrinkElement(0, 0);
+        }
+
+        while let MatrixShrink::ShrinkElement(row, col) = self.shrink {
+            let row = match self.rows.next_included(row) {
+                Some(row) => row,
+                None => return false,
+            };
+            let col = match self.cols.next_included(col) {
+                Some(col) => col,
+                None => {
+                    self.shrink = MatrixShrink::ShrinkElement(row + 1, 0);
+                    continue;
+                }
+            };
+
+            if self.elements[row][col].simplify() {
+                self.shrink = MatrixShrink::ShrinkElement(row, col);
+                self.prev_shrink = Some(self.shrink);
+                return true;
+            }
+
+            self.shrink = MatrixShrink::ShrinkElement(row, col + 1);
+        }
+
+        panic!("Unexpected shrink state");
+    }
+
+    fn complicate(&mut self) -> bool {
+        match self.prev_shrink {
+            None => false,
+            Some(MatrixShrink::DeleteRows { .. }) => {
+                self.rows.undelete();
+                self.prev_shrink = None;
+                true
+            }
+            Some(MatrixShrink::DeleteCols { .. }) => {
+                self.cols.undelete();
+                self.prev_shrink = None;
+                true
+            }
+            Some(MatrixShrink::ShrinkElement(row, col)) => {
+                if self.elements[row][col].complicate() {
+                    true
+                } else {
+                    self.prev_shrink = None;
+                    false
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Shrink {
+    /// Delete the elements which are still included among the `len`
+    /// elements starting at index `start`.
+    DeleteChunk {
+        start: usize,
+        len: usize,
+    },
+    ShrinkElement(usize),
+}
+
+impl Shrink {
+    /// Start deleting chunks of half of `len` elements.
+    fn delete_halves(len: usize) -> Self {
+        Shrink::DeleteChunk {
+            start: 0,
+            len: cmp::max(1, len / 2),
+        }
+    }
+}
+
+/// `ValueTree` corresponding to `VecStrategy`.
+#[derive(Clone, Debug)]
+pub struct VecValueTree<T: ValueTree> {
+    elements: Vec<T>,
+    included_elements: VarBitSet,
+    min_size: usize,
+    shrink: Shrink,
+    prev_shrink: Option<Shrink>,
+    /// The indices of the elements deleted by the last `DeleteChunk`.
+    deleted: Vec<usize>,
+}
+
+impl<T: Strategy> Strategy for VecStrategy<T> {
+    type Tree = VecValueTree<T::Tree>;
+    type Value = Vec<T::Value>;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let start = self.size.start();
+        let max_size = self.size.sample(runner);
+        let mut elements = Vec::with_capacity(max_size);
+        while elements.len() < max_size {
+            elements.push(self.element.new_tree(runner)?);
+        }
+
+        Ok(VecValueTree {
+            elements,
+            included_elements: VarBitSet::saturated(max_size),
+            min_size: start,
+            shrink: Shrink::delete_halves(max_size),
+            prev_shrink: None,
+            deleted: Vec::new(),
+        })
+    }
+}
+
+impl<T: Strategy> Strategy for Vec<T> {
+    type Tree = VecValueTree<T::Tree>;
+    type Value = Vec<T::Value>;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let len = self.len();
+        let elements = self
+            .iter()
+            .map(|t| t.new_tree(runner))
+            .collect::<Result<Vec<_>, Reason>>()?;
+
+        Ok(VecValueTree {
+            elements,
+            included_elements: VarBitSet::saturated(len),
+            min_size: len,
+            shrink: Shrink::ShrinkElement(0),
+            prev_shrink: None,
+            deleted: Vec::new(),
+        })
     }
 }
 
@@ -605,21 +2326,53 @@ impl<T: ValueTree> ValueTree for VecValueTree<T> {
         // list until we can do so no further, then to shrink each remaining
         // element in sequence.
         //
+        // Elements are deleted in the style of delta debugging: first try to
+        // delete each half of the list, then each quarter, and so on down to
+        // single elements. Long lists which only fail because of a few
+        // elements thus lose most of their elements in a few steps rather than
+        // one step per element.
+        //
         // For `complicate()`, we simply undo the last shrink operation, if
         // there was any.
-        if let Shrink::DeleteElement(ix) = self.shrink {
-            // Can't delete an element if beyond the end of the vec or if it
-            // would put us under the minimum length.
-            if ix >= self.elements.len()
-                || self.included_elements.count() == self.min_size
-            {
+        while let Shrink::DeleteChunk { start, len } = self.shrink {
+            // Can't delete anything if it would put us under the minimum
+            // length.
+            if self.included_elements.count() == self.min_size {
                 self.shrink = Shrink::ShrinkElement(0);
-            } else {
-                self.included_elements.clear(ix);
-                self.prev_shrink = Some(self.shrink);
-                self.shrink = Shrink::DeleteElement(ix + 1);
-                return true;
+                break;
             }
+
+            // Move on to smaller chunks after reaching the end of the vec.
+            if start >= self.elements.len() {
+                self.shrink = if len > 1 {
+                    Shrink::delete_halves(len)
+                } else {
+                    Shrink::ShrinkElement(0)
+                };
+                continue;
+            }
+
+            let end = cmp::min(start + len, self.elements.len());
+            self.shrink = Shrink::DeleteChunk { start: end, len };
+
+            let included = (start..end)
+                .filter(|&ix| self.included_elements.test(ix))
+                .count();
+            if 0 == included
+                || self.included_elements.count() - included < self.min_size
+            {
+                continue;
+            }
+
+            self.deleted.clear();
+            for ix in start..end {
+                if self.included_elements.test(ix) {
+                    self.included_elements.clear(ix);
+                    self.deleted.push(ix);
+                }
+            }
+            self.prev_shrink = Some(Shrink::DeleteChunk { start, len });
+            return true;
         }
 
         while let Shrink::ShrinkElement(ix) = self.shrink {
@@ -649,10 +2402,12 @@ impl<T: ValueTree> ValueTree for VecValueTree<T> {
     fn complicate(&mut self) -> bool {
         match self.prev_shrink {
             None => false,
-            Some(Shrink::DeleteElement(ix)) => {
-                // Undo the last item we deleted. Can't complicate any further,
-                // so unset prev_shrink.
-                self.included_elements.set(ix);
+            Some(Shrink::DeleteChunk { .. }) => {
+                // Undo the last items we deleted. Can't complicate any
+                // further, so unset prev_shrink.
+                for &ix in &self.deleted {
+                    self.included_elements.set(ix);
+                }
                 self.prev_shrink = None;
                 true
             }
@@ -680,6 +2435,118 @@ mod test {
     use super::*;
 
     use crate::bits;
+    #[test]
+    fn long_vec_shrinks_by_deleting_chunks() {
+        use core::cell::Cell;
+
+        fn fails(v: &[u32]) -> bool {
+            v.iter().any(|&x| x >= 990)
+        }
+
+        let mut runner = TestRunner::new(Config {
+            max_shrink_iters: 1_000_000,
+            ..Config::default()
+        });
+        let input = vec(0u32..1000, 0..=10_000);
+        let case = loop {
+            let case = input.new_tree(&mut runner).unwrap();
+            if case.current().len() >= 9000 && fails(&case.current()) {
+                break case;
+            }
+        };
+
+        // The number of calls needed to delete the elements one at a time,
+        // as collections used to shrink.
+        let mut one_at_a_time = 0;
+        let mut v = case.current();
+        let mut ix = 0;
+        while ix < v.len() {
+            let mut shorter = v.clone();
+            shorter.remove(ix);
+            one_at_a_time += 1;
+            if fails(&shorter) {
+                v = shorter;
+            } else {
+                ix += 1;
+            }
+        }
+        assert_eq!(1, v.len());
+
+        let calls = Cell::new(0);
+        let result = runner.run_one(case, |v| {
+            calls.set(calls.get() + 1);
+            prop_assert!(!fails(&v));
+            Ok(())
+        });
+
+        match result {
+            Err(TestError::Fail(_, v)) => assert_eq!(vec![990], v),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert!(
+            calls.get() * 20 < one_at_a_time,
+            "Took {} calls, against {} deleting one element at a time",
+            calls.get(),
+            one_at_a_time
+        );
+    }
+
+    #[test]
+    fn vec_size_follows_runner_size() {
+        let input = vec(0u8..10, 5..=1000);
+        let mut runner = TestRunner::deterministic();
+        for &(size, max_len) in &[(0, 5), (1, 15), (50, 503)] {
+            runner.set_size(size);
+            for _ in 0..64 {
+                let len = input.new_tree(&mut runner).unwrap().current().len();
+                assert!(len >= 5 && len <= max_len, "size {}: {}", size, len);
+            }
+        }
+    }
+
+    fn sample_lens(strategy: impl Strategy<Value = Vec<u8>>) -> Vec<usize> {
+        let mut runner = TestRunner::deterministic();
+        (0..1000)
+            .map(|_| strategy.new_tree(&mut runner).unwrap().current().len())
+            .collect()
+    }
+
+    #[test]
+    fn log_uniform_sizes_mostly_small() {
+        let size = size_range(0..=10_000)
+            .with_distribution(SizeDistribution::LogUniform);
+        let lens = sample_lens(vec(0u8..1, size));
+        assert!(lens.iter().all(|&len| len <= 10_000));
+        assert!(lens.iter().filter(|&&len| len < 127).count() > 350);
+        assert!(lens.iter().filter(|&&len| len >= 1023).count() > 150);
+        assert!(lens.iter().any(|&len| 0 == len));
+    }
+
+    #[test]
+    fn bimodal_sizes_favour_ends() {
+        let size =
+            size_range(3..=100).with_distribution(SizeDistribution::Bimodal);
+        let lens = sample_lens(vec(0u8..1, size));
+        assert!(lens.iter().all(|&len| len >= 3 && len <= 100));
+        assert!(lens.iter().filter(|&&len| 3 == len).count() > 150);
+        assert!(lens.iter().filter(|&&len| 100 == len).count() > 150);
+    }
+
+    #[test]
+    fn distribution_kept_by_arbitrary_and_transformations() {
+        use crate::arbitrary::any_with;
+
+        let size = size_range(0..=10_000)
+            .with_distribution(SizeDistribution::LogUniform);
+        assert_eq!(
+            SizeDistribution::LogUniform,
+            (size.clone() + 1).distribution()
+        );
+        assert_eq!(SizeDistribution::LogUniform, size.scaled(1).distribution());
+
+        let lens = sample_lens(any_with::<Vec<u8>>(size.lift()));
+        assert!(lens.iter().filter(|&&len| len < 127).count() > 350);
+    }
 
     #[test]
     fn test_vec() {
@@ -764,6 +2631,373 @@ mod test {
         }
     }
 
+    #[test]
+    fn unique_vec_samples_small_domain_without_replacement() {
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..256 {
+            let mut v = unique_vec(0..10u8, 10)
+                .new_tree(&mut runner)
+                .unwrap()
+                .current();
+            v.sort();
+            assert_eq!((0..10).collect::<Vec<_>>(), v);
+
+            let set = btree_set(crate::sample::select(vec!['a', 'b', 'c']), 3)
+                .new_tree(&mut runner)
+                .unwrap()
+                .current();
+            assert_eq!(3, set.len());
+        }
+    }
+
+    #[test]
+    fn small_enumerable_domains_sampled_by_default() {
+        assert!(unique_vec(0..40u8, 10).0.sample.is_some());
+        assert!(unique_vec(0..41u8, 10).0.sample.is_none());
+        assert!(unique_vec((0..10u8).prop_filter("", |_| true), 10)
+            .0
+            .sample
+            .is_none());
+        assert!(unique_vec((0..4u8, crate::bool::ANY), 8).0.sample.is_some());
+        assert!(unique_vec(prop_oneof![Just(0), 5..10u8], 6)
+            .0
+            .sample
+            .is_some());
+        assert!(unique_by_key(0..3u8, 3, |&x| x).0.sample.is_some());
+        assert!(btree_set(crate::sample::select(vec!['a', 'b']), 2)
+            .0
+            .source_mut()
+            .sample
+            .is_some());
+        assert!(btree_map(0..20u8, ".*", 20).0.source_mut().sample.is_some());
+
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..256 {
+            let set = hash_set(0..10u8, 10)
+                .new_tree(&mut runner)
+                .unwrap()
+                .current();
+            assert_eq!(10, set.len());
+        }
+    }
+
+    #[test]
+    fn sample_domain_draws_distinct_indices() {
+        let mut runner = TestRunner::deterministic();
+        let mut unsorted = false;
+        for _ in 0..256 {
+            let v = unique_vec(0..1000u16, 1000)
+                .sample_domain()
+                .new_tree(&mut runner)
+                .unwrap()
+                .current();
+            let mut sorted = v.clone();
+            sorted.sort();
+            unsorted |= sorted != v;
+            assert_eq!((0..1000).collect::<Vec<_>>(), sorted);
+
+            let map = btree_map(0..20u8, 0..1000u32, 20)
+                .sample_domain()
+                .new_tree(&mut runner)
+                .unwrap()
+                .current();
+            assert_eq!(
+                (0..20).collect::<Vec<_>>(),
+                map.into_keys().collect::<Vec<_>>()
+            );
+
+            // Mapping can send several indices to the same value.
+            let set = btree_set((0..10u8).prop_map(|x| x / 2), 0..=10)
+                .sample_domain()
+                .new_tree(&mut runner)
+                .unwrap()
+                .current();
+            assert!(set.len() <= 5, "{:?}", set);
+        }
+        assert!(unsorted);
+    }
+
+    #[test]
+    fn test_sample_domain_sanity() {
+        check_strategy_sanity(
+            btree_set(0i32..100, 5..50).sample_domain(),
+            Some(CheckStrategySanityOptions {
+                strict_complicate_after_simplify: false,
+                ..CheckStrategySanityOptions::default()
+            }),
+        );
+    }
+
+    #[test]
+    fn unique_vec_shorter_than_chosen_size_when_domain_exhausted() {
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..64 {
+            let v = unique_vec(0..4u8, 2..=10)
+                .new_tree(&mut runner)
+                .unwrap()
+                .current();
+            assert!(v.len() >= 2 && v.len() <= 4, "{:?}", v);
+        }
+
+        assert!(btree_set(0..3u8, 4).new_tree(&mut runner).is_err());
+    }
+
+    #[test]
+    fn unique_vec_shrinks_without_creating_duplicates() {
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..64 {
+            let case =
+                unique_vec(0..1000u32, 5..10).new_tree(&mut runner).unwrap();
+            let result = runner.run_one(case, |v| {
+                let distinct = v.iter().collect::<BTreeSet<_>>();
+                assert_eq!(v.len(), distinct.len(), "{:?}", v);
+                prop_assert!(v.iter().all(|&x| x < 3));
+                Ok(())
+            });
+
+            match result {
+                Err(TestError::Fail(_, mut v)) => {
+                    v.sort();
+                    assert_eq!(vec![0, 1, 2, 3, 4], v);
+                }
+                result => panic!("Unexpected result: {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn unique_by_key_keys_are_distinct() {
+        let input = unique_by_key((0..20u8, 0..1000u32), 5..=15, |e| e.0);
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..64 {
+            let case = input.new_tree(&mut runner).unwrap();
+            let result = runner.run_one(case, |v| {
+                let keys = v.iter().map(|e| e.0).collect::<BTreeSet<_>>();
+                assert_eq!(v.len(), keys.len(), "{:?}", v);
+                prop_assert!(v.len() < 5);
+                Ok(())
+            });
+
+            match result {
+                Err(TestError::Fail(_, mut v)) => {
+                    v.sort();
+                    assert_eq!(vec![(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)], v);
+                }
+                result => panic!("Unexpected result: {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn test_unique_vec_sanity() {
+        check_strategy_sanity(
+            unique_vec(0i32..1000, 5..10),
+            Some(CheckStrategySanityOptions {
+                // Shrinking into a duplicate is undone by complicating, which
+                // may use up what `complicate()` would otherwise do.
+                strict_complicate_after_simplify: false,
+                ..CheckStrategySanityOptions::default()
+            }),
+        );
+    }
+
+    #[test]
+    fn sorted_vec_stays_sorted_while_shrinking() {
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..64 {
+            let case =
+                sorted_vec(0..1000u32, 1..10).new_tree(&mut runner).unwrap();
+            let result = runner.run_one(case, |v| {
+                assert!(v.windows(2).all(|w| w[0] <= w[1]), "{:?}", v);
+                prop_assert!(v.iter().all(|&x| x < 500));
+                Ok(())
+            });
+
+            match result {
+                Ok(_) => (),
+                Err(TestError::Fail(_, v)) => assert_eq!(vec![500], v),
+                result => panic!("Unexpected result: {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn test_sorted_vec_sanity() {
+        check_strategy_sanity(
+            sorted_vec(0i32..1000, 5..10),
+            Some(CheckStrategySanityOptions {
+                // Shrinking below a predecessor is undone by complicating,
+                // which may use up what `complicate()` would otherwise do.
+                strict_complicate_after_simplify: false,
+                ..CheckStrategySanityOptions::default()
+            }),
+        );
+    }
+
+    #[test]
+    fn strictly_increasing_draws_without_replacement() {
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..64 {
+            let v = strictly_increasing(0..10u8, 10)
+                .new_tree(&mut runner)
+                .unwrap()
+                .current();
+            assert_eq!((0..10).collect::<Vec<_>>(), v);
+
+            let v = strictly_increasing(i64::MIN..i64::MAX, 0..100)
+                .new_tree(&mut runner)
+                .unwrap()
+                .current();
+            assert!(v.len() < 100);
+            assert!(v.windows(2).all(|w| w[0] < w[1]), "{:?}", v);
+        }
+    }
+
+    #[test]
+    fn strictly_increasing_shrinks_towards_predecessors() {
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..64 {
+            let case = strictly_increasing(-1000..1000i32, 2..20)
+                .new_tree(&mut runner)
+                .unwrap();
+            let result = runner.run_one(case, |v| {
+                assert!(v.windows(2).all(|w| w[0] < w[1]), "{:?}", v);
+                prop_assert!(v[v.len() - 1] - v[0] < 100);
+                Ok(())
+            });
+
+            match result {
+                Ok(_) => (),
+                Err(TestError::Fail(_, v)) => assert_eq!(vec![-1000, -900], v),
+                result => panic!("Unexpected result: {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "fewer than 5 values")]
+    fn strictly_increasing_rejects_narrow_range() {
+        let _ = strictly_increasing(0..4u8, 5..10);
+    }
+
+    #[test]
+    fn test_strictly_increasing_sanity() {
+        check_strategy_sanity(strictly_increasing(0u32..1000, 5..10), None);
+    }
+
+    #[test]
+    fn partition_sums_to_total() {
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..64 {
+            let case = partition(100u8, 4).new_tree(&mut runner).unwrap();
+            let result = runner.run_one(case, |v| {
+                assert_eq!(4, v.len());
+                assert_eq!(100, v.iter().map(|&p| u32::from(p)).sum::<u32>());
+                prop_assert!(v[0] < 10);
+                Ok(())
+            });
+
+            match result {
+                Ok(_) => (),
+                Err(TestError::Fail(_, v)) => assert_eq!(vec![10, 0, 0, 90], v),
+                result => panic!("Unexpected result: {:?}", result),
+            }
+        }
+
+        assert_eq!(
+            Vec::<u8>::new(),
+            partition(0u8, 0).new_tree(&mut runner).unwrap().current()
+        );
+        assert_eq!(
+            vec![7],
+            partition(7i8, 1).new_tree(&mut runner).unwrap().current()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "negative total")]
+    fn partition_rejects_negative_total() {
+        let _ = partition(-1i32, 3);
+    }
+
+    #[test]
+    fn test_partition_sanity() {
+        check_strategy_sanity(partition(1000u64, 5), None);
+    }
+
+    #[test]
+    fn vecs_same_len_deletes_same_index() {
+        let input = vecs_same_len((0..100u32, 0..100u32, 0..2u8), 1..20);
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..64 {
+            let case = input.new_tree(&mut runner).unwrap();
+            let result = runner.run_one(case, |(a, b, c)| {
+                assert!(a.len() == b.len() && b.len() == c.len());
+                prop_assert!(a.iter().all(|&x| x < 50));
+                Ok(())
+            });
+
+            match result {
+                Ok(_) => (),
+                Err(TestError::Fail(_, v)) => {
+                    assert_eq!((vec![50], vec![0], vec![0]), v)
+                }
+                result => panic!("Unexpected result: {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn test_vecs_same_len_sanity() {
+        check_strategy_sanity(
+            vecs_same_len((0i32..1000, 0..10u8), 5..10),
+            None,
+        );
+    }
+
+    #[test]
+    fn matrix_shrinks_rows_and_columns() {
+        let input = matrix(0..10u8, 1..6, 0..6);
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..64 {
+            let case = input.new_tree(&mut runner).unwrap();
+            let result = runner.run_one(case, |m| {
+                assert!(m.iter().all(|row| row.len() == m[0].len()));
+                prop_assert!(
+                    m[0].len() < 3 || m.iter().flatten().all(|&x| x < 5)
+                );
+                Ok(())
+            });
+
+            match result {
+                Ok(_) => (),
+                Err(TestError::Fail(_, m)) => {
+                    assert_eq!(1, m.len());
+                    let mut row = m[0].clone();
+                    row.sort();
+                    assert_eq!(vec![0, 0, 5], row);
+                }
+                result => panic!("Unexpected result: {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn matrix_dimensions_within_ranges() {
+        let input = matrix(0..10u8, 2..4, 3..=3);
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..64 {
+            let m = input.new_tree(&mut runner).unwrap().current();
+            assert!(m.len() >= 2 && m.len() < 4);
+            assert!(m.iter().all(|row| 3 == row.len()));
+        }
+    }
+
+    #[test]
+    fn test_matrix_sanity() {
+        check_strategy_sanity(matrix(0i32..1000, 1..5, 1..5), None);
+    }
+
     #[cfg(feature = "std")]
     #[test]
     fn test_set() {
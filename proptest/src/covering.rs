@@ -0,0 +1,645 @@
+//-
+// Copyright 2021 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Strategies which cover every combination of a few parameters.
+//!
+//! A test of a configuration matrix, say 8 settings with 3 to 5 values each,
+//! has too many combinations to run them all, but random cases will still
+//! miss some pairs of settings, and many bugs only need two settings to
+//! interact. `pairwise()` wraps a tuple of `Enumerable` strategies so that
+//! the first cases of a run form a covering array: every value of every
+//! element is tested together with every value of every other element. Once
+//! the array is exhausted, the cases are random combinations.
+//!
+//! `t_wise()` does the same for every combination of `t` elements, and
+//! `Covering::report()` tells which combinations the cases run so far have
+//! covered.
+//!
+//! The rows of the covering array are taken in the order of the cases of a
+//! run, so every run by `TestRunner::run()` starts over with the first row.
+//! A case rejected by the test, e.g. with `prop_assume!`, still uses up its
+//! row. Failures of cases from the covering array are not persisted, since
+//! their seeds do not reproduce them; the values generated while replaying
+//! persisted failures, or outside of a run, are random.
+//!
+//! ```
+//! use proptest::covering::pairwise;
+//! use proptest::prelude::*;
+//! use proptest::test_runner::{Config, TestRunner};
+//!
+//! let factors = pairwise((any::<bool>(), 0u8..4, 0u8..4, any::<bool>()));
+//! let mut runner = TestRunner::new(Config::with_cases(20));
+//! runner.run(&factors, |(_, a, b, _)| {
+//!     prop_assert!(a + b < 8);
+//!     Ok(())
+//! }).unwrap();
+//!
+//! let report = factors.report();
+//! assert_eq!(report.combinations, report.covered);
+//! ```
+
+use crate::std_facade::{fmt, vec, Arc, Vec};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::strategy::*;
+use crate::test_runner::*;
+use crate::tuple::TupleValueTree;
+
+/// The largest number of value combinations a covering array is built for.
+const MAX_COMBINATIONS: usize = 1 << 24;
+
+/// A tuple of `Enumerable` strategies whose elements can be covered in
+/// combination.
+///
+/// This is implemented for tuples of 2 to 12 elements.
+pub trait Factors: Strategy {
+    /// Returns the number of values of each element, or `None` for elements
+    /// with more values than fit in a `u64`.
+    fn factor_sizes(&self) -> Vec<Option<u64>>;
+
+    /// Creates a value tree for the tuple whose elements start at the given
+    /// indices into their domains.
+    fn new_tree_at_factors(
+        &self,
+        indices: &[u64],
+        runner: &mut TestRunner,
+    ) -> NewTree<Self>;
+}
+
+macro_rules! factors {
+    ($($fld:tt : $typ:ident),*) => {
+        impl<$($typ : Enumerable),*> Factors for ($($typ,)*) {
+            fn factor_sizes(&self) -> Vec<Option<u64>> {
+                vec![$(self.$fld.domain_size()),*]
+            }
+
+            fn new_tree_at_factors(
+                &self,
+                indices: &[u64],
+                runner: &mut TestRunner,
+            ) -> NewTree<Self> {
+                Ok(TupleValueTree::new((
+                    $(self.$fld.new_tree_at(indices[$fld], runner)?,)*
+                )))
+            }
+        }
+    };
+}
+
+factors!(0: A, 1: B);
+factors!(0: A, 1: B, 2: C);
+factors!(0: A, 1: B, 2: C, 3: D);
+factors!(0: A, 1: B, 2: C, 3: D, 4: E);
+factors!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F);
+factors!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G);
+factors!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H);
+factors!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I);
+factors!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I, 9: J);
+factors!(
+    0: A,
+    1: B,
+    2: C,
+    3: D,
+    4: E,
+    5: F,
+    6: G,
+    7: H,
+    8: I,
+    9: J,
+    10: K
+);
+factors!(
+    0: A,
+    1: B,
+    2: C,
+    3: D,
+    4: E,
+    5: F,
+    6: G,
+    7: H,
+    8: I,
+    9: J,
+    10: K,
+    11: L
+);
+
+/// Create a strategy which covers every pair of values of the elements of
+/// `factors` in its first cases.
+///
+/// This is `t_wise(factors, 2)`.
+pub fn pairwise<S: Factors>(factors: S) -> Covering<S> {
+    t_wise(factors, 2)
+}
+
+/// Create a strategy which covers every combination of values of any
+/// `strength` elements of `factors` in its first cases, and then produces
+/// random values of `factors`.
+///
+/// If `strength` is at least the number of elements, the first cases are
+/// every value of `factors`.
+///
+/// ## Panics
+///
+/// Panics if `strength` is 0, if any element has no values, or if there are
+/// too many combinations to build a covering array for; this is meant for
+/// elements with a handful of values each.
+pub fn t_wise<S: Factors>(factors: S, strength: usize) -> Covering<S> {
+    assert!(strength > 0, "Covering strength must be at least 1");
+
+    let sizes = factors
+        .factor_sizes()
+        .into_iter()
+        .enumerate()
+        .map(|(ix, size)| match size {
+            Some(size) if size > 0 && size <= MAX_COMBINATIONS as u64 => {
+                size as usize
+            }
+            Some(0) => panic!("Element {} has no values to cover", ix),
+            _ => panic!("Element {} has too many values to cover", ix),
+        })
+        .collect::<Vec<_>>();
+
+    let mut table = CoverageTable::new(sizes, strength);
+    let mut rng = TestRng::deterministic_rng(RngAlgorithm::ChaCha);
+    let mut rows = Vec::new();
+    while table.uncovered > 0 {
+        let row = table.best_row(&mut rng);
+        table.cover(&row);
+        rows.push(row);
+    }
+
+    Covering {
+        factors,
+        strength: table.strength,
+        sizes: table.sizes,
+        rows: Arc::new(rows),
+        generated: Arc::new(AtomicUsize::new(0)),
+    }
+}
+
+/// Strategy which covers combinations of the elements of a tuple.
+///
+/// Created by `pairwise()` and `t_wise()` in the same module. Clones share
+/// the record of the rows generated so far.
+#[derive(Clone, Debug)]
+#[must_use = "strategies do nothing unless used"]
+pub struct Covering<S> {
+    factors: S,
+    strength: usize,
+    sizes: Vec<usize>,
+    rows: Arc<Vec<Vec<usize>>>,
+    /// The number of rows up to the last one generated.
+    generated: Arc<AtomicUsize>,
+}
+
+impl<S: Factors> Strategy for Covering<S> {
+    type Tree = S::Tree;
+    type Value = S::Value;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let row = runner.case_index().map(|ix| ix as usize);
+        match row.and_then(|row| self.rows.get(row).map(|values| (row, values)))
+        {
+            Some((row, values)) => {
+                self.generated.fetch_max(row + 1, Ordering::SeqCst);
+                runner.case_unseeded();
+                let indices =
+                    values.iter().map(|&ix| ix as u64).collect::<Vec<_>>();
+                self.factors.new_tree_at_factors(&indices, runner)
+            }
+            None => self.factors.new_tree(runner),
+        }
+    }
+}
+
+impl<S> Covering<S> {
+    /// Returns the number of cases it takes to cover every combination.
+    pub fn covering_cases(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Reports which combinations the cases generated so far cover.
+    ///
+    /// The rows of the covering array up to the last one generated are
+    /// counted, but not the random cases after the array.
+    pub fn report(&self) -> CoveringReport {
+        let cases = self.generated.load(Ordering::SeqCst);
+        let mut table = CoverageTable::new(self.sizes.clone(), self.strength);
+        for row in self.rows.iter().take(cases) {
+            table.cover(row);
+        }
+
+        CoveringReport {
+            strength: table.strength,
+            cases,
+            covering_cases: self.rows.len(),
+            combinations: table.combinations,
+            covered: table.combinations - table.uncovered,
+            uncovered: table.uncovered_combinations(),
+        }
+    }
+}
+
+/// Which combinations of values a `Covering` strategy has covered.
+///
+/// Returned by `Covering::report()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoveringReport {
+    /// The number of elements in each combination.
+    pub strength: usize,
+    /// The number of cases of the covering array generated so far.
+    pub cases: usize,
+    /// The number of cases it takes to cover every combination.
+    pub covering_cases: usize,
+    /// The number of combinations to cover.
+    pub combinations: usize,
+    /// The number of combinations covered so far.
+    pub covered: usize,
+    /// The combinations not covered yet, as `(element, value index)` pairs.
+    pub uncovered: Vec<Vec<(usize, u64)>>,
+}
+
+impl fmt::Display for CoveringReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "covered {} of {} {}-wise combinations in {} cases",
+            self.covered, self.combinations, self.strength, self.cases
+        )?;
+        if self.covered < self.combinations {
+            write!(f, " ({} cases cover them all)", self.covering_cases)?;
+        }
+        Ok(())
+    }
+}
+
+/// The combinations of values, of every `strength` elements, which a set of
+/// rows covers.
+struct CoverageTable {
+    strength: usize,
+    sizes: Vec<usize>,
+    /// Every set of `strength` elements, in lexicographic order.
+    subsets: Vec<Vec<usize>>,
+    /// For each subset, whether each combination of its values is covered,
+    /// indexed with the first element varying fastest.
+    covered: Vec<Vec<bool>>,
+    combinations: usize,
+    uncovered: usize,
+}
+
+impl CoverageTable {
+    fn new(sizes: Vec<usize>, strength: usize) -> Self {
+        let strength = strength.min(sizes.len());
+        let subsets = subsets(sizes.len(), strength);
+        let covered = subsets
+            .iter()
+            .map(|subset| {
+                let len = subset.iter().try_fold(1usize, |len, &elt| {
+                    len.checked_mul(sizes[elt])
+                        .filter(|&len| len <= MAX_COMBINATIONS)
+                });
+                vec![false; len.expect("Too many combinations to cover")]
+            })
+            .collect::<Vec<_>>();
+        let combinations = covered.iter().map(Vec::len).sum::<usize>();
+        assert!(
+            combinations <= MAX_COMBINATIONS,
+            "Too many combinations to cover"
+        );
+
+        CoverageTable {
+            strength,
+            sizes,
+            subsets,
+            covered,
+            combinations,
+            uncovered: combinations,
+        }
+    }
+
+    /// Returns the index into `covered[subset]` of the values in `row`.
+    fn index(&self, subset: usize, row: &[usize]) -> usize {
+        self.subsets[subset]
+            .iter()
+            .rev()
+            .fold(0, |ix, &elt| ix * self.sizes[elt] + row[elt])
+    }
+
+    fn cover(&mut self, row: &[usize]) {
+        for subset in 0..self.subsets.len() {
+            let ix = self.index(subset, row);
+            if !self.covered[subset][ix] {
+                self.covered[subset][ix] = true;
+                self.uncovered -= 1;
+            }
+        }
+    }
+
+    /// Returns the values of the first uncovered combination.
+    fn first_uncovered(&self) -> Option<(usize, Vec<usize>)> {
+        self.covered
+            .iter()
+            .enumerate()
+            .find_map(|(subset, covered)| {
+                let mut ix = covered.iter().position(|&c| !c)?;
+                let values = self.subsets[subset]
+                    .iter()
+                    .map(|&elt| {
+                        let value = ix % self.sizes[elt];
+                        ix /= self.sizes[elt];
+                        value
+                    })
+                    .collect();
+                Some((subset, values))
+            })
+    }
+
+    fn uncovered_combinations(&self) -> Vec<Vec<(usize, u64)>> {
+        let mut uncovered = Vec::new();
+        for (subset, covered) in self.covered.iter().enumerate() {
+            for (mut ix, _) in covered.iter().enumerate().filter(|c| !*c.1) {
+                uncovered.push(
+                    self.subsets[subset]
+                        .iter()
+                        .map(|&elt| {
+                            let value = ix % self.sizes[elt];
+                            ix /= self.sizes[elt];
+                            (elt, value as u64)
+                        })
+                        .collect(),
+                );
+            }
+        }
+        uncovered
+    }
+
+    /// Builds a row covering as many uncovered combinations as possible,
+    /// using the greedy heuristic of AETG: start from an uncovered
+    /// combination, then give the other elements, in random order, the value
+    /// which completes the most uncovered combinations. The best of a few
+    /// such rows is returned.
+    fn best_row(&self, rng: &mut TestRng) -> Vec<usize> {
+        const CANDIDATES: usize = 16;
+
+        let (subset, values) = self
+            .first_uncovered()
+            .expect("No combinations left to cover");
+        let mut best: Option<(usize, Vec<Option<usize>>)> = None;
+        for _ in 0..CANDIDATES {
+            let mut row = vec![None; self.sizes.len()];
+            for (&elt, &value) in self.subsets[subset].iter().zip(&values) {
+                row[elt] = Some(value);
+            }
+
+            let mut rest = (0..self.sizes.len())
+                .filter(|&elt| row[elt].is_none())
+                .collect::<Vec<_>>();
+            rest.shuffle(rng);
+
+            for elt in rest {
+                // Break ties between values at random.
+                let size = self.sizes[elt];
+                let offset = rng.gen_range(0..size);
+                let mut best_value = (0, offset);
+                for value in (0..size).map(|ix| (ix + offset) % size) {
+                    row[elt] = Some(value);
+                    let gain = self.gain(&row, Some(elt));
+                    if gain > best_value.0 {
+                        best_value = (gain, value);
+                    }
+                }
+                row[elt] = Some(best_value.1);
+            }
+
+            let gain = self.gain(&row, None);
+            if !matches!(best, Some((best_gain, _)) if best_gain >= gain) {
+                best = Some((gain, row));
+            }
+        }
+
+        best.unwrap().1.into_iter().map(Option::unwrap).collect()
+    }
+
+    /// Counts the uncovered combinations which `row` completes, among those
+    /// involving `elt` if given.
+    fn gain(&self, row: &[Option<usize>], elt: Option<usize>) -> usize {
+        let mut gain = 0;
+        for (subset, elts) in self.subsets.iter().enumerate() {
+            if matches!(elt, Some(elt) if !elts.contains(&elt)) {
+                continue;
+            }
+
+            let ix = elts.iter().rev().try_fold(0, |ix, &elt| {
+                row[elt].map(|value| ix * self.sizes[elt] + value)
+            });
+            if let Some(ix) = ix {
+                if !self.covered[subset][ix] {
+                    gain += 1;
+                }
+            }
+        }
+        gain
+    }
+}
+
+/// Returns every set of `k` numbers below `n`, in lexicographic order.
+fn subsets(n: usize, k: usize) -> Vec<Vec<usize>> {
+    let mut subsets = Vec::new();
+    if k > n {
+        return subsets;
+    }
+
+    let mut subset = (0..k).collect::<Vec<_>>();
+    loop {
+        subsets.push(subset.clone());
+
+        // Advance the rightmost number which can still be advanced, and
+        // restart the numbers after it just above it.
+        let ix = match (0..k).rev().find(|&ix| subset[ix] < n - k + ix) {
+            Some(ix) => ix,
+            None => return subsets,
+        };
+        subset[ix] += 1;
+        for next in ix + 1..k {
+            subset[next] = subset[next - 1] + 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::std_facade::{BTreeSet, Box};
+    use core::cell::RefCell;
+
+    /// Runs `cases` cases of `strategy`, returning the values generated.
+    fn run_cases<S: Strategy>(strategy: &S, cases: u32) -> Vec<S::Value> {
+        let values = RefCell::new(Vec::new());
+        let mut runner = TestRunner::new(Config {
+            cases,
+            failure_persistence: None,
+            ..Config::default()
+        });
+        runner
+            .run(strategy, |value| {
+                values.borrow_mut().push(value);
+                Ok(())
+            })
+            .unwrap();
+        values.into_inner()
+    }
+
+    /// Checks that `rows` cover every combination of values of `strength`
+    /// elements.
+    fn assert_covers(sizes: &[usize], strength: usize, rows: &[Vec<usize>]) {
+        for subset in subsets(sizes.len(), strength) {
+            let seen = rows
+                .iter()
+                .map(|row| subset.iter().map(|&elt| row[elt]).collect())
+                .collect::<BTreeSet<Vec<usize>>>();
+            let expected =
+                subset.iter().map(|&elt| sizes[elt]).product::<usize>();
+            assert_eq!(expected, seen.len(), "subset {:?}", subset);
+        }
+    }
+
+    #[test]
+    fn subsets_are_lexicographic() {
+        assert_eq!(
+            vec![
+                vec![0, 1],
+                vec![0, 2],
+                vec![0, 3],
+                vec![1, 2],
+                vec![1, 3],
+                vec![2, 3],
+            ],
+            subsets(4, 2)
+        );
+        assert_eq!(vec![vec![0, 1, 2]], subsets(3, 3));
+        assert!(subsets(2, 3).is_empty());
+    }
+
+    #[test]
+    fn pairwise_covers_every_pair_in_few_cases() {
+        let strategy = pairwise((
+            0u8..3,
+            0u8..4,
+            0u8..5,
+            crate::bool::ANY,
+            0u8..3,
+            0u8..4,
+            0u8..5,
+            0u8..3,
+        ));
+        assert!(
+            strategy.covering_cases() <= 40,
+            "{} cases",
+            strategy.covering_cases()
+        );
+
+        let cases = strategy.covering_cases();
+        let rows = run_cases(&strategy, cases as u32 + 1)
+            .into_iter()
+            .take(cases)
+            .map(|v| {
+                vec![
+                    v.0 as usize,
+                    v.1 as usize,
+                    v.2 as usize,
+                    v.3 as usize,
+                    v.4 as usize,
+                    v.5 as usize,
+                    v.6 as usize,
+                    v.7 as usize,
+                ]
+            })
+            .collect::<Vec<_>>();
+        assert_covers(&[3, 4, 5, 2, 3, 4, 5, 3], 2, &rows);
+
+        let report = strategy.report();
+        assert_eq!(report.combinations, report.covered);
+        assert!(report.uncovered.is_empty());
+
+        // Random cases follow the covering array, and are not counted.
+        assert_eq!(cases, report.cases);
+    }
+
+    #[test]
+    fn three_wise_covers_every_triple() {
+        let strategy = t_wise((0u8..3, 0u8..3, 0u8..3, 0u8..3, 0u8..3), 3);
+        assert!(strategy.covering_cases() < 3 * 3 * 3 * 3 * 3);
+        assert_covers(&[3; 5], 3, &strategy.rows);
+    }
+
+    #[test]
+    fn full_strength_is_exhaustive() {
+        let strategy = t_wise((0u8..3, crate::bool::ANY), 5);
+        assert_eq!(6, strategy.covering_cases());
+
+        let values =
+            run_cases(&strategy, 6).into_iter().collect::<BTreeSet<_>>();
+        assert_eq!(6, values.len());
+    }
+
+    #[test]
+    fn runs_start_over_and_do_not_persist_covered_failures() {
+        let strategy = pairwise((0u8..3, 0u8..3, crate::bool::ANY));
+        let cases = strategy.covering_cases() as u32;
+        assert_eq!(run_cases(&strategy, cases), run_cases(&strategy, cases));
+
+        let mut runner = TestRunner::new(Config {
+            failure_persistence: Some(Box::new(
+                MapFailurePersistence::default(),
+            )),
+            ..Config::default()
+        });
+        let result = runner.run(&strategy, |(a, _, _)| {
+            prop_assert!(a < 2);
+            Ok(())
+        });
+        assert!(result.is_err());
+        assert!(runner
+            .config()
+            .failure_persistence
+            .as_ref()
+            .unwrap()
+            .load_persisted_failures2(None)
+            .is_empty());
+    }
+
+    #[test]
+    fn report_lists_uncovered_pairs() {
+        let strategy = pairwise((crate::bool::ANY, crate::bool::ANY));
+        let first = run_cases(&strategy, 1)[0];
+
+        let report = strategy.report();
+        assert_eq!(1, report.cases);
+        assert_eq!(4, report.combinations);
+        assert_eq!(1, report.covered);
+        assert_eq!(3, report.uncovered.len());
+        assert!(!report
+            .uncovered
+            .contains(&vec![(0, first.0 as u64), (1, first.1 as u64)]));
+        assert_eq!(
+            "covered 1 of 4 2-wise combinations in 1 cases \
+             (4 cases cover them all)",
+            format!("{}", report)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Element 1 has too many values to cover")]
+    fn huge_elements_panic() {
+        let _ = pairwise((0u8..3, 0u64..));
+    }
+}
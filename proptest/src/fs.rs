@@ -0,0 +1,573 @@
+//-
+// Copyright 2021 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Strategies for generating directory trees, and support for creating them
+//! on disk.
+//!
+//! The strategies in this module generate `DirTree`s, which only describe a
+//! tree of directories, files and symbolic links. `DirTree::materialize()`
+//! creates the described tree in a fresh temporary directory, which is
+//! deleted again when the returned `TempTree` is dropped; i.e., usually at the
+//! end of the test case.
+//!
+//! Trees shrink by removing entries and truncating file contents.
+//!
+//! This module requires the `fs` feature, which is enabled by default.
+
+use crate::std_facade::{BTreeMap, Box, String, Vec};
+use core::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tempfile::TempDir;
+
+use crate::bool;
+use crate::collection::{vec, SizeRange};
+use crate::sample::Index;
+use crate::strategy::*;
+use crate::string::string_regex;
+use crate::test_runner::*;
+
+/// The target of symbolic links which were generated to be dangling.
+///
+/// This can never collide with the name of a generated entry.
+pub const DANGLING_TARGET: &str = "dangling-link-target";
+
+/// A single entry in a `DirTree`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Entry {
+    /// A regular file.
+    File {
+        /// The contents of the file.
+        contents: Vec<u8>,
+        /// Whether the file is read-only.
+        readonly: bool,
+    },
+    /// A directory.
+    Dir {
+        /// The entries of the directory.
+        tree: DirTree,
+        /// Whether the directory is read-only.
+        readonly: bool,
+    },
+    /// A symbolic link.
+    Symlink {
+        /// The target of the link, relative to the directory containing the
+        /// link. This is either the path of another file or directory in the
+        /// tree or `DANGLING_TARGET`.
+        target: PathBuf,
+    },
+}
+
+/// A description of a directory tree, mapping the names of the entries of a
+/// directory to the entries themselves.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DirTree {
+    /// The entries of the root directory of the tree.
+    pub entries: BTreeMap<String, Entry>,
+}
+
+impl DirTree {
+    /// Return the paths of all entries in the tree, relative to its root, in
+    /// depth-first order.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        self.walk(Path::new(""), &mut |path, _| paths.push(path.to_path_buf()));
+        paths
+    }
+
+    fn walk(&self, parent: &Path, f: &mut dyn FnMut(&Path, &Entry)) {
+        for (name, entry) in &self.entries {
+            let path = parent.join(name);
+            f(&path, entry);
+            if let Entry::Dir { ref tree, .. } = *entry {
+                tree.walk(&path, f);
+            }
+        }
+    }
+
+    /// Create the tree in a new temporary directory.
+    ///
+    /// The directory and everything in it is deleted when the returned
+    /// `TempTree` is dropped.
+    pub fn materialize(&self) -> io::Result<TempTree> {
+        let dir = tempfile::Builder::new().prefix("proptest-fs").tempdir()?;
+        let mut temp = TempTree {
+            dir: Some(dir),
+            readonly: Vec::new(),
+        };
+        // Record read-only entries first so that they can be cleaned up even
+        // if creating the tree fails part way.
+        self.walk(Path::new(""), &mut |path, entry| match *entry {
+            Entry::File { readonly: true, .. }
+            | Entry::Dir { readonly: true, .. } => {
+                temp.readonly.push(path.to_path_buf())
+            }
+            _ => (),
+        });
+        self.materialize_in(temp.path())?;
+        Ok(temp)
+    }
+
+    /// Create the entries of the tree inside `root`, which must be an
+    /// existing directory.
+    ///
+    /// Nothing is done to clean the entries up afterwards; note that
+    /// read-only entries may need their permissions changed before they can
+    /// be deleted.
+    pub fn materialize_in(&self, root: &Path) -> io::Result<()> {
+        let mut result = Ok(());
+        let mut symlinks = Vec::new();
+        let mut readonly = Vec::new();
+
+        // Links are created after everything else, since creating links on
+        // Windows requires knowing whether the target is a directory, and
+        // permissions last, since read-only directories cannot be filled.
+        self.walk(Path::new(""), &mut |path, entry| {
+            if result.is_err() {
+                return;
+            }
+            let full_path = root.join(path);
+            result = match *entry {
+                Entry::File {
+                    ref contents,
+                    readonly: ro,
+                } => {
+                    if ro {
+                        readonly.push(full_path.clone());
+                    }
+                    fs::write(&full_path, contents)
+                }
+                Entry::Dir { readonly: ro, .. } => {
+                    if ro {
+                        readonly.push(full_path.clone());
+                    }
+                    fs::create_dir(&full_path)
+                }
+                Entry::Symlink { ref target } => {
+                    symlinks.push((full_path, target.clone()));
+                    Ok(())
+                }
+            };
+        });
+        result?;
+
+        for (path, target) in symlinks {
+            symlink(&path, &target)?;
+        }
+
+        // Children come after their parents, so set permissions bottom-up.
+        for path in readonly.into_iter().rev() {
+            set_readonly(&path, true)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn symlink(path: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, path)
+}
+
+#[cfg(windows)]
+fn symlink(path: &Path, target: &Path) -> io::Result<()> {
+    let resolved = path.parent().unwrap_or(Path::new("")).join(target);
+    if resolved.is_dir() {
+        std::os::windows::fs::symlink_dir(target, path)
+    } else {
+        std::os::windows::fs::symlink_file(target, path)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn symlink(_path: &Path, _target: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "symbolic links are not supported on this platform",
+    ))
+}
+
+fn set_readonly(path: &Path, readonly: bool) -> io::Result<()> {
+    let mut permissions = fs::symlink_metadata(path)?.permissions();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = permissions.mode();
+        permissions.set_mode(if readonly {
+            mode & !0o222
+        } else {
+            mode | 0o200
+        });
+    }
+    #[cfg(not(unix))]
+    permissions.set_readonly(readonly);
+    fs::set_permissions(path, permissions)
+}
+
+/// A directory tree created by `DirTree::materialize()`.
+///
+/// The tree is deleted when this is dropped.
+pub struct TempTree {
+    // Only `None` while being dropped.
+    dir: Option<TempDir>,
+    readonly: Vec<PathBuf>,
+}
+
+impl TempTree {
+    /// Return the path of the root directory of the tree.
+    pub fn path(&self) -> &Path {
+        self.dir.as_ref().expect("TempTree already dropped").path()
+    }
+}
+
+impl fmt::Debug for TempTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("TempTree").field(&self.path()).finish()
+    }
+}
+
+impl Drop for TempTree {
+    fn drop(&mut self) {
+        // Read-only directories would keep their contents from being
+        // deleted. Errors are ignored since `TempDir` ignores them too.
+        for path in &self.readonly {
+            let _ = set_readonly(&self.path().join(path), false);
+        }
+        self.dir.take();
+    }
+}
+
+/// Parameters for generating trees with `trees()`.
+///
+/// The `Default` generates trees up to 3 levels deep with up to 7 entries per
+/// directory, including symbolic links and read-only entries.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TreeParams {
+    /// The maximum number of levels of directories below the root.
+    pub depth: u32,
+    /// The number of entries in each directory.
+    pub entries: SizeRange,
+    /// Whether to generate symbolic links, some of which are dangling.
+    ///
+    /// Note that creating symbolic links on Windows requires special
+    /// privileges.
+    pub symlinks: bool,
+    /// Whether to generate read-only files and directories.
+    pub readonly: bool,
+}
+
+impl Default for TreeParams {
+    fn default() -> Self {
+        TreeParams {
+            depth: 3,
+            entries: (0..8).into(),
+            symlinks: true,
+            readonly: true,
+        }
+    }
+}
+
+/// The shape of a tree before symbolic links have been resolved.
+#[derive(Clone, Debug)]
+enum Node {
+    File(Vec<u8>, bool),
+    Dir(Vec<(String, Node)>, bool),
+    /// `None` for a dangling link.
+    Symlink(Option<Index>),
+}
+
+opaque_strategy_wrapper! {
+    /// Strategy to create `DirTree`s.
+    ///
+    /// Created by the `trees()` function in the same module.
+    #[derive(Debug)]
+    pub struct TreeStrategy[][](BoxedStrategy<DirTree>)
+        -> TreeValueTree;
+    /// `ValueTree` corresponding to `TreeStrategy`.
+    pub struct TreeValueTree[][](Box<dyn ValueTree<Value = DirTree>>)
+        -> DirTree;
+}
+
+impl fmt::Debug for TreeValueTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("TreeValueTree")
+            .field(&self.0.current())
+            .finish()
+    }
+}
+
+/// Create a strategy to generate `DirTree`s as described by `params`, with
+/// the contents of files generated by `contents`.
+///
+/// Entry names are made of lowercase ASCII letters, digits and underscores.
+///
+/// ```
+/// use proptest::fs::{trees, TreeParams};
+/// use proptest::prelude::*;
+///
+/// proptest! {
+///     fn everything_gets_created(
+///         tree in trees(prop::collection::vec(any::<u8>(), 0..64),
+///                       TreeParams::default())
+///     ) {
+///         let temp = tree.materialize()?;
+///         for path in tree.paths() {
+///             prop_assert!(temp.path().join(&path)
+///                              .symlink_metadata().is_ok());
+///         }
+///     }
+/// }
+/// # fn main() { everything_gets_created(); }
+/// ```
+pub fn trees<S>(contents: S, params: TreeParams) -> TreeStrategy
+where
+    S: Strategy<Value = Vec<u8>> + 'static,
+{
+    let readonly = if params.readonly {
+        bool::weighted(0.1).boxed()
+    } else {
+        Just(false).boxed()
+    };
+
+    let file = (contents, readonly.clone())
+        .prop_map(|(contents, readonly)| Node::File(contents, readonly));
+    let leaf = if params.symlinks {
+        Union::new_weighted(vec![
+            (4, file.boxed()),
+            (
+                1,
+                prop_oneof![
+                    4 => crate::arbitrary::any::<Index>().prop_map(Some),
+                    1 => Just(None),
+                ]
+                .prop_map(Node::Symlink)
+                .boxed(),
+            ),
+        ])
+        .boxed()
+    } else {
+        file.boxed()
+    };
+
+    let entries = params.entries.clone();
+    let node = leaf.prop_recursive(
+        params.depth,
+        params.entries.end_excl() as u32 * params.depth.max(1),
+        params.entries.end_excl() as u32,
+        move |inner| {
+            (vec((name(), inner), entries.clone()), readonly.clone()).prop_map(
+                |(entries, readonly)| Node::Dir(dedup(entries), readonly),
+            )
+        },
+    );
+
+    TreeStrategy(
+        vec((name(), node), params.entries)
+            .prop_map(|entries| resolve(&Node::Dir(dedup(entries), false)))
+            .boxed(),
+    )
+}
+
+fn name() -> impl Strategy<Value = String> {
+    string_regex("[a-z0-9_]{1,8}").unwrap()
+}
+
+/// Remove all but the last of the entries with the same name.
+fn dedup(entries: Vec<(String, Node)>) -> Vec<(String, Node)> {
+    entries
+        .into_iter()
+        .collect::<BTreeMap<_, _>>()
+        .into_iter()
+        .collect()
+}
+
+/// Convert `root` to a `DirTree`, picking the targets of symbolic links among
+/// the other entries in the tree.
+fn resolve(root: &Node) -> DirTree {
+    fn collect_targets(node: &Node, parent: &Path, targets: &mut Vec<PathBuf>) {
+        if let Node::Dir(ref entries, _) = *node {
+            for (name, node) in entries {
+                let path = parent.join(name);
+                match *node {
+                    Node::Symlink(_) => (),
+                    _ => targets.push(path.clone()),
+                }
+                collect_targets(node, &path, targets);
+            }
+        }
+    }
+
+    fn convert(node: &Node, parent: &Path, targets: &[PathBuf]) -> DirTree {
+        let mut tree = DirTree::default();
+        if let Node::Dir(ref entries, _) = *node {
+            for (name, node) in entries {
+                let path = parent.join(name);
+                let entry = match *node {
+                    Node::File(ref contents, readonly) => Entry::File {
+                        contents: contents.clone(),
+                        readonly,
+                    },
+                    Node::Dir(_, readonly) => Entry::Dir {
+                        tree: convert(node, &path, targets),
+                        readonly,
+                    },
+                    Node::Symlink(Some(ref index)) if !targets.is_empty() => {
+                        // Targets are relative to the directory of the link.
+                        let mut target = PathBuf::new();
+                        for _ in parent.components() {
+                            target.push("..");
+                        }
+                        target.push(index.get(targets));
+                        Entry::Symlink { target }
+                    }
+                    Node::Symlink(_) => Entry::Symlink {
+                        target: DANGLING_TARGET.into(),
+                    },
+                };
+                tree.entries.insert(name.clone(), entry);
+            }
+        }
+        tree
+    }
+
+    let mut targets = Vec::new();
+    collect_targets(root, Path::new(""), &mut targets);
+    convert(root, Path::new(""), &targets)
+}
+
+#[cfg(test)]
+mod test {
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::arbitrary::any;
+
+    fn contents() -> impl Strategy<Value = Vec<u8>> {
+        vec(any::<u8>(), 0..16)
+    }
+
+    #[test]
+    fn params_respected() {
+        let params = TreeParams {
+            depth: 1,
+            entries: (1..4).into(),
+            symlinks: false,
+            readonly: false,
+        };
+        let strategy = trees(contents(), params);
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..256 {
+            let tree = strategy.new_tree(&mut runner).unwrap().current();
+            assert!(!tree.entries.is_empty() && tree.entries.len() < 4);
+            for path in tree.paths() {
+                assert!(path.components().count() <= 2);
+            }
+            tree.walk(Path::new(""), &mut |_, entry| match *entry {
+                Entry::Symlink { .. } => panic!("Unexpected symlink"),
+                Entry::File { readonly, .. } | Entry::Dir { readonly, .. } => {
+                    assert!(!readonly)
+                }
+            });
+        }
+    }
+
+    #[test]
+    fn symlinks_point_into_tree_or_dangle() {
+        let strategy = trees(contents(), TreeParams::default());
+        let mut runner = TestRunner::deterministic();
+        let mut saw_link = false;
+        for _ in 0..256 {
+            let tree = strategy.new_tree(&mut runner).unwrap().current();
+            let paths = tree.paths();
+            tree.walk(Path::new(""), &mut |path, entry| {
+                if let Entry::Symlink { ref target } = *entry {
+                    saw_link = true;
+                    if target != Path::new(DANGLING_TARGET) {
+                        let parent = path.parent().unwrap();
+                        let ups = parent.components().count();
+                        let resolved =
+                            target.components().skip(ups).collect::<PathBuf>();
+                        assert!(paths.contains(&resolved));
+                    }
+                }
+            });
+        }
+        assert!(saw_link);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn materialized_and_cleaned_up() {
+        let strategy = trees(contents(), TreeParams::default());
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..32 {
+            let tree = strategy.new_tree(&mut runner).unwrap().current();
+            let temp = tree.materialize().unwrap();
+            let root = temp.path().to_path_buf();
+            tree.walk(Path::new(""), &mut |path, entry| {
+                let path = root.join(path);
+                let meta = fs::symlink_metadata(&path).unwrap();
+                match *entry {
+                    Entry::File {
+                        ref contents,
+                        readonly,
+                    } => {
+                        assert_eq!(*contents, fs::read(&path).unwrap());
+                        assert_eq!(readonly, meta.permissions().readonly());
+                    }
+                    Entry::Dir { readonly, .. } => {
+                        assert!(meta.is_dir());
+                        assert_eq!(readonly, meta.permissions().readonly());
+                    }
+                    Entry::Symlink { ref target } => {
+                        assert_eq!(*target, fs::read_link(&path).unwrap());
+                    }
+                }
+            });
+            drop(temp);
+            assert!(!root.exists());
+        }
+    }
+
+    #[test]
+    fn shrinks_by_removing_entries_and_truncating() {
+        let mut runner = TestRunner::default();
+        let result =
+            runner.run(&trees(contents(), TreeParams::default()), |tree| {
+                let mut total = 0;
+                tree.walk(Path::new(""), &mut |_, entry| {
+                    if let Entry::File { ref contents, .. } = *entry {
+                        total += contents.len();
+                    }
+                });
+                prop_assert!(total < 4);
+                Ok(())
+            });
+
+        match result {
+            Err(TestError::Fail(_, tree)) => {
+                assert_eq!(1, tree.paths().len());
+                match *tree.entries.values().next().unwrap() {
+                    Entry::File {
+                        ref contents,
+                        readonly,
+                    } => {
+                        assert_eq!(4, contents.len());
+                        assert!(!readonly);
+                    }
+                    ref entry => panic!("Unexpected entry: {:?}", entry),
+                }
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+}
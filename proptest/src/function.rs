@@ -0,0 +1,590 @@
+//-
+// Copyright 2021 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Strategies for generating pure functions, for testing higher-order code.
+//!
+//! The functions are `Fun` values, created by the `fun()` strategy. A `Fun`
+//! does not decide its outputs up front; the first time it is called with a
+//! given input, it draws an output from the output strategy, seeded from the
+//! input and the test RNG, and remembers it. Calling it with the same input
+//! again always gives the same output.
+//!
+//! When a test fails, the `Debug` output of a `Fun` shows the inputs it was
+//! called with and the outputs it gave, followed by the output for all other
+//! inputs under `_`. Shrinking first tries to turn the function into a
+//! constant function, then replaces individual outputs with that constant,
+//! and finally shrinks the outputs themselves.
+//!
+//! ```
+//! use proptest::function::{fun, Fun};
+//! use proptest::prelude::*;
+//!
+//! fn map_all(v: &[i32], f: &Fun<i32, bool>) -> Vec<bool> {
+//!     v.iter().map(|x| f.call(x)).collect()
+//! }
+//!
+//! proptest! {
+//!     fn map_preserves_length(
+//!         v in prop::collection::vec(any::<i32>(), 0..10),
+//!         f in fun(any::<bool>())
+//!     ) {
+//!         prop_assert_eq!(v.len(), map_all(&v, &f).len());
+//!     }
+//! }
+//! #
+//! # fn main() { map_preserves_length(); }
+//! ```
+
+use crate::std_facade::{fmt, Rc, Vec};
+use core::cell::{Cell, RefCell};
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use std::collections::HashMap;
+
+use rand::Rng;
+
+use crate::generate::Seed;
+use crate::strategy::*;
+use crate::test_runner::*;
+
+/// A generated pure function from `&A` to `B`.
+///
+/// Created by the `fun()` strategy. Clones of a `Fun` share the same table
+/// of outputs.
+pub struct Fun<A, B> {
+    table: Rc<dyn Table<A, B>>,
+}
+
+impl<A, B> Clone for Fun<A, B> {
+    fn clone(&self) -> Self {
+        Fun {
+            table: Rc::clone(&self.table),
+        }
+    }
+}
+
+impl<A, B> Fun<A, B> {
+    /// Apply the function to `input`.
+    pub fn call(&self, input: &A) -> B {
+        self.table.call(input)
+    }
+
+    /// Borrow the function as a closure, for passing to code which expects
+    /// an `Fn`.
+    pub fn as_fn(&self) -> impl Fn(&A) -> B + '_ {
+        move |input| self.call(input)
+    }
+}
+
+impl<A, B> fmt::Debug for Fun<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.table.fmt(f)
+    }
+}
+
+/// Create a strategy to generate functions from `&A` to values drawn from
+/// `output`.
+///
+/// `A` is usually inferred from how the function is called. See the module
+/// documentation for how the functions behave.
+pub fn fun<A, S: Strategy>(output: S) -> FunStrategy<A, S> {
+    FunStrategy {
+        output: Rc::new(output),
+        _input: PhantomData,
+    }
+}
+
+/// Strategy to create `Fun`s.
+///
+/// Created by the `fun()` function in the same module.
+#[must_use = "strategies do nothing unless used"]
+pub struct FunStrategy<A, S> {
+    output: Rc<S>,
+    _input: PhantomData<fn(&A)>,
+}
+
+impl<A, S> Clone for FunStrategy<A, S> {
+    fn clone(&self) -> Self {
+        FunStrategy {
+            output: Rc::clone(&self.output),
+            _input: PhantomData,
+        }
+    }
+}
+
+impl<A, S: fmt::Debug> fmt::Debug for FunStrategy<A, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FunStrategy")
+            .field("output", &self.output)
+            .finish()
+    }
+}
+
+impl<A, S> Strategy for FunStrategy<A, S>
+where
+    A: Clone + Eq + Hash + fmt::Debug + 'static,
+    S: Strategy + 'static,
+{
+    type Tree = FunValueTree<A, S>;
+    type Value = Fun<A, S::Value>;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let default = self.output.new_tree(runner)?;
+        let seed = runner.rng().gen();
+        Ok(FunValueTree {
+            state: Rc::new(State {
+                output: Rc::clone(&self.output),
+                runner: runner.partial_clone(),
+                seed,
+                constant: Cell::new(false),
+                default: RefCell::new(default),
+                entries: RefCell::new(Vec::new()),
+                index: RefCell::new(HashMap::new()),
+            }),
+            shrink: FunShrink::Constant,
+            prev_shrink: None,
+        })
+    }
+}
+
+/// The outputs of a `Fun`, without the type of the output value tree.
+trait Table<A, B> {
+    fn call(&self, input: &A) -> B;
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result;
+}
+
+/// An input a `Fun` has been called with.
+struct Entry<A, T> {
+    input: A,
+    /// The tree for the output, or `None` if the output strategy rejected
+    /// the input's seed, in which case the default output is used.
+    tree: Option<T>,
+    /// Whether shrinking has replaced this output with the default.
+    defaulted: bool,
+}
+
+/// State shared between a `FunValueTree` and the `Fun`s it produces.
+struct State<A, S: Strategy> {
+    output: Rc<S>,
+    /// The runner from which the runners for new inputs are cloned.
+    runner: TestRunner,
+    /// The seed from which the seeds for new inputs are derived.
+    seed: [u8; 32],
+    /// Whether every input currently maps to the default output.
+    constant: Cell<bool>,
+    default: RefCell<S::Tree>,
+    /// The inputs seen so far, in the order they were first seen.
+    entries: RefCell<Vec<Entry<A, S::Tree>>>,
+    index: RefCell<HashMap<A, usize>>,
+}
+
+/// A `Hasher` which collects the bytes an input hashes to, so that a seed can
+/// be derived from them with `Seed::from_bytes()`.
+///
+/// Unlike `DefaultHasher`, whose algorithm may change between Rust releases,
+/// this gives each input the same seed in every release, and on every
+/// platform: integers are written little-endian, and `usize`s and `isize`s
+/// as 64-bit integers.
+#[derive(Default)]
+struct InputBytes(Vec<u8>);
+
+impl InputBytes {
+    fn seed(&self) -> Seed {
+        Seed::from_bytes(&self.0)
+    }
+}
+
+impl Hasher for InputBytes {
+    fn finish(&self) -> u64 {
+        let mut hash = [0; 8];
+        hash.copy_from_slice(&self.seed().as_bytes()[..8]);
+        u64::from_le_bytes(hash)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64);
+    }
+}
+
+impl<A: Clone + Eq + Hash, S: Strategy> State<A, S> {
+    /// Return the index of the entry for `input`, adding one if this is a
+    /// new input.
+    fn entry(&self, input: &A) -> usize {
+        if let Some(&ix) = self.index.borrow().get(input) {
+            return ix;
+        }
+
+        let mut bytes = InputBytes::default();
+        input.hash(&mut bytes);
+        let mut seed = self.seed;
+        for (byte, hash) in seed.iter_mut().zip(bytes.seed().as_bytes()) {
+            *byte ^= hash;
+        }
+
+        let mut runner = self.runner.clone();
+        *runner.rng() = TestRng::from_seed(RngAlgorithm::ChaCha, &seed);
+        let tree = self.output.new_tree(&mut runner).ok();
+
+        let mut entries = self.entries.borrow_mut();
+        entries.push(Entry {
+            input: input.clone(),
+            tree,
+            defaulted: false,
+        });
+        self.index
+            .borrow_mut()
+            .insert(input.clone(), entries.len() - 1);
+        entries.len() - 1
+    }
+}
+
+impl<A, S> Table<A, S::Value> for State<A, S>
+where
+    A: Clone + Eq + Hash + fmt::Debug,
+    S: Strategy,
+{
+    fn call(&self, input: &A) -> S::Value {
+        let ix = self.entry(input);
+        if !self.constant.get() {
+            let entries = self.entries.borrow();
+            let entry = &entries[ix];
+            if let (false, Some(tree)) = (entry.defaulted, &entry.tree) {
+                return tree.current();
+            }
+        }
+        self.default.borrow().current()
+    }
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        struct Wildcard;
+        impl fmt::Debug for Wildcard {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("_")
+            }
+        }
+
+        let mut map = f.debug_map();
+        if !self.constant.get() {
+            for entry in self.entries.borrow().iter() {
+                if let (false, Some(tree)) = (entry.defaulted, &entry.tree) {
+                    map.entry(&entry.input, &tree.current());
+                }
+            }
+        }
+        map.entry(&Wildcard, &self.default.borrow().current());
+        map.finish()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum FunShrink {
+    /// Make every input map to the default output.
+    Constant,
+    /// Shrink the default output while the function is constant.
+    ShrinkConstant,
+    /// Replace the output for the entry at the index with the default.
+    DefaultEntry(usize),
+    /// Shrink the output for the entry at the index.
+    ShrinkEntry(usize),
+    /// Shrink the default output while the function is not constant.
+    ShrinkDefault,
+}
+
+/// `ValueTree` corresponding to `FunStrategy`.
+pub struct FunValueTree<A, S: Strategy> {
+    state: Rc<State<A, S>>,
+    shrink: FunShrink,
+    prev_shrink: Option<FunShrink>,
+}
+
+impl<A: fmt::Debug, S: Strategy> fmt::Debug for FunValueTree<A, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FunValueTree")
+            .field("constant", &self.state.constant.get())
+            .field("default", &self.state.default.borrow().current())
+            .field("shrink", &self.shrink)
+            .field("prev_shrink", &self.prev_shrink)
+            .finish()
+    }
+}
+
+impl<A, S> ValueTree for FunValueTree<A, S>
+where
+    A: Clone + Eq + Hash + fmt::Debug + 'static,
+    S: Strategy + 'static,
+{
+    type Value = Fun<A, S::Value>;
+
+    fn current(&self) -> Fun<A, S::Value> {
+        Fun {
+            table: Rc::clone(&self.state) as Rc<dyn Table<A, S::Value>>,
+        }
+    }
+
+    fn simplify(&mut self) -> bool {
+        let state = &self.state;
+        if let FunShrink::Constant = self.shrink {
+            self.shrink = FunShrink::ShrinkConstant;
+            state.constant.set(true);
+            self.prev_shrink = Some(FunShrink::Constant);
+            return true;
+        }
+
+        if let FunShrink::ShrinkConstant = self.shrink {
+            if state.default.borrow_mut().simplify() {
+                self.prev_shrink = Some(self.shrink);
+                return true;
+            }
+            return false;
+        }
+
+        while let FunShrink::DefaultEntry(ix) = self.shrink {
+            let mut entries = state.entries.borrow_mut();
+            let entry = match entries.get_mut(ix) {
+                Some(entry) => entry,
+                None => {
+                    self.shrink = FunShrink::ShrinkEntry(0);
+                    break;
+                }
+            };
+
+            self.shrink = FunShrink::DefaultEntry(ix + 1);
+            if !entry.defaulted && entry.tree.is_some() {
+                entry.defaulted = true;
+                self.prev_shrink = Some(FunShrink::DefaultEntry(ix));
+                return true;
+            }
+        }
+
+        while let FunShrink::ShrinkEntry(ix) = self.shrink {
+            let mut entries = state.entries.borrow_mut();
+            let entry = match entries.get_mut(ix) {
+                Some(entry) => entry,
+                None => {
+                    self.shrink = FunShrink::ShrinkDefault;
+                    break;
+                }
+            };
+
+            if let (false, Some(tree)) = (entry.defaulted, &mut entry.tree) {
+                if tree.simplify() {
+                    self.prev_shrink = Some(self.shrink);
+                    return true;
+                }
+            }
+            self.shrink = FunShrink::ShrinkEntry(ix + 1);
+        }
+
+        if state.default.borrow_mut().simplify() {
+            self.prev_shrink = Some(FunShrink::ShrinkDefault);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn complicate(&mut self) -> bool {
+        let state = &self.state;
+        match self.prev_shrink {
+            None => false,
+            Some(FunShrink::Constant) => {
+                // The test needs more than one output; go back to the table
+                // and try to default its entries one at a time.
+                state.constant.set(false);
+                self.shrink = FunShrink::DefaultEntry(0);
+                self.prev_shrink = None;
+                true
+            }
+            Some(FunShrink::DefaultEntry(ix)) => {
+                state.entries.borrow_mut()[ix].defaulted = false;
+                self.prev_shrink = None;
+                true
+            }
+            Some(FunShrink::ShrinkEntry(ix)) => {
+                let mut entries = state.entries.borrow_mut();
+                let complicated = match entries[ix].tree {
+                    Some(ref mut tree) => tree.complicate(),
+                    None => false,
+                };
+                if !complicated {
+                    self.prev_shrink = None;
+                }
+                complicated
+            }
+            Some(FunShrink::ShrinkConstant)
+            | Some(FunShrink::ShrinkDefault) => {
+                if state.default.borrow_mut().complicate() {
+                    true
+                } else {
+                    self.prev_shrink = None;
+                    false
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::std_facade::{vec, String};
+
+    #[test]
+    fn input_bytes_do_not_depend_on_platform() {
+        let mut bytes = InputBytes::default();
+        (258u32, 3usize, "ab").hash(&mut bytes);
+        assert_eq!(
+            vec![2, 1, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, b'a', b'b', 0xff],
+            bytes.0
+        );
+    }
+
+    #[test]
+    fn same_input_gives_same_output() {
+        let mut runner = TestRunner::deterministic();
+        let f = fun::<u32, _>(0..1_000_000u32)
+            .new_tree(&mut runner)
+            .unwrap()
+            .current();
+        let outputs = (0..100).map(|x| f.call(&x)).collect::<Vec<_>>();
+        assert_eq!(
+            outputs,
+            (0..100).rev().map(|x| f.call(&x)).rev().collect::<Vec<_>>()
+        );
+        // Not a constant function.
+        assert!(outputs.iter().any(|&y| y != outputs[0]));
+
+        let g = f.clone();
+        assert_eq!(outputs[5], g.as_fn()(&5));
+    }
+
+    #[test]
+    fn outputs_do_not_depend_on_call_order() {
+        let strategy = fun::<u32, _>(0..1_000_000u32);
+        let f = strategy
+            .new_tree(&mut TestRunner::deterministic())
+            .unwrap()
+            .current();
+        let g = strategy
+            .new_tree(&mut TestRunner::deterministic())
+            .unwrap()
+            .current();
+        let a = (0..10).map(|x| f.call(&x)).collect::<Vec<_>>();
+        let b = (0..10).rev().map(|x| g.call(&x)).rev().collect::<Vec<_>>();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn shrinks_to_constant_function() {
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..32 {
+            let case = fun::<u32, _>(0..100u32).new_tree(&mut runner).unwrap();
+            let result = runner.run_one(case, |f| {
+                prop_assert!(f.call(&1) + f.call(&2) > 1000);
+                Ok(())
+            });
+
+            match result {
+                Err(TestError::Fail(_, f)) => {
+                    assert_eq!("{_: 0}", format!("{:?}", f));
+                }
+                result => panic!("Unexpected result: {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn shrinks_table_when_constant_passes() {
+        let mut failures = 0;
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..32 {
+            let case = fun::<u32, _>(0..100u32).new_tree(&mut runner).unwrap();
+            let result = runner.run_one(case, |f| {
+                prop_assert!(f.call(&1) < 50);
+                Ok(())
+            });
+
+            match result {
+                Err(TestError::Fail(_, f)) => {
+                    failures += 1;
+                    let table = format!("{:?}", f);
+                    assert!(
+                        table == "{_: 50}" || table == "{1: 50, _: 0}",
+                        "{}",
+                        table
+                    );
+                }
+                Ok(_) => (),
+                result => panic!("Unexpected result: {:?}", result),
+            }
+        }
+        assert!(failures > 8);
+    }
+
+    #[test]
+    fn shrinks_to_two_outputs() {
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..32 {
+            let case = fun::<u32, _>(0..100u32).new_tree(&mut runner).unwrap();
+            let result = runner.run_one(case, |f| {
+                prop_assert!(f.call(&1) == f.call(&2));
+                Ok(())
+            });
+
+            match result {
+                Err(TestError::Fail(_, f)) => {
+                    // Only one of the two inputs needs its own entry.
+                    assert_ne!(f.call(&1), f.call(&2));
+                    assert_eq!(2, format!("{:?}", f).matches(": ").count());
+                }
+                Ok(_) => (),
+                result => panic!("Unexpected result: {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn debug_shows_observed_inputs() {
+        let mut runner = TestRunner::deterministic();
+        let f = fun::<String, _>(Just(7u8))
+            .new_tree(&mut runner)
+            .unwrap()
+            .current();
+        assert_eq!("{_: 7}", format!("{:?}", f));
+        f.call(&"a".into());
+        f.call(&"b".into());
+        f.call(&"a".into());
+        assert_eq!(r#"{"a": 7, "b": 7, _: 7}"#, format!("{:?}", f));
+        assert_eq!(vec![7, 7], vec![f.call(&"x".into()), f.call(&"y".into())]);
+    }
+}
@@ -0,0 +1,268 @@
+//-
+// Copyright 2021 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Reproducible generation of values outside of tests.
+//!
+//! Strategies are also a convenient way to describe data for benchmarks,
+//! demos or fixtures. `values()` turns a strategy and a seed into an endless
+//! iterator of values which is the same every time it is created with the
+//! same seed.
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use proptest::generate;
+//!
+//! let users = (1u32..100_000, prop::sample::select(vec!["admin", "guest"]));
+//! let first = generate::values(&users, "demo database")
+//!     .take(100)
+//!     .collect::<Vec<_>>();
+//! let again = generate::values(&users, "demo database")
+//!     .take(100)
+//!     .collect::<Vec<_>>();
+//! assert_eq!(first, again);
+//! ```
+//!
+//! ## Stability
+//!
+//! For a given version of proptest, the same strategy and seed always produce
+//! the same values, on every platform, with two exceptions: collections whose
+//! iteration order depends on a randomly seeded hasher, like `HashSet` and
+//! `HashMap`, and strategies which themselves rely on outside state, such as
+//! `prop_perturb()` closures drawing from another RNG.
+//!
+//! Across versions of proptest, the values of the following strategies are
+//! expected to stay the same, and any change to them is called out in the
+//! changelog:
+//!
+//! - `Just`, `LazyJust` and `any::<bool>()`;
+//! - integer ranges and `any()` for integer types;
+//! - `sample::select()` and `sample::subsequence()`;
+//! - `prop_oneof!` and `Union`;
+//! - tuples and arrays of stable strategies, and `prop_map()` of a stable
+//!   strategy.
+//!
+//! Everything else, notably floating-point numbers, characters, strings and
+//! regexes, collections, filters and recursive strategies, may generate
+//! different values after an upgrade, since improving how they explore their
+//! domain is an ordinary change for a test library. Values are also only
+//! stable as long as the strategy itself is unchanged: generating an extra
+//! value anywhere in a strategy shifts everything generated after it.
+
+use crate::std_facade::{fmt, String, Vec};
+
+use crate::strategy::{Strategy, ValueTree};
+use crate::test_runner::{
+    default_default_config, Reason, RngAlgorithm, TestRng, TestRunner,
+};
+
+/// The seed of a `values()` iterator.
+///
+/// Seeds are usually created from strings or byte slices of any length,
+/// which are hashed into the 32 bytes of the seed. A `[u8; 32]` array is used
+/// as the seed directly.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Seed([u8; 32]);
+
+impl Seed {
+    /// Derives a seed from `bytes`.
+    ///
+    /// The derivation is fixed: the same bytes give the same seed in every
+    /// version of proptest.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let mut seed = [0u8; 32];
+        for (lane, chunk) in seed.chunks_mut(8).enumerate() {
+            // FNV-1a over the lane number and the bytes, followed by the
+            // SplitMix64 finaliser to spread the bits.
+            let mut hash = FNV_OFFSET;
+            for &byte in [lane as u8].iter().chain(bytes) {
+                hash = (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME);
+            }
+            hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            hash ^= hash >> 31;
+            chunk.copy_from_slice(&hash.to_le_bytes());
+        }
+        Seed(seed)
+    }
+
+    /// Returns the bytes of this seed.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Seed(")?;
+        for byte in &self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        f.write_str(")")
+    }
+}
+
+impl From<[u8; 32]> for Seed {
+    fn from(bytes: [u8; 32]) -> Self {
+        Seed(bytes)
+    }
+}
+
+impl<'a> From<&'a [u8]> for Seed {
+    fn from(bytes: &'a [u8]) -> Self {
+        Seed::from_bytes(bytes)
+    }
+}
+
+impl<'a> From<&'a str> for Seed {
+    fn from(s: &'a str) -> Self {
+        Seed::from_bytes(s.as_bytes())
+    }
+}
+
+impl<'a> From<&'a String> for Seed {
+    fn from(s: &'a String) -> Self {
+        Seed::from_bytes(s.as_bytes())
+    }
+}
+
+impl From<u64> for Seed {
+    fn from(n: u64) -> Self {
+        Seed::from_bytes(&n.to_le_bytes())
+    }
+}
+
+/// Returns an endless iterator of values generated by `strategy` from
+/// `seed`.
+///
+/// The values do not depend on the environment: `PROPTEST_*` variables are
+/// ignored, and the default configuration is used. See the module
+/// documentation for which strategies generate the same values across
+/// versions.
+pub fn values<S: Strategy>(strategy: S, seed: impl Into<Seed>) -> Values<S> {
+    Values {
+        strategy,
+        runner: seeded_runner(seed),
+    }
+}
+
+/// Creates a runner with the default configuration, regardless of the
+/// environment, whose RNG is determined by `seed`.
+pub(crate) fn seeded_runner(seed: impl Into<Seed>) -> TestRunner {
+    TestRunner::new_with_rng(
+        default_default_config(),
+        TestRng::from_seed(RngAlgorithm::ChaCha, &seed.into().0),
+    )
+}
+
+/// Iterator returned by `values()`.
+///
+/// ## Panics
+///
+/// `next()` panics if the strategy fails to generate a value, such as when a
+/// filter rejects too many values in a row. Use `try_next()` to handle such
+/// failures instead.
+#[must_use = "iterators do nothing unless consumed"]
+#[derive(Debug)]
+pub struct Values<S> {
+    strategy: S,
+    runner: TestRunner,
+}
+
+impl<S: Strategy> Values<S> {
+    /// Generates the next value, or returns why the strategy failed to.
+    ///
+    /// After a failure, the values which follow are still deterministic but
+    /// differ from those generated without it.
+    pub fn try_next(&mut self) -> Result<S::Value, Reason> {
+        self.strategy
+            .new_tree(&mut self.runner)
+            .map(|tree| tree.current())
+    }
+}
+
+impl<S: Strategy> Iterator for Values<S> {
+    type Item = S::Value;
+
+    fn next(&mut self) -> Option<S::Value> {
+        match self.try_next() {
+            Ok(value) => Some(value),
+            Err(why) => panic!("Failed to generate a value: {}", why),
+        }
+    }
+}
+
+/// Collects the first `n` values generated by `strategy` from `seed`.
+///
+/// This is shorthand for `values(strategy, seed).take(n).collect()`.
+pub fn collect<S: Strategy>(
+    strategy: S,
+    seed: impl Into<Seed>,
+    n: usize,
+) -> Vec<S::Value> {
+    values(strategy, seed).take(n).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::std_facade::vec;
+    use crate::strategy::{Just, Strategy};
+
+    #[test]
+    fn same_seed_same_values() {
+        let strategy = (0u64..1_000_000, crate::bool::ANY);
+        assert_eq!(
+            collect(&strategy, "fixtures", 50),
+            collect(&strategy, "fixtures", 50)
+        );
+        assert_ne!(
+            collect(&strategy, "fixtures", 50),
+            collect(&strategy, "fixture", 50)
+        );
+        assert_eq!(
+            collect(&strategy, b"fixtures" as &[u8], 50),
+            collect(&strategy, "fixtures", 50)
+        );
+    }
+
+    #[test]
+    fn seed_derivation_is_fixed() {
+        assert_eq!(
+            "Seed(33ad1732c51ab759b11f215379f0fd54\
+             5122953ba594b1f525c93c7f49d704e1)",
+            format!("{:?}", Seed::from("proptest"))
+        );
+        assert_ne!(Seed::from(""), Seed::from(&[0u8][..]));
+        assert_eq!(&[7; 32], Seed::from([7; 32]).as_bytes());
+    }
+
+    #[test]
+    fn stable_strategies_keep_their_values() {
+        let strategy = (0u32..1000, crate::bool::ANY, Just('x'));
+        assert_eq!(
+            vec![
+                (810, true, 'x'),
+                (157, true, 'x'),
+                (372, false, 'x'),
+                (975, false, 'x'),
+                (510, true, 'x'),
+            ],
+            collect(&strategy, "stability", 5)
+        );
+    }
+
+    #[test]
+    fn failures_are_reported() {
+        let mut values = values((0..10).prop_filter("never", |_| false), 1);
+        assert!(values.try_next().is_err());
+    }
+}
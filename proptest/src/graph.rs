@@ -0,0 +1,822 @@
+//-
+// Copyright 2021 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Strategies for generating graphs and trees.
+//!
+//! All strategies in this module produce a `Graph`, which holds a payload
+//! for every node and every edge. The payloads are drawn from the node and
+//! edge strategies passed to the constructor; use `Just(())` for graphs
+//! without payloads, or a numeric strategy for the edges of a weighted graph.
+//!
+//! Graphs shrink by deleting nodes (together with their edges), then deleting
+//! edges, then shrinking the payloads. Deletions which would break the
+//! property the strategy was asked for, such as connectivity, are skipped, so
+//! every value produced while shrinking still has that property.
+//!
+//! ```
+//! use proptest::graph;
+//! use proptest::prelude::*;
+//!
+//! proptest! {
+//!     fn spanning_tree_has_one_edge_less(
+//!         g in graph::connected(Just(()), 1..100u32, 1..20, 0..50)
+//!     ) {
+//!         prop_assert!(g.edge_count() + 1 >= g.node_count());
+//!     }
+//! }
+//! #
+//! # fn main() { spanning_tree_has_one_edge_less(); }
+//! ```
+
+use crate::std_facade::{BTreeSet, Vec, VecDeque};
+use core::cmp;
+
+use rand::seq::index;
+use rand::Rng;
+
+use crate::bits::BitSetLike;
+use crate::collection::{Dimension, SizeRange};
+use crate::strategy::*;
+use crate::test_runner::*;
+
+/// A graph with a payload of type `N` for each node and a payload of type
+/// `E` for each edge.
+///
+/// Nodes are identified by their index in `nodes()`. Edges connect two
+/// distinct nodes, and there is at most one edge between any two nodes (or
+/// one in each direction for directed graphs).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Graph<N, E> {
+    nodes: Vec<N>,
+    edges: Vec<(usize, usize, E)>,
+    directed: bool,
+}
+
+impl<N, E> Graph<N, E> {
+    /// Return whether the edges of this graph are directed.
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    /// Return the payloads of the nodes.
+    pub fn nodes(&self) -> &[N] {
+        &self.nodes
+    }
+
+    /// Return the edges, as `(from, to, payload)` triples, sorted by `from`
+    /// and then `to`.
+    ///
+    /// For undirected graphs, `from` is always less than `to`.
+    pub fn edges(&self) -> &[(usize, usize, E)] {
+        &self.edges
+    }
+
+    /// Return the number of nodes.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Return the number of edges.
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Return the adjacency lists of this graph.
+    ///
+    /// The list at index `i` holds the nodes reachable from node `i` over a
+    /// single edge, along with the payload of that edge. Each edge of an
+    /// undirected graph appears in the lists of both of its ends.
+    pub fn adjacency(&self) -> Vec<Vec<(usize, &E)>> {
+        let mut adjacency = (0..self.nodes.len())
+            .map(|_| Vec::new())
+            .collect::<Vec<_>>();
+        for &(from, to, ref payload) in &self.edges {
+            adjacency[from].push((to, payload));
+            if !self.directed {
+                adjacency[to].push((from, payload));
+            }
+        }
+        adjacency
+    }
+
+    /// Return the nodes reachable from `node` over a single edge.
+    pub fn neighbors(&self, node: usize) -> Vec<usize> {
+        self.adjacency()
+            .swap_remove(node)
+            .into_iter()
+            .map(|(to, _)| to)
+            .collect()
+    }
+
+    /// Split the graph into its node payloads and its edges.
+    pub fn into_parts(self) -> (Vec<N>, Vec<(usize, usize, E)>) {
+        (self.nodes, self.edges)
+    }
+}
+
+/// The kind of graph generated by a `GraphStrategy`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Directed,
+    Undirected,
+    Dag,
+    Connected,
+    Tree,
+    Forest,
+}
+
+impl Kind {
+    fn is_directed(self) -> bool {
+        matches!(self, Kind::Directed | Kind::Dag)
+    }
+
+    fn is_connected(self) -> bool {
+        matches!(self, Kind::Connected | Kind::Tree)
+    }
+
+    /// The most edges a graph of this kind can have with `nodes` nodes.
+    fn max_edges(self, nodes: usize) -> usize {
+        let pairs = nodes.saturating_mul(nodes.saturating_sub(1));
+        match self {
+            Kind::Directed => pairs,
+            Kind::Undirected | Kind::Dag | Kind::Connected => pairs / 2,
+            Kind::Tree | Kind::Forest => nodes.saturating_sub(1),
+        }
+    }
+}
+
+/// Strategy to create `Graph`s.
+///
+/// Created by the functions in the same module.
+#[must_use = "strategies do nothing unless used"]
+#[derive(Clone, Debug)]
+pub struct GraphStrategy<N, E> {
+    kind: Kind,
+    node: N,
+    edge: E,
+    nodes: SizeRange,
+    edges: SizeRange,
+}
+
+/// Create a strategy to generate directed graphs with a number of nodes in
+/// `nodes` and a number of edges in `edges`.
+///
+/// The number of edges is limited to what the number of nodes allows.
+pub fn directed<N: Strategy, E: Strategy>(
+    node: N,
+    edge: E,
+    nodes: impl Into<SizeRange>,
+    edges: impl Into<SizeRange>,
+) -> GraphStrategy<N, E> {
+    GraphStrategy::new(Kind::Directed, node, edge, nodes.into(), edges.into())
+}
+
+/// Create a strategy to generate undirected graphs with a number of nodes in
+/// `nodes` and a number of edges in `edges`.
+///
+/// The number of edges is limited to what the number of nodes allows.
+pub fn undirected<N: Strategy, E: Strategy>(
+    node: N,
+    edge: E,
+    nodes: impl Into<SizeRange>,
+    edges: impl Into<SizeRange>,
+) -> GraphStrategy<N, E> {
+    GraphStrategy::new(Kind::Undirected, node, edge, nodes.into(), edges.into())
+}
+
+/// Create a strategy to generate directed acyclic graphs with a number of
+/// nodes in `nodes` and a number of edges in `edges`.
+///
+/// The graphs are generated over a topological ordering of the nodes, which
+/// is the order of their indices: every edge goes from a node to one with a
+/// greater index. The number of edges is limited to what the number of nodes
+/// allows.
+pub fn dag<N: Strategy, E: Strategy>(
+    node: N,
+    edge: E,
+    nodes: impl Into<SizeRange>,
+    edges: impl Into<SizeRange>,
+) -> GraphStrategy<N, E> {
+    GraphStrategy::new(Kind::Dag, node, edge, nodes.into(), edges.into())
+}
+
+/// Create a strategy to generate connected undirected graphs with a number
+/// of nodes in `nodes` and a number of edges in `edges`.
+///
+/// Each graph is built from a random spanning tree plus further edges, so it
+/// always has at least one edge less than it has nodes, even if `edges`
+/// would allow fewer.
+/// Nodes and edges are only deleted while shrinking if the graph stays
+/// connected.
+pub fn connected<N: Strategy, E: Strategy>(
+    node: N,
+    edge: E,
+    nodes: impl Into<SizeRange>,
+    edges: impl Into<SizeRange>,
+) -> GraphStrategy<N, E> {
+    GraphStrategy::new(Kind::Connected, node, edge, nodes.into(), edges.into())
+}
+
+/// Create a strategy to generate trees, as undirected graphs with a number
+/// of nodes in `nodes`.
+///
+/// Each edge goes from a parent to a child with a greater index, so node `0`
+/// can be treated as the root. Shrinking only deletes leaves.
+pub fn tree<N: Strategy, E: Strategy>(
+    node: N,
+    edge: E,
+    nodes: impl Into<SizeRange>,
+) -> GraphStrategy<N, E> {
+    GraphStrategy::new(
+        Kind::Tree,
+        node,
+        edge,
+        nodes.into(),
+        SizeRange::default(),
+    )
+}
+
+/// Create a strategy to generate forests, as undirected graphs with a number
+/// of nodes in `nodes` and a number of edges in `edges`.
+///
+/// As with `tree()`, each edge goes from a parent to a child with a greater
+/// index, so the nodes without an edge to a smaller index are the roots.
+pub fn forest<N: Strategy, E: Strategy>(
+    node: N,
+    edge: E,
+    nodes: impl Into<SizeRange>,
+    edges: impl Into<SizeRange>,
+) -> GraphStrategy<N, E> {
+    GraphStrategy::new(Kind::Forest, node, edge, nodes.into(), edges.into())
+}
+
+impl<N, E> GraphStrategy<N, E> {
+    fn new(
+        kind: Kind,
+        node: N,
+        edge: E,
+        nodes: SizeRange,
+        edges: SizeRange,
+    ) -> Self {
+        nodes.assert_nonempty();
+        edges.assert_nonempty();
+        GraphStrategy {
+            kind,
+            node,
+            edge,
+            nodes,
+            edges,
+        }
+    }
+
+    /// Choose the pairs of nodes to connect in a graph with `nodes` nodes.
+    fn sample_edges(
+        &self,
+        runner: &mut TestRunner,
+        nodes: usize,
+    ) -> BTreeSet<(usize, usize)> {
+        let max = self.kind.max_edges(nodes);
+        let mut pairs = BTreeSet::new();
+        match self.kind {
+            Kind::Tree => {
+                for child in 1..nodes {
+                    pairs.insert((runner.rng().gen_range(0..child), child));
+                }
+            }
+
+            Kind::Forest => {
+                let count = cmp::min(self.edges.sample(runner), max);
+                for child in index::sample(runner.rng(), max, count) {
+                    let child = child + 1;
+                    pairs.insert((runner.rng().gen_range(0..child), child));
+                }
+            }
+
+            Kind::Directed | Kind::Undirected | Kind::Dag | Kind::Connected => {
+                if Kind::Connected == self.kind {
+                    for child in 1..nodes {
+                        let parent = runner.rng().gen_range(0..child);
+                        pairs.insert((parent, child));
+                    }
+                }
+
+                let count = cmp::max(
+                    pairs.len(),
+                    cmp::min(self.edges.sample(runner), max),
+                );
+                while pairs.len() < count {
+                    let from = runner.rng().gen_range(0..nodes);
+                    let mut to = runner.rng().gen_range(0..nodes - 1);
+                    if to >= from {
+                        to += 1;
+                    }
+
+                    pairs.insert(if Kind::Directed == self.kind {
+                        (from, to)
+                    } else {
+                        (cmp::min(from, to), cmp::max(from, to))
+                    });
+                }
+            }
+        }
+        pairs
+    }
+}
+
+impl<N: Strategy, E: Strategy> Strategy for GraphStrategy<N, E> {
+    type Tree = GraphValueTree<N::Tree, E::Tree>;
+    type Value = Graph<N::Value, E::Value>;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let node_count = self.nodes.sample(runner);
+        let pairs = self.sample_edges(runner, node_count);
+
+        let mut nodes = Vec::with_capacity(node_count);
+        while nodes.len() < node_count {
+            nodes.push(self.node.new_tree(runner)?);
+        }
+        let mut edges = Vec::with_capacity(pairs.len());
+        for (from, to) in pairs {
+            edges.push((from, to, self.edge.new_tree(runner)?));
+        }
+
+        let edge_count = edges.len();
+        Ok(GraphValueTree {
+            kind: self.kind,
+            node_dim: Dimension::new(node_count, self.nodes.start()),
+            edge_dim: Dimension::new(edge_count, 0),
+            min_edges: cmp::min(self.edges.start(), edge_count),
+            shrink: GraphShrink::DeleteNodes {
+                start: 0,
+                len: cmp::max(1, node_count / 2),
+            },
+            prev_shrink: None,
+            pass_deleted: false,
+            pass_skipped: false,
+            deletion_pending: false,
+            nodes,
+            edges,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum GraphShrink {
+    /// Delete chunks of nodes, along with the edges touching them.
+    DeleteNodes {
+        start: usize,
+        len: usize,
+    },
+    /// Delete chunks of edges.
+    DeleteEdges {
+        start: usize,
+        len: usize,
+    },
+    ShrinkNode(usize),
+    ShrinkEdge(usize),
+}
+
+/// `ValueTree` corresponding to `GraphStrategy`.
+#[derive(Clone, Debug)]
+pub struct GraphValueTree<N: ValueTree, E: ValueTree> {
+    kind: Kind,
+    nodes: Vec<N>,
+    edges: Vec<(usize, usize, E)>,
+    node_dim: Dimension,
+    /// Edges stay in `edge_dim` after one of their nodes is deleted, but are
+    /// only included if both of their nodes are.
+    edge_dim: Dimension,
+    min_edges: usize,
+    shrink: GraphShrink,
+    prev_shrink: Option<GraphShrink>,
+    /// Whether the current pass of deletions deleted anything which was
+    /// kept, and whether it skipped anything for breaking the requested kind
+    /// of graph. If both, the skipped deletions may now be possible, so
+    /// another pass is made.
+    pass_deleted: bool,
+    pass_skipped: bool,
+    /// Whether the last deletion is yet to be either kept or undone.
+    deletion_pending: bool,
+}
+
+impl<N: ValueTree, E: ValueTree> GraphValueTree<N, E> {
+    fn has_edge(&self, ix: usize) -> bool {
+        let (from, to, _) = self.edges[ix];
+        self.edge_dim.included.test(ix)
+            && self.node_dim.included.test(from)
+            && self.node_dim.included.test(to)
+    }
+
+    /// Delete the next chunk of nodes or edges which leaves a graph of the
+    /// requested kind, returning whether there was one.
+    fn delete_next(
+        &mut self,
+        nodes: bool,
+        start: &mut usize,
+        len: &mut usize,
+    ) -> bool {
+        // Not having been undone by `complicate()`, the last deletion is kept.
+        self.pass_deleted |= self.deletion_pending;
+        self.deletion_pending = false;
+
+        loop {
+            let dim = if nodes {
+                &mut self.node_dim
+            } else {
+                &mut self.edge_dim
+            };
+            if dim.delete_next(start, len) {
+                if self.is_valid() {
+                    self.deletion_pending = true;
+                    return true;
+                }
+
+                let dim = if nodes {
+                    &mut self.node_dim
+                } else {
+                    &mut self.edge_dim
+                };
+                dim.undelete();
+                self.pass_skipped = true;
+            } else if self.pass_deleted && self.pass_skipped {
+                *start = 0;
+                *len = 1;
+                self.pass_deleted = false;
+                self.pass_skipped = false;
+            } else {
+                self.pass_deleted = false;
+                self.pass_skipped = false;
+                return false;
+            }
+        }
+    }
+
+    /// Whether the included nodes and edges still form a graph of the
+    /// requested kind.
+    fn is_valid(&self) -> bool {
+        let edges = (0..self.edges.len()).filter(|&ix| self.has_edge(ix));
+        if edges.clone().count() < self.min_edges {
+            return false;
+        }
+        if !self.kind.is_connected() {
+            return true;
+        }
+
+        let mut adjacency = (0..self.nodes.len())
+            .map(|_| Vec::new())
+            .collect::<Vec<_>>();
+        for ix in edges {
+            let (from, to, _) = self.edges[ix];
+            adjacency[from].push(to);
+            adjacency[to].push(from);
+        }
+
+        let root = match self.node_dim.next_included(0) {
+            Some(root) => root,
+            None => return true,
+        };
+        let mut seen = BTreeSet::new();
+        let mut queue = VecDeque::new();
+        seen.insert(root);
+        queue.push_back(root);
+        while let Some(node) = queue.pop_front() {
+            for &next in &adjacency[node] {
+                if seen.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        seen.len() == self.node_dim.included.count()
+    }
+}
+
+impl<N: ValueTree, E: ValueTree> ValueTree for GraphValueTree<N, E> {
+    type Value = Graph<N::Value, E::Value>;
+
+    fn current(&self) -> Graph<N::Value, E::Value> {
+        let mut renumbered = Vec::with_capacity(self.nodes.len());
+        let mut nodes = Vec::new();
+        for (ix, node) in self.nodes.iter().enumerate() {
+            renumbered.push(nodes.len());
+            if self.node_dim.included.test(ix) {
+                nodes.push(node.current());
+            }
+        }
+
+        let edges = (0..self.edges.len())
+            .filter(|&ix| self.has_edge(ix))
+            .map(|ix| {
+                let (from, to, ref payload) = self.edges[ix];
+                (renumbered[from], renumbered[to], payload.current())
+            })
+            .collect();
+
+        Graph {
+            nodes,
+            edges,
+            directed: self.kind.is_directed(),
+        }
+    }
+
+    fn simplify(&mut self) -> bool {
+        if let GraphShrink::DeleteNodes { mut start, mut len } = self.shrink {
+            if self.delete_next(true, &mut start, &mut len) {
+                self.shrink = GraphShrink::DeleteNodes { start, len };
+                self.prev_shrink = Some(self.shrink);
+                return true;
+            }
+
+            // Edges of deleted nodes would only make deleting edges slower.
+            for ix in 0..self.edges.len() {
+                if !self.has_edge(ix) {
+                    self.edge_dim.included.clear(ix);
+                }
+            }
+            // Deleting any edge of a tree disconnects it.
+            self.shrink = if Kind::Tree == self.kind {
+                GraphShrink::ShrinkNode(0)
+            } else {
+                GraphShrink::DeleteEdges {
+                    start: 0,
+                    len: cmp::max(1, self.edge_dim.included.count() / 2),
+                }
+            };
+        }
+
+        if let GraphShrink::DeleteEdges { mut start, mut len } = self.shrink {
+            if self.delete_next(false, &mut start, &mut len) {
+                self.shrink = GraphShrink::DeleteEdges { start, len };
+                self.prev_shrink = Some(self.shrink);
+                return true;
+            }
+
+            self.shrink = GraphShrink::ShrinkNode(0);
+        }
+
+        while let GraphShrink::ShrinkNode(ix) = self.shrink {
+            let ix = match self.node_dim.next_included(ix) {
+                Some(ix) => ix,
+                None => {
+                    self.shrink = GraphShrink::ShrinkEdge(0);
+                    break;
+                }
+            };
+
+            if self.nodes[ix].simplify() {
+                self.shrink = GraphShrink::ShrinkNode(ix);
+                self.prev_shrink = Some(self.shrink);
+                return true;
+            }
+            self.shrink = GraphShrink::ShrinkNode(ix + 1);
+        }
+
+        while let GraphShrink::ShrinkEdge(ix) = self.shrink {
+            let ix = match self.edge_dim.next_included(ix) {
+                Some(ix) => ix,
+                None => return false,
+            };
+
+            if self.edges[ix].2.simplify() {
+                self.shrink = GraphShrink::ShrinkEdge(ix);
+                self.prev_shrink = Some(self.shrink);
+                return true;
+            }
+            self.shrink = GraphShrink::ShrinkEdge(ix + 1);
+        }
+
+        panic!("Unexpected shrink state");
+    }
+
+    fn complicate(&mut self) -> bool {
+        match self.prev_shrink {
+            None => false,
+            Some(GraphShrink::DeleteNodes { .. }) => {
+                self.node_dim.undelete();
+                self.deletion_pending = false;
+                self.prev_shrink = None;
+                true
+            }
+            Some(GraphShrink::DeleteEdges { .. }) => {
+                self.edge_dim.undelete();
+                self.deletion_pending = false;
+                self.prev_shrink = None;
+                true
+            }
+            Some(GraphShrink::ShrinkNode(ix)) => {
+                if self.nodes[ix].complicate() {
+                    true
+                } else {
+                    self.prev_shrink = None;
+                    false
+                }
+            }
+            Some(GraphShrink::ShrinkEdge(ix)) => {
+                if self.edges[ix].2.complicate() {
+                    true
+                } else {
+                    self.prev_shrink = None;
+                    false
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::std_facade::vec;
+    use crate::strategy::Just;
+
+    fn is_connected<N, E>(graph: &Graph<N, E>) -> bool {
+        let adjacency = graph.adjacency();
+        let mut seen = BTreeSet::new();
+        let mut stack = Vec::new();
+        if graph.node_count() > 0 {
+            seen.insert(0);
+            stack.push(0);
+        }
+        while let Some(node) = stack.pop() {
+            for &(next, _) in &adjacency[node] {
+                if seen.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+        seen.len() == graph.node_count()
+    }
+
+    fn check_simple<N, E>(graph: &Graph<N, E>) {
+        let mut pairs = BTreeSet::new();
+        for &(from, to, _) in graph.edges() {
+            assert!(from != to, "self loop at {}", from);
+            assert!(from < graph.node_count() && to < graph.node_count());
+            assert!(graph.is_directed() || from < to);
+            assert!(pairs.insert((from, to)), "duplicate edge");
+        }
+    }
+
+    #[test]
+    fn generates_graphs_of_requested_size() {
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..256 {
+            let g = directed(Just(()), Just(()), 0..10, 0..20)
+                .new_tree(&mut runner)
+                .unwrap()
+                .current();
+            check_simple(&g);
+            assert!(g.node_count() < 10 && g.edge_count() < 20);
+
+            let g = undirected(Just(()), Just(()), 3..=3, 5..10)
+                .new_tree(&mut runner)
+                .unwrap()
+                .current();
+            check_simple(&g);
+            // Only 3 possible edges among 3 nodes.
+            assert_eq!(3, g.edge_count());
+        }
+    }
+
+    #[test]
+    fn dag_edges_follow_topological_order() {
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..256 {
+            let g = dag(Just(()), Just(()), 1..20, 0..50)
+                .new_tree(&mut runner)
+                .unwrap()
+                .current();
+            check_simple(&g);
+            assert!(g.edges().iter().all(|&(from, to, _)| from < to));
+        }
+    }
+
+    #[test]
+    fn trees_and_forests_are_acyclic() {
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..256 {
+            let g = tree(Just(()), Just(()), 1..30)
+                .new_tree(&mut runner)
+                .unwrap()
+                .current();
+            check_simple(&g);
+            assert_eq!(g.node_count() - 1, g.edge_count());
+            assert!(is_connected(&g));
+
+            let g = forest(Just(()), Just(()), 0..30, 0..30)
+                .new_tree(&mut runner)
+                .unwrap()
+                .current();
+            check_simple(&g);
+            // Every node has at most one parent.
+            let mut children = BTreeSet::new();
+            for &(parent, child, _) in g.edges() {
+                assert!(parent < child);
+                assert!(children.insert(child));
+            }
+        }
+    }
+
+    #[test]
+    fn connected_graphs_stay_connected_while_shrinking() {
+        let input = connected(0..100u32, 0..100u32, 1..20, 0..60);
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..64 {
+            let case = input.new_tree(&mut runner).unwrap();
+            let result = runner.run_one(case, |g| {
+                check_simple(&g);
+                assert!(is_connected(&g), "{:?}", g);
+                prop_assert!(g.node_count() < 4);
+                Ok(())
+            });
+
+            match result {
+                Ok(_) => (),
+                Err(TestError::Fail(_, g)) => {
+                    // A path or star over 4 nodes, with zero payloads.
+                    assert_eq!(vec![0; 4], g.nodes());
+                    assert_eq!(3, g.edge_count());
+                    assert!(g.edges().iter().all(|e| 0 == e.2));
+                }
+                result => panic!("Unexpected result: {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn trees_shrink_by_deleting_leaves() {
+        let input = tree(Just(()), 0..100u32, 1..30);
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..64 {
+            let case = input.new_tree(&mut runner).unwrap();
+            let result = runner.run_one(case, |g| {
+                assert_eq!(g.node_count() - 1, g.edge_count());
+                assert!(is_connected(&g), "{:?}", g);
+                prop_assert!(g.edges().iter().all(|e| e.2 < 50));
+                Ok(())
+            });
+
+            match result {
+                Ok(_) => (),
+                Err(TestError::Fail(_, g)) => {
+                    assert_eq!(vec![(0, 1, 50)], g.edges())
+                }
+                result => panic!("Unexpected result: {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn minimum_edges_kept_while_shrinking() {
+        let input = directed(Just(()), Just(()), 5..10, 4..30);
+        let mut runner = TestRunner::deterministic();
+        let case = input.new_tree(&mut runner).unwrap();
+        let result = runner.run_one(case, |g| {
+            check_simple(&g);
+            assert!(g.edge_count() >= 4);
+            Err(TestCaseError::fail("always"))
+        });
+
+        match result {
+            Err(TestError::Fail(_, g)) => {
+                assert_eq!(5, g.node_count());
+                assert_eq!(4, g.edge_count());
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn adjacency_lists_both_directions_of_undirected_edges() {
+        let graph = Graph {
+            nodes: vec!['a', 'b', 'c'],
+            edges: vec![(0, 1, 5), (1, 2, 7)],
+            directed: false,
+        };
+        assert_eq!(
+            vec![vec![(1, &5)], vec![(0, &5), (2, &7)], vec![(1, &7)]],
+            graph.adjacency()
+        );
+        assert_eq!(vec![0, 2], graph.neighbors(1));
+
+        let graph = Graph {
+            directed: true,
+            ..graph
+        };
+        assert_eq!(
+            vec![vec![(1, &5)], vec![(2, &7)], vec![]],
+            graph.adjacency()
+        );
+    }
+
+    #[test]
+    fn test_sanity() {
+        check_strategy_sanity(connected(0..10u8, 0..10u8, 1..10, 0..20), None);
+        check_strategy_sanity(dag(0..10u8, 0..10u8, 0..10, 0..20), None);
+    }
+}
@@ -0,0 +1,510 @@
+//-
+// Copyright 2021 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Property suites for the laws of standard traits.
+//!
+//! Implementations of `PartialEq`, `Ord`, `Hash`, `Clone` or `FromStr` are
+//! expected to follow laws which the compiler cannot check, such as `Ord`
+//! agreeing with `Eq`, or equal values having equal hashes. `Laws` runs the
+//! properties expressing those laws through a `TestRunner`, and reports the
+//! first violated law by name along with the minimal values violating it.
+//!
+//! ```
+//! use proptest::laws;
+//! use proptest::strategy::Strategy;
+//!
+//! #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+//! struct Version(u8, u8, u8);
+//!
+//! let laws = laws::with_strategy(
+//!     (0..3u8, 0..3u8, 0..3u8).prop_map(|(a, b, c)| Version(a, b, c)));
+//! laws.check_eq().unwrap();
+//! laws.check_ord().unwrap();
+//! laws.check_hash().unwrap();
+//! laws.check_clone().unwrap();
+//! ```
+//!
+//! Laws relating two or three values, such as transitivity, draw values which
+//! are clones of each other most of the time, since independently generated
+//! values are rarely equal.
+//!
+//! Proptest does not depend on serde, so there is no serde-specific check.
+//! Serde formats are checked with `check_roundtrip()`, which works with any
+//! encoding, for example with `serde_json::to_string` and
+//! `serde_json::from_str`.
+
+use crate::std_facade::{fmt, String, ToOwned};
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::str::FromStr;
+use std::collections::hash_map::DefaultHasher;
+
+use crate::arbitrary::{any, Arbitrary};
+use crate::strategy::Strategy;
+use crate::test_runner::{Config, TestCaseError, TestError, TestRunner};
+
+/// Returns the law suites for the values of `any::<T>()`.
+pub fn of<T: Arbitrary>() -> Laws<T::Strategy> {
+    with_strategy(any::<T>())
+}
+
+/// Returns the law suites for the values of `strategy`.
+///
+/// The suites run with the default configuration, except that failures are
+/// not persisted; use `Laws::with_config()` to change it.
+pub fn with_strategy<S: Strategy>(strategy: S) -> Laws<S> {
+    Laws {
+        strategy,
+        config: Config {
+            failure_persistence: None,
+            ..Config::default()
+        },
+    }
+}
+
+/// A law which does not hold, as reported by the checks of `Laws`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LawViolation {
+    /// The name of the law, such as `Ord: transitivity`.
+    pub law: String,
+    /// How the law is violated, with the minimal values violating it, or why
+    /// the check could not run.
+    pub message: String,
+}
+
+impl fmt::Display for LawViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "law `{}` violated: {}", self.law, self.message)
+    }
+}
+
+impl ::std::error::Error for LawViolation {}
+
+/// The law suites for the values of a strategy, as returned by `of()` and
+/// `with_strategy()`.
+///
+/// Each `check_*` method checks the laws of one trait, one law at a time,
+/// and returns the first violation found.
+#[derive(Clone, Debug)]
+pub struct Laws<S> {
+    strategy: S,
+    config: Config,
+}
+
+type Holds = Result<(), String>;
+
+impl<S: Strategy> Laws<S> {
+    /// Runs the suites with `config` instead of the default configuration.
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    fn check<T: fmt::Debug>(
+        &self,
+        law: &str,
+        strategy: impl Strategy<Value = T>,
+        holds: impl Fn(T) -> Holds,
+    ) -> Result<(), LawViolation> {
+        let mut runner = TestRunner::new(self.config.clone());
+        let result = runner
+            .run(&strategy, |input| holds(input).map_err(TestCaseError::fail));
+        let message = match result {
+            Ok(()) => return Ok(()),
+            Err(TestError::Fail(why, _)) => why.message().to_owned(),
+            Err(TestError::Flaky {
+                reason,
+                passes,
+                failures,
+                ..
+            }) => format!(
+                "{} (flaky: passed {} of {} re-runs)",
+                reason.message(),
+                passes,
+                passes + failures
+            ),
+            Err(TestError::Abort(why)) => format!("aborted: {}", why),
+        };
+        Err(LawViolation {
+            law: law.to_owned(),
+            message,
+        })
+    }
+
+    /// Checks a law about one value.
+    fn check1(
+        &self,
+        law: &str,
+        holds: impl Fn(S::Value) -> Holds,
+    ) -> Result<(), LawViolation> {
+        self.check(law, &self.strategy, holds)
+    }
+
+    /// Checks a law about three values, `b` and `c` usually being clones of
+    /// `a` or of each other.
+    fn check3(
+        &self,
+        law: &str,
+        holds: impl Fn(&S::Value, &S::Value, &S::Value) -> Holds,
+    ) -> Result<(), LawViolation>
+    where
+        S::Value: Clone,
+    {
+        let strategy = (&self.strategy, &self.strategy, &self.strategy, 0..4u8);
+        self.check(law, strategy, |(a, b, c, equal)| {
+            let (b, c) = match equal {
+                0 => (a.clone(), a.clone()),
+                1 => (a.clone(), c),
+                2 => (b.clone(), b),
+                _ => (b, c),
+            };
+            holds(&a, &b, &c)
+        })
+    }
+
+    /// Checks that `==` is reflexive, symmetric and transitive, and that `!=`
+    /// is its negation.
+    #[allow(clippy::eq_op)]
+    pub fn check_eq(&self) -> Result<(), LawViolation>
+    where
+        S::Value: Eq + Clone,
+    {
+        self.check1("Eq: reflexivity", |a| {
+            if a == a {
+                Ok(())
+            } else {
+                Err(format!("{:?} != itself", a))
+            }
+        })?;
+        self.check3("PartialEq: symmetry", |a, b, _| {
+            if (a == b) == (b == a) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "a == b is {} but b == a is not, with a = {:?} \
+                     and b = {:?}",
+                    a == b,
+                    a,
+                    b
+                ))
+            }
+        })?;
+        self.check3("PartialEq: transitivity", |a, b, c| {
+            if a != b || b != c || a == c {
+                Ok(())
+            } else {
+                Err(format!(
+                    "a == b and b == c but a != c, with a = {:?}, \
+                     b = {:?} and c = {:?}",
+                    a, b, c
+                ))
+            }
+        })?;
+        self.check3("PartialEq: ne is the negation of eq", |a, b, _| {
+            if (a != b) != (a == b) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "a == b and a != b are both {}, with a = {:?} \
+                     and b = {:?}",
+                    a == b,
+                    a,
+                    b
+                ))
+            }
+        })
+    }
+
+    /// Checks that `cmp()` agrees with `==` and `partial_cmp()`, is
+    /// antisymmetric and transitive, and that `max()` and `min()` agree with
+    /// it.
+    pub fn check_ord(&self) -> Result<(), LawViolation>
+    where
+        S::Value: Ord + Clone,
+    {
+        self.check3("Ord: consistency with Eq", |a, b, _| {
+            if (Ordering::Equal == a.cmp(b)) == (a == b) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "a.cmp(b) is {:?} but a == b is {}, with a = \
+                     {:?} and b = {:?}",
+                    a.cmp(b),
+                    a == b,
+                    a,
+                    b
+                ))
+            }
+        })?;
+        self.check3("Ord: consistency with PartialOrd", |a, b, _| {
+            if Some(a.cmp(b)) == a.partial_cmp(b) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "a.cmp(b) is {:?} but a.partial_cmp(b) is \
+                     {:?}, with a = {:?} and b = {:?}",
+                    a.cmp(b),
+                    a.partial_cmp(b),
+                    a,
+                    b
+                ))
+            }
+        })?;
+        self.check3("Ord: antisymmetry", |a, b, _| {
+            if a.cmp(b) == b.cmp(a).reverse() {
+                Ok(())
+            } else {
+                Err(format!(
+                    "a.cmp(b) is {:?} but b.cmp(a) is {:?}, with \
+                     a = {:?} and b = {:?}",
+                    a.cmp(b),
+                    b.cmp(a),
+                    a,
+                    b
+                ))
+            }
+        })?;
+        self.check3("Ord: transitivity", |a, b, c| {
+            if a > b || b > c || a <= c {
+                Ok(())
+            } else {
+                Err(format!(
+                    "a <= b and b <= c but a > c, with a = {:?}, \
+                     b = {:?} and c = {:?}",
+                    a, b, c
+                ))
+            }
+        })?;
+        self.check3("Ord: max and min", |a, b, _| {
+            let (max, min) =
+                (a.clone().max(b.clone()), a.clone().min(b.clone()));
+            let (expected_max, expected_min) =
+                if a > b { (a, b) } else { (b, a) };
+            if max == *expected_max && min == *expected_min {
+                Ok(())
+            } else {
+                Err(format!(
+                    "max(a, b) is {:?} and min(a, b) is {:?}, with \
+                     a = {:?} and b = {:?}",
+                    max, min, a, b
+                ))
+            }
+        })
+    }
+
+    /// Checks that equal values have equal hashes.
+    pub fn check_hash(&self) -> Result<(), LawViolation>
+    where
+        S::Value: Hash + Eq + Clone,
+    {
+        fn hash<T: Hash>(value: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        self.check3("Hash: consistency with Eq", |a, b, _| {
+            if a != b || hash(a) == hash(b) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "a == b but their hashes differ, with a = {:?} \
+                     and b = {:?}",
+                    a, b
+                ))
+            }
+        })
+    }
+
+    /// Checks that clones are equal to the original value.
+    pub fn check_clone(&self) -> Result<(), LawViolation>
+    where
+        S::Value: Clone + PartialEq,
+    {
+        self.check1("Clone: equality with the original", |a| {
+            let clone = a.clone();
+            if clone == a {
+                Ok(())
+            } else {
+                Err(format!("the clone of {:?} is {:?}", a, clone))
+            }
+        })
+    }
+
+    /// Checks that parsing the `Display` representation of a value gives
+    /// back the value.
+    pub fn check_from_str(&self) -> Result<(), LawViolation>
+    where
+        S::Value: fmt::Display + FromStr + PartialEq,
+        <S::Value as FromStr>::Err: fmt::Debug,
+    {
+        self.check_roundtrip(
+            "FromStr: round-trip of Display",
+            |a| format!("{}", a),
+            |s| s.parse::<S::Value>(),
+        )
+    }
+
+    /// Checks that decoding the result of encoding a value gives back the
+    /// value, under the law name `law`.
+    ///
+    /// This covers any serialisation format:
+    ///
+    /// ```
+    /// use proptest::laws;
+    ///
+    /// laws::of::<u32>()
+    ///     .check_roundtrip(
+    ///         "to_be_bytes round-trip",
+    ///         |n| n.to_be_bytes(),
+    ///         |bytes| Ok::<_, ()>(u32::from_be_bytes(bytes)),
+    ///     )
+    ///     .unwrap();
+    /// ```
+    pub fn check_roundtrip<E: fmt::Debug, Err: fmt::Debug>(
+        &self,
+        law: &str,
+        encode: impl Fn(&S::Value) -> E,
+        decode: impl Fn(E) -> Result<S::Value, Err>,
+    ) -> Result<(), LawViolation>
+    where
+        S::Value: PartialEq,
+    {
+        self.check1(law, |a| {
+            let encoded = encode(&a);
+            let description = format!("{:?}", encoded);
+            match decode(encoded) {
+                Ok(ref decoded) if *decoded == a => Ok(()),
+                Ok(decoded) => Err(format!(
+                    "{:?} was encoded as {} and decoded as {:?}",
+                    a, description, decoded
+                )),
+                Err(err) => Err(format!(
+                    "{:?} was encoded as {}, which failed to decode: {:?}",
+                    a, description, err
+                )),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::strategy::Strategy;
+
+    /// Equal by `id` only, but ordered and hashed by `weight` too.
+    #[derive(Clone, Debug)]
+    struct Inconsistent {
+        id: u8,
+        weight: u8,
+    }
+
+    impl PartialEq for Inconsistent {
+        fn eq(&self, other: &Self) -> bool {
+            self.id == other.id
+        }
+    }
+
+    impl Eq for Inconsistent {}
+
+    impl PartialOrd for Inconsistent {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Inconsistent {
+        fn cmp(&self, other: &Self) -> Ordering {
+            (self.id, self.weight).cmp(&(other.id, other.weight))
+        }
+    }
+
+    impl Hash for Inconsistent {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.id.hash(state);
+            self.weight.hash(state);
+        }
+    }
+
+    fn inconsistent() -> Laws<impl Strategy<Value = Inconsistent>> {
+        with_strategy(
+            (0..4u8, 0..4u8)
+                .prop_map(|(id, weight)| Inconsistent { id, weight }),
+        )
+    }
+
+    #[test]
+    fn lawful_types_pass() {
+        let laws = of::<i32>();
+        assert_eq!(Ok(()), laws.check_eq());
+        assert_eq!(Ok(()), laws.check_ord());
+        assert_eq!(Ok(()), laws.check_hash());
+        assert_eq!(Ok(()), laws.check_clone());
+        assert_eq!(Ok(()), laws.check_from_str());
+        assert_eq!(Ok(()), of::<String>().check_from_str());
+        assert_eq!(Ok(()), of::<(bool, char)>().check_hash());
+
+        // Equal by `id` only, which is still a lawful equivalence.
+        assert_eq!(Ok(()), inconsistent().check_eq());
+    }
+
+    #[test]
+    fn reports_ord_disagreeing_with_eq() {
+        let violation = inconsistent().check_ord().unwrap_err();
+        assert_eq!("Ord: consistency with Eq", violation.law);
+        let message = format!("{}", violation);
+        assert!(
+            message.starts_with("law `Ord: consistency with Eq` violated: "),
+            "{}",
+            message
+        );
+        assert!(message.contains("but a == b is true, with a = "));
+    }
+
+    #[test]
+    fn reports_hash_disagreeing_with_eq() {
+        let violation = inconsistent().check_hash().unwrap_err();
+        assert_eq!("Hash: consistency with Eq", violation.law);
+        assert!(violation
+            .message
+            .starts_with("a == b but their hashes differ, with a = "));
+    }
+
+    #[test]
+    fn reports_failed_roundtrips() {
+        let violation = of::<f64>()
+            .check_roundtrip(
+                "f32 round-trip",
+                |&v| v as f32,
+                |v| Ok::<_, ()>(f64::from(v)),
+            )
+            .unwrap_err();
+        assert_eq!("f32 round-trip", violation.law);
+        assert!(violation.message.contains("was encoded as"));
+
+        let violation = with_strategy(crate::strategy::Just(-1i8))
+            .check_roundtrip(
+                "unsigned",
+                |&v| v as u8,
+                |v| {
+                    if v < 128 {
+                        Ok(v as i8)
+                    } else {
+                        Err("too big")
+                    }
+                },
+            )
+            .unwrap_err();
+        assert_eq!(
+            "-1 was encoded as 255, which failed to decode: \"too big\"",
+            violation.message
+        );
+    }
+}
@@ -94,12 +94,23 @@ pub mod bits;
 pub mod bool;
 pub mod char;
 pub mod collection;
+pub mod covering;
+pub mod generate;
+pub mod graph;
 pub mod num;
 pub mod strategy;
 pub mod test_runner;
 pub mod tuple;
 
+#[cfg(feature = "fs")]
+pub mod fs;
+#[cfg(feature = "std")]
+pub mod function;
+#[cfg(feature = "std")]
+pub mod laws;
 pub mod option;
+#[cfg(feature = "std")]
+pub mod path;
 pub mod result;
 pub mod sample;
 #[cfg(feature = "std")]
@@ -28,6 +28,26 @@ macro_rules! mapfn {
     }
 }
 
+// Implements the hidden `Strategy` methods of an `Enumerable` strategy by
+// deferring to its `Enumerable` implementation.
+macro_rules! enumerable_strategy {
+    () => {
+        fn enumerable_domain_size(&self) -> Option<u64> {
+            $crate::strategy::Enumerable::domain_size(self)
+        }
+
+        fn enumerable_new_tree_at(
+            &self,
+            index: u64,
+            runner: &mut $crate::test_runner::TestRunner,
+        ) -> Option<$crate::strategy::NewTree<Self>> {
+            Some($crate::strategy::Enumerable::new_tree_at(
+                self, index, runner,
+            ))
+        }
+    };
+}
+
 macro_rules! delegate_vt_0 {
     () => {
         fn current(&self) -> Self::Value {
@@ -71,6 +91,20 @@ macro_rules! opaque_strategy_wrapper {
             fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
                 self.0.new_tree(runner).map($vtname)
             }
+
+            fn enumerable_domain_size(&self) -> Option<u64> {
+                self.0.enumerable_domain_size()
+            }
+
+            fn enumerable_new_tree_at(
+                &self,
+                index: u64,
+                runner: &mut TestRunner,
+            ) -> Option<NewTree<Self>> {
+                self.0
+                    .enumerable_new_tree_at(index, runner)
+                    .map(|tree| tree.map($vtname))
+            }
         }
 
         $(#[$allmeta])*
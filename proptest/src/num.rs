@@ -10,10 +10,11 @@
 //! Strategies to generate numeric values (as opposed to integers used as bit
 //! fields).
 //!
-//! All strategies in this module shrink by binary searching towards 0.
+//! All strategies in this module shrink by binary searching towards 0, except
+//! for those for non-zero integers, which shrink towards 1 or -1.
 
 use crate::test_runner::TestRunner;
-use core::ops::Range;
+use core::ops::{Bound, Range, RangeBounds};
 use rand::distributions::uniform::{SampleUniform, Uniform};
 use rand::distributions::{Distribution, Standard};
 
@@ -49,12 +50,19 @@ macro_rules! int_any {
             fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
                 Ok(BinarySearch::new(runner.rng().gen()))
             }
+
+            enumerable_strategy!();
         }
     };
 }
 
 macro_rules! numeric_api {
     ($typ:ident, $epsilon:expr) => {
+        numeric_api!($typ, $epsilon, {});
+    };
+    // `$enumerable` is added to the `Strategy` impls of the ranges, which are
+    // only `Enumerable` for integers.
+    ($typ:ident, $epsilon:expr, {$($enumerable:tt)*}) => {
         impl Strategy for ::core::ops::Range<$typ> {
             type Tree = BinarySearch;
             type Value = $typ;
@@ -66,6 +74,8 @@ macro_rules! numeric_api {
                     self.end - $epsilon,
                 ))
             }
+
+            $($enumerable)*
         }
 
         impl Strategy for ::core::ops::RangeInclusive<$typ> {
@@ -83,6 +93,8 @@ macro_rules! numeric_api {
                     *self.end(),
                 ))
             }
+
+            $($enumerable)*
         }
 
         impl Strategy for ::core::ops::RangeFrom<$typ> {
@@ -100,6 +112,8 @@ macro_rules! numeric_api {
                     ::core::$typ::MAX,
                 ))
             }
+
+            $($enumerable)*
         }
 
         impl Strategy for ::core::ops::RangeTo<$typ> {
@@ -116,6 +130,8 @@ macro_rules! numeric_api {
                     self.end,
                 ))
             }
+
+            $($enumerable)*
         }
 
         impl Strategy for ::core::ops::RangeToInclusive<$typ> {
@@ -133,12 +149,158 @@ macro_rules! numeric_api {
                     self.end,
                 ))
             }
+
+            $($enumerable)*
+        }
+    };
+}
+
+macro_rules! int_enumerable {
+    ($typ:ident, $utyp:ident) => {
+        /// Returns the number of values in `lo..=hi`, or `None` if it does
+        /// not fit in a `u64`.
+        fn domain_size_incl(lo: $typ, hi: $typ) -> Option<u64> {
+            if lo > hi {
+                return Some(0);
+            }
+
+            let size = (hi.wrapping_sub(lo) as $utyp as u128).checked_add(1)?;
+            if size <= u128::from(u64::MAX) {
+                Some(size as u64)
+            } else {
+                None
+            }
+        }
+
+        /// Returns the value `index` places after `lo`.
+        fn value_at(lo: $typ, index: u64, size: Option<u64>) -> $typ {
+            assert!(
+                size.map_or(true, |size| index < size),
+                "Index out of range of the domain"
+            );
+            (lo as $utyp).wrapping_add(index as $utyp) as $typ
+        }
+
+        impl Enumerable for Any {
+            fn domain_size(&self) -> Option<u64> {
+                domain_size_incl($typ::MIN, $typ::MAX)
+            }
+
+            fn new_tree_at(
+                &self,
+                index: u64,
+                _: &mut TestRunner,
+            ) -> NewTree<Self> {
+                Ok(BinarySearch::new(value_at(
+                    $typ::MIN,
+                    index,
+                    self.domain_size(),
+                )))
+            }
+        }
+
+        impl Enumerable for ::core::ops::Range<$typ> {
+            fn domain_size(&self) -> Option<u64> {
+                if self.start >= self.end {
+                    Some(0)
+                } else {
+                    domain_size_incl(self.start, self.end - 1)
+                }
+            }
+
+            fn new_tree_at(
+                &self,
+                index: u64,
+                _: &mut TestRunner,
+            ) -> NewTree<Self> {
+                Ok(BinarySearch::new_clamped(
+                    self.start,
+                    value_at(self.start, index, self.domain_size()),
+                    self.end - 1,
+                ))
+            }
+        }
+
+        impl Enumerable for ::core::ops::RangeInclusive<$typ> {
+            fn domain_size(&self) -> Option<u64> {
+                domain_size_incl(*self.start(), *self.end())
+            }
+
+            fn new_tree_at(
+                &self,
+                index: u64,
+                _: &mut TestRunner,
+            ) -> NewTree<Self> {
+                Ok(BinarySearch::new_clamped(
+                    *self.start(),
+                    value_at(*self.start(), index, self.domain_size()),
+                    *self.end(),
+                ))
+            }
+        }
+
+        impl Enumerable for ::core::ops::RangeFrom<$typ> {
+            fn domain_size(&self) -> Option<u64> {
+                domain_size_incl(self.start, $typ::MAX)
+            }
+
+            fn new_tree_at(
+                &self,
+                index: u64,
+                _: &mut TestRunner,
+            ) -> NewTree<Self> {
+                Ok(BinarySearch::new_clamped(
+                    self.start,
+                    value_at(self.start, index, self.domain_size()),
+                    $typ::MAX,
+                ))
+            }
+        }
+
+        impl Enumerable for ::core::ops::RangeTo<$typ> {
+            fn domain_size(&self) -> Option<u64> {
+                if $typ::MIN == self.end {
+                    Some(0)
+                } else {
+                    domain_size_incl($typ::MIN, self.end - 1)
+                }
+            }
+
+            fn new_tree_at(
+                &self,
+                index: u64,
+                _: &mut TestRunner,
+            ) -> NewTree<Self> {
+                Ok(BinarySearch::new_clamped(
+                    $typ::MIN,
+                    value_at($typ::MIN, index, self.domain_size()),
+                    self.end,
+                ))
+            }
+        }
+
+        impl Enumerable for ::core::ops::RangeToInclusive<$typ> {
+            fn domain_size(&self) -> Option<u64> {
+                domain_size_incl($typ::MIN, self.end)
+            }
+
+            fn new_tree_at(
+                &self,
+                index: u64,
+                _: &mut TestRunner,
+            ) -> NewTree<Self> {
+                Ok(BinarySearch::new_clamped(
+                    $typ::MIN,
+                    value_at($typ::MIN, index, self.domain_size()),
+                    self.end,
+                ))
+            }
         }
     };
 }
 
 macro_rules! signed_integer_bin_search {
-    ($typ:ident) => {
+    ($typ:ident, $utyp:ident) => {
         #[allow(missing_docs)]
         pub mod $typ {
             use rand::Rng;
@@ -169,7 +331,11 @@ macro_rules! signed_integer_bin_search {
                 /// Creates a new binary searcher which will not produce values
                 /// on the other side of `lo` or `hi` from `start`. `lo` is
                 /// inclusive, `hi` is exclusive.
-                fn new_clamped(lo: $typ, start: $typ, hi: $typ) -> Self {
+                pub(crate) fn new_clamped(
+                    lo: $typ,
+                    start: $typ,
+                    hi: $typ,
+                ) -> Self {
                     use core::cmp::{max, min};
 
                     BinarySearch {
@@ -234,7 +400,10 @@ macro_rules! signed_integer_bin_search {
                 }
             }
 
-            numeric_api!($typ, 1);
+            numeric_api!($typ, 1, {
+                enumerable_strategy!();
+            });
+            int_enumerable!($typ, $utyp);
         }
     };
 }
@@ -270,7 +439,11 @@ macro_rules! unsigned_integer_bin_search {
 
                 /// Creates a new binary searcher which will not search below
                 /// the given `lo` value.
-                fn new_clamped(lo: $typ, start: $typ, _hi: $typ) -> Self {
+                pub(crate) fn new_clamped(
+                    lo: $typ,
+                    start: $typ,
+                    _hi: $typ,
+                ) -> Self {
                     BinarySearch {
                         lo: lo,
                         curr: start,
@@ -322,18 +495,21 @@ macro_rules! unsigned_integer_bin_search {
                 }
             }
 
-            numeric_api!($typ, 1);
+            numeric_api!($typ, 1, {
+                enumerable_strategy!();
+            });
+            int_enumerable!($typ, $typ);
         }
     };
 }
 
-signed_integer_bin_search!(i8);
-signed_integer_bin_search!(i16);
-signed_integer_bin_search!(i32);
-signed_integer_bin_search!(i64);
+signed_integer_bin_search!(i8, u8);
+signed_integer_bin_search!(i16, u16);
+signed_integer_bin_search!(i32, u32);
+signed_integer_bin_search!(i64, u64);
 #[cfg(not(target_arch = "wasm32"))]
-signed_integer_bin_search!(i128);
-signed_integer_bin_search!(isize);
+signed_integer_bin_search!(i128, u128);
+signed_integer_bin_search!(isize, usize);
 unsigned_integer_bin_search!(u8);
 unsigned_integer_bin_search!(u16);
 unsigned_integer_bin_search!(u32);
@@ -342,6 +518,179 @@ unsigned_integer_bin_search!(u64);
 unsigned_integer_bin_search!(u128);
 unsigned_integer_bin_search!(usize);
 
+fn non_zero_bounds<T: Copy>(
+    range: &impl RangeBounds<T>,
+    min: T,
+    max: T,
+    succ: impl Fn(T) -> Option<T>,
+    pred: impl Fn(T) -> Option<T>,
+) -> (T, T) {
+    let lo = match range.start_bound() {
+        Bound::Included(&lo) => lo,
+        Bound::Excluded(&lo) => succ(lo).expect("Empty range"),
+        Bound::Unbounded => min,
+    };
+    let hi = match range.end_bound() {
+        Bound::Included(&hi) => hi,
+        Bound::Excluded(&hi) => pred(hi).expect("Empty range"),
+        Bound::Unbounded => max,
+    };
+    (lo, hi)
+}
+
+macro_rules! non_zero_bin_search {
+    ($module:ident, $typ:ident, $prim:ident) => {
+        #[allow(missing_docs)]
+        pub mod $module {
+            use core::num::$typ;
+            use core::ops::RangeBounds;
+
+            use crate::strategy::*;
+            use crate::test_runner::TestRunner;
+
+            /// Type of the `ANY` constant.
+            #[derive(Clone, Copy, Debug)]
+            #[must_use = "strategies do nothing unless used"]
+            pub struct Any(());
+            /// Generates non-zero integers with completely arbitrary values,
+            /// uniformly distributed over the whole range.
+            pub const ANY: Any = Any(());
+
+            impl Strategy for Any {
+                type Tree = BinarySearch;
+                type Value = $typ;
+
+                fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+                    range(..).new_tree(runner)
+                }
+            }
+
+            /// Strategy to generate non-zero integers within a range.
+            ///
+            /// Created by the `range()` function in the same module.
+            #[derive(Clone, Copy, Debug)]
+            #[must_use = "strategies do nothing unless used"]
+            pub struct Range {
+                lo: $prim,
+                hi: $prim,
+            }
+
+            /// Generates non-zero integers uniformly distributed over
+            /// `range`, e.g., `range(1..100)`. Zero is skipped even if it lies
+            /// within `range`.
+            ///
+            /// ## Panics
+            ///
+            /// Panics if `range` does not contain any non-zero integer.
+            pub fn range(range: impl RangeBounds<$prim>) -> Range {
+                let (lo, hi) = super::non_zero_bounds(
+                    &range,
+                    ::core::$prim::MIN,
+                    ::core::$prim::MAX,
+                    |v| v.checked_add(1),
+                    |v| v.checked_sub(1),
+                );
+                assert!(
+                    lo <= hi && !(0 == lo && 0 == hi),
+                    "Range contains no non-zero integers"
+                );
+                Range { lo, hi }
+            }
+
+            impl Strategy for Range {
+                type Tree = BinarySearch;
+                type Value = $typ;
+
+                // Zero is the minimum of unsigned types.
+                #[allow(unused_comparisons)]
+                #[allow(clippy::absurd_extreme_comparisons)]
+                fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+                    // Sample from one fewer values if the range contains zero,
+                    // then skip over it.
+                    let contains_zero = self.lo <= 0 && 0 <= self.hi;
+                    let hi = if contains_zero { self.hi - 1 } else { self.hi };
+                    let mut start =
+                        crate::num::sample_uniform_incl(runner, self.lo, hi);
+                    if contains_zero && start >= 0 {
+                        start += 1;
+                    }
+                    Ok(BinarySearch::new_in(self.lo, start, self.hi))
+                }
+            }
+
+            /// Shrinks a non-zero integer towards 1 (or -1 if it is
+            /// negative), using binary search to find boundary points.
+            #[derive(Clone, Copy, Debug)]
+            pub struct BinarySearch(super::$prim::BinarySearch);
+
+            impl BinarySearch {
+                /// Creates a new binary searcher starting at the given value.
+                pub fn new(start: $typ) -> Self {
+                    BinarySearch::new_in(
+                        ::core::$prim::MIN,
+                        start.get(),
+                        ::core::$prim::MAX,
+                    )
+                }
+
+                /// Creates a new binary searcher which will not produce values
+                /// outside of `lo..=hi`.
+                #[allow(unused_comparisons)]
+                #[allow(clippy::absurd_extreme_comparisons)]
+                fn new_in(lo: $prim, start: $prim, hi: $prim) -> Self {
+                    let inner = if start < 0 {
+                        // The searcher's upper bound is exclusive.
+                        super::$prim::BinarySearch::new_clamped(
+                            lo,
+                            start,
+                            if hi < 0 { hi + 1 } else { 0 },
+                        )
+                    } else {
+                        super::$prim::BinarySearch::new_clamped(
+                            lo.max(1),
+                            start,
+                            hi,
+                        )
+                    };
+                    BinarySearch(inner)
+                }
+            }
+
+            impl ValueTree for BinarySearch {
+                type Value = $typ;
+
+                fn current(&self) -> $typ {
+                    $typ::new(self.0.current())
+                        .expect("Non-zero binary search produced zero")
+                }
+
+                fn simplify(&mut self) -> bool {
+                    self.0.simplify()
+                }
+
+                fn complicate(&mut self) -> bool {
+                    self.0.complicate()
+                }
+            }
+        }
+    };
+}
+
+non_zero_bin_search!(non_zero_i8, NonZeroI8, i8);
+non_zero_bin_search!(non_zero_i16, NonZeroI16, i16);
+non_zero_bin_search!(non_zero_i32, NonZeroI32, i32);
+non_zero_bin_search!(non_zero_i64, NonZeroI64, i64);
+#[cfg(not(target_arch = "wasm32"))]
+non_zero_bin_search!(non_zero_i128, NonZeroI128, i128);
+non_zero_bin_search!(non_zero_isize, NonZeroIsize, isize);
+non_zero_bin_search!(non_zero_u8, NonZeroU8, u8);
+non_zero_bin_search!(non_zero_u16, NonZeroU16, u16);
+non_zero_bin_search!(non_zero_u32, NonZeroU32, u32);
+non_zero_bin_search!(non_zero_u64, NonZeroU64, u64);
+#[cfg(not(target_arch = "wasm32"))]
+non_zero_bin_search!(non_zero_u128, NonZeroU128, u128);
+non_zero_bin_search!(non_zero_usize, NonZeroUsize, usize);
+
 bitflags! {
     pub(crate) struct FloatTypes: u32 {
         const POSITIVE          = 0b0000_0001;
@@ -852,6 +1201,7 @@ float_bin_search!(f64);
 
 #[cfg(test)]
 mod test {
+    use crate::std_facade::{vec, Vec};
     use crate::strategy::*;
     use crate::test_runner::*;
 
@@ -1031,6 +1381,120 @@ mod test {
         }
     }
 
+    #[test]
+    fn non_zero_never_shrinks_to_zero() {
+        let mut runner = TestRunner::default();
+        for _ in 0..100 {
+            let mut state = non_zero_i32::ANY.new_tree(&mut runner).unwrap();
+            let negative = state.current().get() < 0;
+            while state.simplify() {
+                assert_eq!(negative, state.current().get() < 0);
+            }
+            assert_eq!(if negative { -1 } else { 1 }, state.current().get());
+
+            let mut state = non_zero_u8::ANY.new_tree(&mut runner).unwrap();
+            while state.simplify() {}
+            assert_eq!(1, state.current().get());
+        }
+    }
+
+    #[test]
+    fn non_zero_range_stays_in_bounds() {
+        let mut runner = TestRunner::default();
+        for _ in 0..100 {
+            let mut state =
+                non_zero_i32::range(-42..=-5).new_tree(&mut runner).unwrap();
+            loop {
+                let v = state.current().get();
+                assert!(v >= -42 && v <= -5, "Violated bounds: {}", v);
+                if !state.simplify() {
+                    break;
+                }
+            }
+            assert_eq!(-5, state.current().get());
+
+            let mut state =
+                non_zero_u32::range(10..100).new_tree(&mut runner).unwrap();
+            loop {
+                let v = state.current().get();
+                assert!(v >= 10 && v < 100, "Violated bounds: {}", v);
+                if !state.simplify() {
+                    break;
+                }
+            }
+            assert_eq!(10, state.current().get());
+        }
+    }
+
+    #[test]
+    fn non_zero_range_skips_zero() {
+        let mut runner = TestRunner::deterministic();
+        let mut seen = [false; 3];
+        for _ in 0..100 {
+            let v = non_zero_i8::range(-1..=1)
+                .new_tree(&mut runner)
+                .unwrap()
+                .current()
+                .get();
+            seen[(v + 1) as usize] = true;
+        }
+        assert_eq!([true, false, true], seen);
+    }
+
+    #[test]
+    #[should_panic]
+    fn non_zero_range_of_only_zero_panics() {
+        let _ = non_zero_u16::range(0..1);
+    }
+
+    #[test]
+    fn non_zero_binsearch_simplify_complicate_contract_upheld() {
+        check_strategy_sanity(non_zero_i32::range(-1000..1000), None);
+        check_strategy_sanity(non_zero_u32::range(1..2), None);
+        check_strategy_sanity(non_zero_u64::ANY, None);
+    }
+
+    #[test]
+    fn int_ranges_enumerate_every_value() {
+        let mut runner = TestRunner::deterministic();
+        let mut values = |strategy: &::core::ops::RangeInclusive<i8>| {
+            (0..strategy.domain_size().unwrap())
+                .map(|ix| strategy.new_tree_at(ix, &mut runner).unwrap())
+                .map(|tree| tree.current())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec![-2, -1, 0, 1, 2], values(&(-2..=2)));
+        assert_eq!(256, values(&(i8::MIN..=i8::MAX)).len());
+
+        assert_eq!(Some(3), (5u32..8).domain_size());
+        assert_eq!(Some(0), (5u32..5).domain_size());
+        assert_eq!(Some(1 << 32), u32::ANY.domain_size());
+        assert_eq!(Some(u64::MAX), (0u64..u64::MAX).domain_size());
+        assert_eq!(None, (0u64..=u64::MAX).domain_size());
+        assert_eq!(Some(128), (..0i8).domain_size());
+        assert_eq!(
+            i64::MAX,
+            (..=i64::MAX)
+                .new_tree_at(u64::MAX, &mut runner)
+                .unwrap()
+                .current()
+        );
+    }
+
+    #[test]
+    fn enumerated_int_trees_shrink_within_range() {
+        let mut runner = TestRunner::deterministic();
+        let mut tree = (-10i32..-5).new_tree_at(4, &mut runner).unwrap();
+        assert_eq!(-6, tree.current());
+        while tree.simplify() {}
+        assert_eq!(-6, tree.current());
+
+        let mut tree = (3u16..10).new_tree_at(5, &mut runner).unwrap();
+        assert_eq!(8, tree.current());
+        while tree.simplify() {}
+        assert_eq!(3, tree.current());
+    }
+
     mod contract_sanity {
         macro_rules! contract_sanity {
             ($t:tt) => {
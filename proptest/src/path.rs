@@ -0,0 +1,371 @@
+//-
+// Copyright 2021 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Strategies for generating `std::path::PathBuf` values.
+//!
+//! Paths are generated as an optional root followed by a sequence of
+//! components and an optional trailing separator. They shrink towards
+//! relative paths with fewer and shorter components made of simpler
+//! characters.
+//!
+//! Generated paths are syntactically valid, but are not checked against any
+//! file system; e.g., components may contain characters Windows does not
+//! allow in file names.
+
+use crate::std_facade::{Box, String};
+use core::fmt;
+use std::ffi::OsString;
+use std::path::{PathBuf, MAIN_SEPARATOR};
+
+use crate::bool;
+use crate::char;
+use crate::collection::{vec, SizeRange};
+use crate::sample::select;
+use crate::strategy::*;
+use crate::test_runner::*;
+
+/// Whether generated paths are absolute or relative.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PathKind {
+    /// Only generate relative paths.
+    Relative,
+    /// Only generate absolute paths.
+    ///
+    /// On Windows, absolute paths start with `C:\`.
+    Absolute,
+    /// Generate both relative and absolute paths, shrinking towards relative
+    /// paths.
+    Any,
+}
+
+/// The characters used in normal (i.e., not `.` or `..`) path components.
+///
+/// Separators and NUL never occur in components.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PathCharset {
+    /// The POSIX portable file name character set: ASCII letters, digits,
+    /// `.`, `_` and `-`.
+    Portable,
+    /// Printable ASCII characters, including spaces.
+    Ascii,
+    /// Any Unicode character.
+    Unicode,
+}
+
+/// Parameters for generating paths with `paths()`.
+///
+/// The `Default` generates relative and absolute paths of up to 7 components
+/// of 1 to 8 Unicode characters each, including `.` and `..` components and
+/// trailing separators, but no non-UTF-8 components.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PathParams {
+    /// Whether paths are absolute or relative.
+    pub kind: PathKind,
+    /// The number of components in each path, not counting the root.
+    pub depth: SizeRange,
+    /// The number of characters (or bytes, for non-UTF-8 components) in each
+    /// normal component.
+    pub component_len: SizeRange,
+    /// The characters used in normal components.
+    pub charset: PathCharset,
+    /// Whether to generate `.` and `..` components.
+    pub dot_components: bool,
+    /// Whether to sometimes end paths with a separator.
+    pub trailing_separator: bool,
+    /// Whether to generate components which are not valid UTF-8.
+    ///
+    /// This only has an effect on Unix, where paths are arbitrary byte
+    /// strings.
+    pub non_utf8: bool,
+}
+
+impl Default for PathParams {
+    fn default() -> Self {
+        PathParams {
+            kind: PathKind::Any,
+            depth: (0..8).into(),
+            component_len: (1..9).into(),
+            charset: PathCharset::Unicode,
+            dot_components: true,
+            trailing_separator: true,
+            non_utf8: false,
+        }
+    }
+}
+
+impl PathParams {
+    /// Parameters for relative paths, otherwise the same as the `Default`.
+    pub fn relative() -> Self {
+        PathParams {
+            kind: PathKind::Relative,
+            ..PathParams::default()
+        }
+    }
+
+    /// Parameters for absolute paths, otherwise the same as the `Default`.
+    pub fn absolute() -> Self {
+        PathParams {
+            kind: PathKind::Absolute,
+            ..PathParams::default()
+        }
+    }
+}
+
+opaque_strategy_wrapper! {
+    /// Strategy to create `PathBuf`s.
+    ///
+    /// Created by the `paths()` function in the same module.
+    #[derive(Debug)]
+    pub struct PathStrategy[][](SBoxedStrategy<PathBuf>)
+        -> PathValueTree;
+    /// `ValueTree` corresponding to `PathStrategy`.
+    pub struct PathValueTree[][](Box<dyn ValueTree<Value = PathBuf>>)
+        -> PathBuf;
+}
+
+impl fmt::Debug for PathValueTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("PathValueTree")
+            .field(&self.0.current())
+            .finish()
+    }
+}
+
+#[cfg(windows)]
+const ROOT: &str = "C:\\";
+#[cfg(not(windows))]
+const ROOT: &str = "/";
+
+const PORTABLE_CHARS: &[char] = &[
+    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o',
+    'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', 'A', 'B', 'C', 'D',
+    'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S',
+    'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '0', '1', '2', '3', '4', '5', '6', '7',
+    '8', '9', '.', '_', '-',
+];
+
+fn is_separator(ch: char) -> bool {
+    ch == '/' || (cfg!(windows) && ch == '\\')
+}
+
+fn component_char(charset: PathCharset) -> SBoxedStrategy<char> {
+    let chars = match charset {
+        PathCharset::Portable => return select(PORTABLE_CHARS).sboxed(),
+        PathCharset::Ascii => char::range(' ', '~'),
+        PathCharset::Unicode => char::any(),
+    };
+    chars
+        .prop_map(|ch| {
+            if is_separator(ch) || '\0' == ch {
+                '_'
+            } else {
+                ch
+            }
+        })
+        .sboxed()
+}
+
+fn normal_component(params: &PathParams) -> SBoxedStrategy<OsString> {
+    vec(component_char(params.charset), params.component_len.clone())
+        .prop_map(|chars| {
+            let name = chars.into_iter().collect::<String>();
+            // Names consisting only of dots would not be normal components.
+            if "." == name || ".." == name {
+                OsString::from("_")
+            } else {
+                OsString::from(name)
+            }
+        })
+        .sboxed()
+}
+
+#[cfg(unix)]
+fn non_utf8_component(params: &PathParams) -> SBoxedStrategy<OsString> {
+    use std::os::unix::ffi::OsStringExt;
+
+    // Bytes of 0x80 and above never form valid UTF-8 on their own, and can
+    // never be mistaken for a separator or NUL.
+    vec(0x80u8..=0xFF, params.component_len.clone())
+        .prop_map(OsString::from_vec)
+        .sboxed()
+}
+
+#[cfg(not(unix))]
+fn non_utf8_component(params: &PathParams) -> SBoxedStrategy<OsString> {
+    normal_component(params)
+}
+
+fn component(params: &PathParams) -> SBoxedStrategy<OsString> {
+    let mut arms = vec![(8, normal_component(params))];
+    if params.dot_components {
+        arms.push((1, Just(OsString::from(".")).sboxed()));
+        arms.push((1, Just(OsString::from("..")).sboxed()));
+    }
+    if params.non_utf8 {
+        arms.push((2, non_utf8_component(params)));
+    }
+    Union::new_weighted(arms).sboxed()
+}
+
+/// Create a strategy to generate `PathBuf`s as described by `params`.
+///
+/// ```
+/// use proptest::path::{paths, PathParams};
+/// use proptest::prelude::*;
+///
+/// proptest! {
+///     fn relative_paths_stay_relative(path in paths(PathParams::relative())) {
+///         prop_assert!(path.is_relative());
+///     }
+/// }
+/// # fn main() { relative_paths_stay_relative(); }
+/// ```
+pub fn paths(params: PathParams) -> PathStrategy {
+    let absolute = match params.kind {
+        PathKind::Relative => Just(false).sboxed(),
+        PathKind::Absolute => Just(true).sboxed(),
+        PathKind::Any => bool::ANY.sboxed(),
+    };
+    let trailing = if params.trailing_separator {
+        bool::weighted(0.2).sboxed()
+    } else {
+        Just(false).sboxed()
+    };
+
+    PathStrategy(
+        (
+            absolute,
+            vec(component(&params), params.depth.clone()),
+            trailing,
+        )
+            .prop_map(|(absolute, components, trailing)| {
+                let separator = String::from(MAIN_SEPARATOR);
+                let mut path = OsString::new();
+                if absolute {
+                    path.push(ROOT);
+                }
+                for (ix, component) in components.iter().enumerate() {
+                    if ix > 0 {
+                        path.push(&separator);
+                    }
+                    path.push(component);
+                }
+                if trailing && !components.is_empty() {
+                    path.push(&separator);
+                }
+                PathBuf::from(path)
+            })
+            .sboxed(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Component, Path};
+    use std::vec::Vec;
+
+    use super::*;
+
+    fn gen_paths(params: PathParams) -> Vec<PathBuf> {
+        let strategy = paths(params);
+        let mut runner = TestRunner::deterministic();
+        (0..256)
+            .map(|_| strategy.new_tree(&mut runner).unwrap().current())
+            .collect()
+    }
+
+    fn raw_components(path: &Path) -> Vec<String> {
+        let path = path.to_string_lossy();
+        let path = path.strip_prefix(ROOT).unwrap_or(&path);
+        path.split(MAIN_SEPARATOR)
+            .filter(|c| !c.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn kind_respected() {
+        assert!(gen_paths(PathParams::relative())
+            .iter()
+            .all(|p| p.is_relative()));
+        assert!(gen_paths(PathParams::absolute())
+            .iter()
+            .all(|p| p.is_absolute()));
+
+        let any = gen_paths(PathParams::default());
+        assert!(any.iter().any(|p| p.is_relative()));
+        assert!(any.iter().any(|p| p.is_absolute()));
+    }
+
+    #[test]
+    fn depth_and_charset_respected() {
+        let params = PathParams {
+            depth: (2..4).into(),
+            component_len: (1..3).into(),
+            charset: PathCharset::Portable,
+            dot_components: false,
+            ..PathParams::relative()
+        };
+        for path in gen_paths(params) {
+            let components = raw_components(&path);
+            assert!(components.len() >= 2 && components.len() < 4);
+            for component in components {
+                assert!(component.len() < 3);
+                assert!(component != "." && component != "..");
+                assert!(component.chars().all(|c| PORTABLE_CHARS.contains(&c)));
+            }
+        }
+    }
+
+    #[test]
+    fn special_components_generated() {
+        let paths = gen_paths(PathParams::default());
+        assert!(paths
+            .iter()
+            .any(|p| p.components().any(|c| c == Component::ParentDir)));
+        assert!(paths
+            .iter()
+            .any(|p| p.to_string_lossy().ends_with(MAIN_SEPARATOR)));
+        assert!(paths.iter().any(|p| p.to_string_lossy().contains(' ')));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_generated_only_when_requested() {
+        assert!(gen_paths(PathParams::default())
+            .iter()
+            .all(|p| p.to_str().is_some()));
+        assert!(gen_paths(PathParams {
+            non_utf8: true,
+            ..PathParams::default()
+        })
+        .iter()
+        .any(|p| p.to_str().is_none()));
+    }
+
+    #[test]
+    fn shrinks_to_fewer_shorter_components() {
+        let mut runner = TestRunner::default();
+        let result = runner.run(&paths(PathParams::default()), |path| {
+            prop_assert!(raw_components(&path).len() < 2);
+            Ok(())
+        });
+
+        match result {
+            Err(TestError::Fail(_, path)) => {
+                assert!(path.is_relative());
+                let components = raw_components(&path);
+                assert_eq!(2, components.len());
+                assert!(components.iter().all(|c| 1 == c.chars().count()));
+                assert!(!path.to_string_lossy().ends_with(MAIN_SEPARATOR));
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+}
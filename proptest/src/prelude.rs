@@ -23,9 +23,11 @@
 pub use crate::arbitrary::{any, any_with, Arbitrary};
 pub use crate::strategy::{BoxedStrategy, Just, SBoxedStrategy, Strategy};
 pub use crate::test_runner::Config as ProptestConfig;
-pub use crate::test_runner::TestCaseError;
+pub use crate::test_runner::{TestCaseError, TestCaseResultExt};
 pub use crate::{
-    prop_assert, prop_assert_eq, prop_assert_ne, prop_assume, prop_compose,
+    prop_assert, prop_assert_approx_eq, prop_assert_eq, prop_assert_ge,
+    prop_assert_gt, prop_assert_le, prop_assert_lt, prop_assert_matches,
+    prop_assert_ne, prop_assume, prop_compose, prop_context, prop_note,
     prop_oneof, proptest,
 };
 
@@ -41,8 +43,12 @@ pub mod prop {
     pub use crate::bool;
     pub use crate::char;
     pub use crate::collection;
+    #[cfg(feature = "fs")]
+    pub use crate::fs;
     pub use crate::num;
     pub use crate::option;
+    #[cfg(feature = "std")]
+    pub use crate::path;
     pub use crate::result;
     pub use crate::sample;
     pub use crate::strategy;
@@ -141,6 +141,20 @@ opaque_strategy_wrapper! {
         -> T;
 }
 
+impl<T: Clone + fmt::Debug + 'static> Enumerable for Select<T> {
+    fn domain_size(&self) -> Option<u64> {
+        self.0.domain_size()
+    }
+
+    fn new_tree_at(
+        &self,
+        index: u64,
+        runner: &mut TestRunner,
+    ) -> NewTree<Self> {
+        self.0.new_tree_at(index, runner).map(SelectValueTree)
+    }
+}
+
 /// Create a strategy which uniformly selects one value from `values`.
 ///
 /// `values` should be a `&'static [T]` or a `Vec<T>`, or potentially another
@@ -39,6 +39,7 @@ multiplex_alloc! {
     alloc::string, ::std::string,
     alloc::sync::Arc, ::std::sync::Arc,
     alloc::rc::Rc, ::std::rc::Rc,
+    alloc::rc::Weak, ::std::rc::Weak,
     alloc::vec::Vec, ::std::vec::Vec,
     alloc::vec, ::std::vec,
     alloc::collections::VecDeque, std::collections::VecDeque,
@@ -0,0 +1,68 @@
+//-
+// Copyright 2021 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::strategy::{NewTree, Strategy};
+use crate::test_runner::TestRunner;
+
+/// A `Strategy` whose values can be listed one by one.
+///
+/// Strategies over small domains, such as `bool`s, short integer ranges,
+/// `Just`, unions and tuples of those, implement this trait so that
+/// `TestRunner::run_exhaustive()` can test every value instead of sampling at
+/// random, which for tiny domains wastes cases on duplicates and can still
+/// miss combinations.
+///
+/// The values are numbered from 0 to `domain_size() - 1`. The value trees
+/// created for them shrink exactly like those from `new_tree()`.
+pub trait Enumerable: Strategy {
+    /// Returns the number of values this strategy can produce, or `None` if
+    /// that number does not fit in a `u64`.
+    fn domain_size(&self) -> Option<u64>;
+
+    /// Creates a value tree whose initial value is the value at `index`.
+    ///
+    /// `runner` is only used for the parts of the tree which are not
+    /// determined by `index`, such as the alternatives a union may shrink
+    /// into.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `index` is not less than `domain_size()`.
+    fn new_tree_at(&self, index: u64, runner: &mut TestRunner)
+        -> NewTree<Self>;
+}
+
+/// Calls `Strategy::enumerable_new_tree_at()` on a strategy whose
+/// `Strategy::enumerable_domain_size()` is known.
+pub(crate) fn enumerated_tree_at<S: Strategy + ?Sized>(
+    strategy: &S,
+    index: u64,
+    runner: &mut TestRunner,
+) -> NewTree<S> {
+    strategy
+        .enumerable_new_tree_at(index, runner)
+        .expect("Strategy has a domain size but cannot enumerate it")
+}
+
+/// Splits `index` into the position of the part of a domain it falls in and
+/// the index within that part, given the sizes of the parts in order.
+pub(crate) fn locate_index(
+    sizes: impl IntoIterator<Item = Option<u64>>,
+    mut index: u64,
+) -> (usize, u64) {
+    for (part, size) in sizes.into_iter().enumerate() {
+        let size = size.expect("Domain size overflows u64");
+        if index < size {
+            return (part, index);
+        }
+        index -= size;
+    }
+
+    panic!("Index out of range of the domain")
+}
@@ -0,0 +1,711 @@
+//-
+// Copyright 2021 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::std_facade::{fmt, Arc, Box, Rc, Vec, Weak};
+use core::any::Any;
+use core::cell::RefCell;
+use rand::seq::{index, SliceRandom};
+
+use crate::collection::SizeRange;
+use crate::strategy::lazy::LazyValueTree;
+use crate::strategy::traits::*;
+use crate::test_runner::*;
+
+/// Builder for strategies generating (possibly mutually) recursive types
+/// within a size budget.
+///
+/// Each recursive type is first declared with `declare()`, which returns a
+/// `FixpointRef`. The `FixpointRef`s are strategies which can be used to build
+/// the strategies for the recursive cases of any of the types, which are then
+/// given to `define()` together with a strategy for the non-recursive cases.
+/// Finally, `strategy()` creates a strategy for one of the types.
+///
+/// Unlike `Strategy::prop_recursive()`, which controls the size of values
+/// through branch probabilities, every generated value is given a budget of
+/// nodes drawn uniformly from a `SizeRange` (scaled according to
+/// `TestRunner::size()`), where each value produced through
+/// a `FixpointRef` counts as one node. A node with a budget of one uses the
+/// non-recursive case. A node with a larger budget uses the recursive case,
+/// and its budget, less the node itself, is split randomly among its children.
+/// To find out how many children there are, the recursive case is first
+/// generated once with non-recursive children.
+///
+/// If the recursive case turns out to have more children than the budget
+/// allows, or none at all, it is generated again a few times, which favours
+/// recursive cases with fewer children for smaller budgets. Thus the number of
+/// nodes is usually exactly the budget. It may differ when no recursive case
+/// fitting the budget is found (the children beyond the budget use the
+/// non-recursive case), or when filters in recursive cases look at the
+/// children, since they see different children the first time.
+///
+/// Shrinking tries replacing each recursive node with a non-recursive one
+/// before shrinking its children.
+///
+/// ## Example
+///
+/// ```
+/// use proptest::prelude::*;
+/// use proptest::strategy::Fixpoint;
+///
+/// #[derive(Clone, Debug)]
+/// enum Expr {
+///     Lit(i32),
+///     Add(Box<Expr>, Box<Expr>),
+///     Block(Vec<Stmt>, Box<Expr>),
+/// }
+///
+/// #[derive(Clone, Debug)]
+/// enum Stmt {
+///     Pass,
+///     Eval(Expr),
+///     If(Expr, Vec<Stmt>),
+/// }
+///
+/// let mut fix = Fixpoint::new();
+/// let expr = fix.declare::<Expr>();
+/// let stmt = fix.declare::<Stmt>();
+/// fix.define(
+///     &expr,
+///     any::<i32>().prop_map(Expr::Lit),
+///     prop_oneof![
+///         (expr.clone(), expr.clone())
+///             .prop_map(|(a, b)| Expr::Add(Box::new(a), Box::new(b))),
+///         (prop::collection::vec(stmt.clone(), 0..4), expr.clone())
+///             .prop_map(|(body, e)| Expr::Block(body, Box::new(e))),
+///     ],
+/// );
+/// fix.define(
+///     &stmt,
+///     Just(Stmt::Pass),
+///     prop_oneof![
+///         expr.clone().prop_map(Stmt::Eval),
+///         (expr.clone(), prop::collection::vec(stmt.clone(), 0..4))
+///             .prop_map(|(cond, body)| Stmt::If(cond, body)),
+///     ],
+/// );
+///
+/// // Programs of up to 8 statements, each of up to 32 nodes.
+/// let programs = prop::collection::vec(fix.strategy(&stmt, 1..=32), 0..8);
+/// # let _ = programs;
+/// ```
+pub struct Fixpoint {
+    state: Rc<FixpointState>,
+    declared: usize,
+    defined: usize,
+}
+
+struct FixpointState {
+    /// The children of the node being generated.
+    children: RefCell<Children>,
+    /// The definitions of all declared types. `FixpointRef`s only hold weak
+    /// references to them, since the definitions usually contain
+    /// `FixpointRef`s themselves.
+    definitions: RefCell<Vec<Rc<dyn Any>>>,
+}
+
+struct Definition<T> {
+    leaf: Arc<BoxedStrategy<T>>,
+    branch: BoxedStrategy<T>,
+}
+
+type DefinitionCell<T> = RefCell<Option<Definition<T>>>;
+
+enum Children {
+    /// The children are being counted, and generate non-recursive values.
+    Counting(usize),
+    /// The budgets of the children not generated yet, in reverse order.
+    Budgets(Vec<usize>),
+}
+
+impl fmt::Debug for Fixpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Fixpoint")
+            .field("declared", &self.declared)
+            .field("defined", &self.defined)
+            .finish()
+    }
+}
+
+impl Default for Fixpoint {
+    fn default() -> Self {
+        Fixpoint {
+            state: Rc::new(FixpointState {
+                children: RefCell::new(Children::Budgets(Vec::new())),
+                definitions: RefCell::new(Vec::new()),
+            }),
+            declared: 0,
+            defined: 0,
+        }
+    }
+}
+
+impl Fixpoint {
+    /// Create a new builder without any declared types.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare a recursive type, returning a strategy for it which may be
+    /// used in the definition of any of the types of this builder.
+    ///
+    /// The type must be defined with `define()` before calling `strategy()`.
+    pub fn declare<T: fmt::Debug + 'static>(&mut self) -> FixpointRef<T> {
+        let definition: Rc<DefinitionCell<T>> = Rc::new(RefCell::new(None));
+        let weak = Rc::downgrade(&definition);
+        self.state.definitions.borrow_mut().push(definition);
+        self.declared += 1;
+        FixpointRef {
+            state: Rc::downgrade(&self.state),
+            definition: weak,
+        }
+    }
+
+    /// Define the type declared as `declared`.
+    ///
+    /// `leaf` generates the non-recursive cases of the type, and must not use
+    /// any `FixpointRef`s. `branch` generates the recursive cases, and
+    /// normally uses the `FixpointRef`s of this builder to generate the
+    /// children.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `declared` was not declared by this builder, or if it is
+    /// already defined.
+    pub fn define<T: fmt::Debug + 'static>(
+        &mut self,
+        declared: &FixpointRef<T>,
+        leaf: impl Strategy<Value = T> + 'static,
+        branch: impl Strategy<Value = T> + 'static,
+    ) {
+        assert!(
+            declared.belongs_to(&self.state),
+            "FixpointRef defined with a different Fixpoint"
+        );
+        let definition = declared.definition();
+        let mut definition = definition.borrow_mut();
+        assert!(definition.is_none(), "FixpointRef defined twice");
+        *definition = Some(Definition {
+            leaf: Arc::new(leaf.boxed()),
+            branch: branch.boxed(),
+        });
+        self.defined += 1;
+    }
+
+    /// Create a strategy generating values of the type declared as `root`,
+    /// with a budget of nodes drawn uniformly from `size`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `root` was not declared by this builder, if any declared
+    /// type has not been defined, or if `size` allows a budget of zero nodes.
+    pub fn strategy<T: fmt::Debug + 'static>(
+        &self,
+        root: &FixpointRef<T>,
+        size: impl Into<SizeRange>,
+    ) -> FixpointStrategy<T> {
+        assert!(
+            root.belongs_to(&self.state),
+            "FixpointRef used with a different Fixpoint"
+        );
+        assert_eq!(
+            self.declared, self.defined,
+            "Fixpoint::strategy() called with undefined types"
+        );
+        let size = size.into();
+        size.assert_nonempty();
+        assert!(size.start() > 0, "Fixpoint size must be at least 1");
+
+        FixpointStrategy {
+            state: Rc::clone(&self.state),
+            root: root.clone(),
+            size,
+        }
+    }
+}
+
+/// Strategy for a type declared with `Fixpoint::declare()`.
+///
+/// Outside of the strategies created by `Fixpoint::strategy()`, there is no
+/// budget and this always generates non-recursive values.
+pub struct FixpointRef<T> {
+    state: Weak<FixpointState>,
+    definition: Weak<DefinitionCell<T>>,
+}
+
+impl<T> Clone for FixpointRef<T> {
+    fn clone(&self) -> Self {
+        FixpointRef {
+            state: Weak::clone(&self.state),
+            definition: Weak::clone(&self.definition),
+        }
+    }
+}
+
+impl<T> fmt::Debug for FixpointRef<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FixpointRef").finish()
+    }
+}
+
+impl<T: fmt::Debug + 'static> FixpointRef<T> {
+    fn belongs_to(&self, state: &Rc<FixpointState>) -> bool {
+        match self.state.upgrade() {
+            Some(own) => Rc::ptr_eq(&own, state),
+            None => false,
+        }
+    }
+
+    fn state(&self) -> Rc<FixpointState> {
+        self.state
+            .upgrade()
+            .expect("FixpointRef used after its Fixpoint was dropped")
+    }
+
+    fn definition(&self) -> Rc<DefinitionCell<T>> {
+        self.definition
+            .upgrade()
+            .expect("FixpointRef used after its Fixpoint was dropped")
+    }
+
+    /// Generate a node with the given budget.
+    fn generate(
+        &self,
+        state: &FixpointState,
+        budget: usize,
+        runner: &mut TestRunner,
+    ) -> Result<FixpointValueTree<T>, Reason> {
+        let (leaf, branch) = match *self.definition().borrow() {
+            Some(ref definition) => {
+                (Arc::clone(&definition.leaf), definition.branch.clone())
+            }
+            None => panic!("FixpointRef used before being defined"),
+        };
+
+        if budget <= 1 {
+            return Ok(FixpointValueTree(Node::Leaf(leaf.new_tree(runner)?)));
+        }
+
+        // Children are generated from RNGs forked off `runner`, so the
+        // recursive case makes the same choices whatever its children are.
+        // This allows counting the children with a dry run on a copy of
+        // `runner` before splitting the budget among them. If the children
+        // cannot use the budget exactly, try again with a different RNG state
+        // a few times.
+        let outer = state.children.replace(Children::Counting(0));
+        let mut attempts = 0;
+        let (count, mut dry_runner) = loop {
+            let mut dry_runner = runner.clone();
+            *state.children.borrow_mut() = Children::Counting(0);
+            let _ = branch.new_tree(&mut dry_runner);
+            let count = match *state.children.borrow() {
+                Children::Counting(count) => count,
+                Children::Budgets(_) => unreachable!(),
+            };
+
+            attempts += 1;
+            if (count > 0 && count < budget) || attempts >= MAX_DRY_RUNS {
+                break (count, dry_runner);
+            }
+            let _ = runner.new_rng();
+        };
+
+        let budgets = split(budget - 1, count, &mut dry_runner);
+        *state.children.borrow_mut() = Children::Budgets(budgets);
+        let tree = branch.new_tree(runner);
+        *state.children.borrow_mut() = outer;
+
+        Ok(FixpointValueTree(Node::Branch {
+            branch: tree?,
+            leaf: Box::new(LazyValueTree::new(leaf, runner)),
+            phase: BranchPhase::Initial,
+        }))
+    }
+}
+
+/// The number of times to try to find a recursive case whose number of
+/// children fits the budget of a node.
+const MAX_DRY_RUNS: u32 = 8;
+
+/// Split `total` into `count` random budgets, in reverse order.
+///
+/// All splits in which each budget is at least one are equally likely. If
+/// `total` is less than `count`, the budgets are zero or one.
+fn split(total: usize, count: usize, runner: &mut TestRunner) -> Vec<usize> {
+    if count > total {
+        let mut budgets = vec![0; count];
+        for budget in &mut budgets[..total] {
+            *budget = 1;
+        }
+        budgets.shuffle(runner.rng());
+        return budgets;
+    }
+
+    if 0 == count {
+        return Vec::new();
+    }
+
+    // Choose where to cut `total` into `count` pieces.
+    let mut cuts = index::sample(runner.rng(), total - 1, count - 1)
+        .into_iter()
+        .map(|ix| ix + 1)
+        .collect::<Vec<_>>();
+    cuts.sort_unstable();
+    cuts.push(total);
+
+    let mut budgets = Vec::with_capacity(count);
+    let mut prev = 0;
+    for cut in cuts {
+        budgets.push(cut - prev);
+        prev = cut;
+    }
+    budgets.reverse();
+    budgets
+}
+
+impl<T: fmt::Debug + 'static> Strategy for FixpointRef<T> {
+    type Tree = FixpointValueTree<T>;
+    type Value = T;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let state = self.state();
+        let budget = match *state.children.borrow_mut() {
+            Children::Counting(ref mut count) => {
+                *count += 1;
+                0
+            }
+            Children::Budgets(ref mut budgets) => budgets.pop().unwrap_or(0),
+        };
+        self.generate(&state, budget, &mut runner.partial_clone())
+    }
+}
+
+/// Strategy returned by `Fixpoint::strategy()`.
+#[must_use = "strategies do nothing unless used"]
+pub struct FixpointStrategy<T> {
+    state: Rc<FixpointState>,
+    root: FixpointRef<T>,
+    size: SizeRange,
+}
+
+impl<T> Clone for FixpointStrategy<T> {
+    fn clone(&self) -> Self {
+        FixpointStrategy {
+            state: Rc::clone(&self.state),
+            root: self.root.clone(),
+            size: self.size.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for FixpointStrategy<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FixpointStrategy")
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+impl<T: fmt::Debug + 'static> Strategy for FixpointStrategy<T> {
+    type Tree = FixpointValueTree<T>;
+    type Value = T;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let budget = self.size.sample(runner);
+        // Values of this strategy may be nested in values of another strategy
+        // of the same `Fixpoint`, so the children of the node being generated
+        // by that need to be restored afterwards.
+        let outer = self.state.children.replace(Children::Budgets(Vec::new()));
+        let tree = self.root.generate(&self.state, budget, runner);
+        *self.state.children.borrow_mut() = outer;
+        tree
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BranchPhase {
+    /// Shrinking has not started.
+    Initial,
+    /// The last simplification switched to the non-recursive case.
+    TryingLeaf,
+    /// The non-recursive case is being shrunk.
+    Leaf,
+    /// The recursive case is being shrunk.
+    Branch,
+}
+
+/// `ValueTree` corresponding to `FixpointRef` and `FixpointStrategy`.
+pub struct FixpointValueTree<T: fmt::Debug>(Node<T>);
+
+enum Node<T: fmt::Debug> {
+    Leaf(Box<dyn ValueTree<Value = T>>),
+    Branch {
+        branch: Box<dyn ValueTree<Value = T>>,
+        leaf: Box<LazyValueTree<BoxedStrategy<T>>>,
+        phase: BranchPhase,
+    },
+}
+
+impl<T: fmt::Debug> fmt::Debug for FixpointValueTree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("FixpointValueTree")
+            .field(&self.current())
+            .finish()
+    }
+}
+
+impl<T: fmt::Debug> ValueTree for FixpointValueTree<T> {
+    type Value = T;
+
+    fn current(&self) -> T {
+        match self.0 {
+            Node::Leaf(ref tree) => tree.current(),
+            Node::Branch {
+                ref branch,
+                ref leaf,
+                phase,
+            } => match (phase, leaf.as_inner()) {
+                (BranchPhase::TryingLeaf, Some(leaf))
+                | (BranchPhase::Leaf, Some(leaf)) => leaf.current(),
+                _ => branch.current(),
+            },
+        }
+    }
+
+    fn simplify(&mut self) -> bool {
+        match self.0 {
+            Node::Leaf(ref mut tree) => tree.simplify(),
+            Node::Branch {
+                ref mut branch,
+                ref mut leaf,
+                ref mut phase,
+            } => match *phase {
+                BranchPhase::Initial => {
+                    leaf.maybe_init();
+                    if leaf.is_initialized() {
+                        *phase = BranchPhase::TryingLeaf;
+                        true
+                    } else {
+                        *phase = BranchPhase::Branch;
+                        branch.simplify()
+                    }
+                }
+                BranchPhase::TryingLeaf | BranchPhase::Leaf => {
+                    *phase = BranchPhase::Leaf;
+                    match leaf.as_inner_mut() {
+                        Some(leaf) => leaf.simplify(),
+                        None => false,
+                    }
+                }
+                BranchPhase::Branch => branch.simplify(),
+            },
+        }
+    }
+
+    fn complicate(&mut self) -> bool {
+        match self.0 {
+            Node::Leaf(ref mut tree) => tree.complicate(),
+            Node::Branch {
+                ref mut branch,
+                ref mut leaf,
+                ref mut phase,
+            } => match *phase {
+                BranchPhase::Initial => false,
+                BranchPhase::TryingLeaf => {
+                    *phase = BranchPhase::Branch;
+                    true
+                }
+                BranchPhase::Leaf => match leaf.as_inner_mut() {
+                    Some(leaf) => leaf.complicate(),
+                    None => false,
+                },
+                BranchPhase::Branch => branch.complicate(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::strategy::just::Just;
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Expr {
+        Lit(u32),
+        Neg(Box<Expr>),
+        Add(Box<Expr>, Box<Expr>),
+        Block(Vec<Stmt>),
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Stmt {
+        Pass,
+        Eval(Expr),
+        Seq(Box<Stmt>, Box<Stmt>),
+    }
+
+    impl Expr {
+        fn nodes(&self) -> usize {
+            1 + match *self {
+                Expr::Lit(_) => 0,
+                Expr::Neg(ref e) => e.nodes(),
+                Expr::Add(ref a, ref b) => a.nodes() + b.nodes(),
+                Expr::Block(ref body) => body.iter().map(Stmt::nodes).sum(),
+            }
+        }
+
+        fn max_lit(&self) -> u32 {
+            match *self {
+                Expr::Lit(n) => n,
+                Expr::Neg(ref e) => e.max_lit(),
+                Expr::Add(ref a, ref b) => a.max_lit().max(b.max_lit()),
+                Expr::Block(ref body) => {
+                    body.iter().map(Stmt::max_lit).max().unwrap_or(0)
+                }
+            }
+        }
+    }
+
+    impl Stmt {
+        fn nodes(&self) -> usize {
+            1 + match *self {
+                Stmt::Pass => 0,
+                Stmt::Eval(ref e) => e.nodes(),
+                Stmt::Seq(ref a, ref b) => a.nodes() + b.nodes(),
+            }
+        }
+
+        fn max_lit(&self) -> u32 {
+            match *self {
+                Stmt::Pass => 0,
+                Stmt::Eval(ref e) => e.max_lit(),
+                Stmt::Seq(ref a, ref b) => a.max_lit().max(b.max_lit()),
+            }
+        }
+    }
+
+    fn builder() -> (Fixpoint, FixpointRef<Expr>, FixpointRef<Stmt>) {
+        let mut fix = Fixpoint::new();
+        let expr = fix.declare::<Expr>();
+        let stmt = fix.declare::<Stmt>();
+        fix.define(
+            &expr,
+            (0..1000u32).prop_map(Expr::Lit),
+            prop_oneof![
+                expr.clone().prop_map(|e| Expr::Neg(Box::new(e))),
+                (expr.clone(), expr.clone())
+                    .prop_map(|(a, b)| Expr::Add(Box::new(a), Box::new(b))),
+                crate::collection::vec(stmt.clone(), 1..4)
+                    .prop_map(Expr::Block),
+            ],
+        );
+        fix.define(
+            &stmt,
+            Just(Stmt::Pass),
+            prop_oneof![
+                expr.clone().prop_map(Stmt::Eval),
+                (stmt.clone(), stmt.clone())
+                    .prop_map(|(a, b)| Stmt::Seq(Box::new(a), Box::new(b))),
+            ],
+        );
+        (fix, expr, stmt)
+    }
+
+    #[test]
+    fn node_count_follows_budget() {
+        let (fix, expr, _) = builder();
+        let mut runner = TestRunner::deterministic();
+        for &size in &[1usize, 2, 10, 50] {
+            let strat = fix.strategy(&expr, size);
+            let counts = (0..256)
+                .map(|_| strat.new_tree(&mut runner).unwrap().current().nodes())
+                .collect::<Vec<_>>();
+            let exact = counts.iter().filter(|&&count| count == size).count();
+            assert!(exact >= 224, "size {}: {} exact", size, exact);
+            assert!(counts.iter().all(|&count| count <= size + 3));
+        }
+    }
+
+    #[test]
+    fn generates_both_types_at_all_levels() {
+        let (fix, _, stmt) = builder();
+        let strat = fix.strategy(&stmt, 10..50);
+        let mut runner = TestRunner::deterministic();
+        let mut nested_block = false;
+        for _ in 0..256 {
+            if let Stmt::Eval(Expr::Block(ref body)) =
+                strat.new_tree(&mut runner).unwrap().current()
+            {
+                nested_block |= body
+                    .iter()
+                    .any(|s| matches!(*s, Stmt::Eval(Expr::Block(_))));
+            }
+        }
+        assert!(nested_block);
+    }
+
+    #[test]
+    fn nested_strategies_restore_budget() {
+        let (fix, expr, _) = builder();
+        let inner = fix.strategy(&expr, 1);
+        let strat = (fix.strategy(&expr, 5), inner, fix.strategy(&expr, 5));
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..256 {
+            let (a, b, c) = strat.new_tree(&mut runner).unwrap().current();
+            assert_eq!(1, b.nodes());
+            assert!(a.nodes() <= 15 && c.nodes() <= 15);
+        }
+    }
+
+    #[test]
+    fn refs_outside_strategy_generate_leaves() {
+        let (_fix, expr, stmt) = builder();
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..256 {
+            assert_eq!(
+                1,
+                expr.new_tree(&mut runner).unwrap().current().nodes()
+            );
+            assert_eq!(
+                Stmt::Pass,
+                stmt.new_tree(&mut runner).unwrap().current()
+            );
+        }
+    }
+
+    #[test]
+    fn shrinks_to_minimal_leaf() {
+        let (fix, _, stmt) = builder();
+        let strat = fix.strategy(&stmt, 1..100);
+        let mut runner = TestRunner::deterministic();
+        let result = runner.run(&strat, |stmt| {
+            prop_assert!(stmt.max_lit() < 500);
+            Ok(())
+        });
+
+        match result {
+            Err(TestError::Fail(_, stmt)) => {
+                assert_eq!(Stmt::Eval(Expr::Lit(500)), stmt)
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "undefined types")]
+    fn strategy_requires_all_definitions() {
+        let mut fix = Fixpoint::new();
+        let expr = fix.declare::<Expr>();
+        let _stmt = fix.declare::<Stmt>();
+        fix.define(&expr, Just(Expr::Lit(0)), Just(Expr::Lit(1)));
+        let _ = fix.strategy(&expr, 1..10);
+    }
+}
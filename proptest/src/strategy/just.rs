@@ -9,7 +9,7 @@
 
 use crate::std_facade::fmt;
 
-use crate::strategy::{NewTree, Strategy, ValueTree};
+use crate::strategy::{Enumerable, NewTree, Strategy, ValueTree};
 use crate::test_runner::TestRunner;
 
 macro_rules! noshrink {
@@ -43,6 +43,19 @@ impl<T: Clone + fmt::Debug> Strategy for Just<T> {
     fn new_tree(&self, _: &mut TestRunner) -> NewTree<Self> {
         Ok(self.clone())
     }
+
+    enumerable_strategy!();
+}
+
+impl<T: Clone + fmt::Debug> Enumerable for Just<T> {
+    fn domain_size(&self) -> Option<u64> {
+        Some(1)
+    }
+
+    fn new_tree_at(&self, index: u64, _: &mut TestRunner) -> NewTree<Self> {
+        assert_eq!(0, index, "Index out of range of the domain");
+        Ok(self.clone())
+    }
 }
 
 impl<T: Clone + fmt::Debug> ValueTree for Just<T> {
@@ -93,6 +106,19 @@ impl<T: fmt::Debug, F: Clone + Fn() -> T> Strategy for LazyJust<T, F> {
     fn new_tree(&self, _: &mut TestRunner) -> NewTree<Self> {
         Ok(self.clone())
     }
+
+    enumerable_strategy!();
+}
+
+impl<T: fmt::Debug, F: Clone + Fn() -> T> Enumerable for LazyJust<T, F> {
+    fn domain_size(&self) -> Option<u64> {
+        Some(1)
+    }
+
+    fn new_tree_at(&self, index: u64, _: &mut TestRunner) -> NewTree<Self> {
+        assert_eq!(0, index, "Index out of range of the domain");
+        Ok(self.clone())
+    }
 }
 
 impl<T: fmt::Debug, F: Fn() -> T> ValueTree for LazyJust<T, F> {
@@ -12,6 +12,7 @@ use core::fmt;
 use core::marker::PhantomData;
 
 use crate::strategy::traits::*;
+use crate::strategy::Enumerable;
 use crate::test_runner::*;
 
 //==============================================================================
@@ -55,6 +56,41 @@ impl<S: Strategy, O: fmt::Debug, F: Fn(S::Value) -> O> Strategy for Map<S, F> {
             fun: Arc::clone(&self.fun),
         })
     }
+
+    fn enumerable_domain_size(&self) -> Option<u64> {
+        self.source.enumerable_domain_size()
+    }
+
+    fn enumerable_new_tree_at(
+        &self,
+        index: u64,
+        runner: &mut TestRunner,
+    ) -> Option<NewTree<Self>> {
+        let tree = self.source.enumerable_new_tree_at(index, runner)?;
+        Some(tree.map(|v| Map {
+            source: v,
+            fun: Arc::clone(&self.fun),
+        }))
+    }
+}
+
+impl<S: Enumerable, O: fmt::Debug, F: Fn(S::Value) -> O> Enumerable
+    for Map<S, F>
+{
+    fn domain_size(&self) -> Option<u64> {
+        self.source.domain_size()
+    }
+
+    fn new_tree_at(
+        &self,
+        index: u64,
+        runner: &mut TestRunner,
+    ) -> NewTree<Self> {
+        self.source.new_tree_at(index, runner).map(|v| Map {
+            source: v,
+            fun: Arc::clone(&self.fun),
+        })
+    }
 }
 
 impl<S: ValueTree, O: fmt::Debug, F: Fn(S::Value) -> O> ValueTree
@@ -9,29 +9,36 @@
 
 //! Defines the core traits used by Proptest.
 
+mod enumerable;
 mod filter;
 mod filter_map;
+mod fixpoint;
 mod flatten;
 mod fuse;
 mod just;
 mod lazy;
 mod map;
+mod preview;
 mod recursive;
 mod shuffle;
 mod traits;
 mod unions;
 
+pub use self::enumerable::*;
 pub use self::filter::*;
 pub use self::filter_map::*;
+pub use self::fixpoint::*;
 pub use self::flatten::*;
 pub use self::fuse::*;
 pub use self::just::*;
 pub use self::lazy::*;
 pub use self::lazy::*;
 pub use self::map::*;
+pub use self::preview::*;
 pub use self::recursive::*;
 pub use self::shuffle::*;
 pub use self::traits::*;
 pub use self::unions::*;
 
 pub mod statics;
+pub mod testing;
@@ -0,0 +1,487 @@
+//-
+// Copyright 2021 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Helpers for looking at what a strategy generates and how it shrinks while
+//! designing it, without writing a test.
+
+use crate::std_facade::{fmt, BTreeMap, String, ToOwned, Vec};
+
+use crate::generate::seeded_runner;
+use crate::strategy::{Strategy, ValueTree};
+use crate::test_runner::{TestCaseError, TestCaseResult, TestRunner};
+
+/// The width, in characters, of the longest bar of a `Histogram`.
+const BAR_WIDTH: usize = 40;
+
+/// Generates `n` values from `strategy` with `runner`.
+///
+/// ## Panics
+///
+/// Panics if `strategy` fails to generate a value.
+pub(crate) fn sample_values<S: Strategy + ?Sized>(
+    strategy: &S,
+    n: usize,
+    runner: &mut TestRunner,
+) -> Vec<S::Value> {
+    (0..n)
+        .map(|_| match strategy.new_tree(runner) {
+            Ok(tree) => tree.current(),
+            Err(why) => panic!("Failed to generate a value: {}", why),
+        })
+        .collect()
+}
+
+/// A textual histogram, as produced by `histogram()` or
+/// `histogram_by_key()`.
+///
+/// Its `Display` implementation renders one line per bucket, with a bar
+/// proportional to the number of values in the bucket.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Histogram {
+    rows: Vec<(String, usize)>,
+    total: usize,
+}
+
+impl Histogram {
+    /// Counts how many times each distinct key occurs in `keys`, with one
+    /// bucket per key in ascending order, labelled by the `Display`
+    /// representation of the key.
+    pub fn of_keys<K: Ord + fmt::Display>(
+        keys: impl IntoIterator<Item = K>,
+    ) -> Self {
+        let mut counts = BTreeMap::new();
+        let mut total = 0;
+        for key in keys {
+            *counts.entry(key).or_insert(0) += 1;
+            total += 1;
+        }
+
+        Histogram {
+            rows: counts
+                .into_iter()
+                .map(|(key, count)| (format!("{}", key), count))
+                .collect(),
+            total,
+        }
+    }
+
+    /// Sorts `values` into `bins` buckets of equal width between the smallest
+    /// and the largest of them. NaNs are counted in a separate bucket at the
+    /// end.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `bins` is 0.
+    pub fn of_numbers(
+        values: impl IntoIterator<Item = f64>,
+        bins: usize,
+    ) -> Self {
+        assert!(bins > 0, "Histogram needs at least one bin");
+
+        let values = values.into_iter().collect::<Vec<_>>();
+        let nans = values.iter().filter(|v| v.is_nan()).count();
+        let (min, max) = values
+            .iter()
+            .cloned()
+            .filter(|v| !v.is_nan())
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+                (min.min(v), max.max(v))
+            });
+
+        let mut rows = Vec::new();
+        if min == max {
+            rows.push((format!("{}", min), values.len() - nans));
+        } else if min < max {
+            let width = (max - min) / bins as f64;
+            let mut counts = vec![0; bins];
+            for &v in values.iter().filter(|v| !v.is_nan()) {
+                let bin = ((v - min) / width) as usize;
+                counts[bin.min(bins - 1)] += 1;
+            }
+            for (bin, count) in counts.into_iter().enumerate() {
+                let lo = min + width * bin as f64;
+                let label = if bin + 1 == bins {
+                    format!("[{}, {}]", lo, max)
+                } else {
+                    format!("[{}, {})", lo, min + width * (bin + 1) as f64)
+                };
+                rows.push((label, count));
+            }
+        }
+        if nans > 0 {
+            rows.push(("NaN".to_owned(), nans));
+        }
+
+        Histogram {
+            rows,
+            total: values.len(),
+        }
+    }
+
+    /// Returns the label and the number of values of each bucket.
+    pub fn rows(&self) -> &[(String, usize)] {
+        &self.rows
+    }
+
+    /// Returns the total number of values counted.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+}
+
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let label_width =
+            self.rows.iter().map(|row| row.0.len()).max().unwrap_or(0);
+        let max_count =
+            self.rows.iter().map(|row| row.1).max().unwrap_or(0).max(1);
+
+        for &(ref label, count) in &self.rows {
+            let bar = (count * BAR_WIDTH + max_count - 1) / max_count;
+            writeln!(
+                f,
+                "{:<lw$} |{:<bw$}| {} ({:.1}%)",
+                label,
+                "#".repeat(bar),
+                count,
+                100.0 * count as f64 / self.total.max(1) as f64,
+                lw = label_width,
+                bw = BAR_WIDTH
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Generates `samples` values from `strategy` and renders a histogram of the
+/// numbers `project` maps them to, sorted into `bins` buckets of equal width.
+///
+/// ```
+/// use proptest::prelude::*;
+/// use proptest::strategy::histogram;
+///
+/// let lengths = prop::collection::vec(any::<u8>(), 0..100);
+/// println!("{}", histogram(&lengths, 1000, 10, |v| v.len() as f64));
+/// ```
+///
+/// ## Panics
+///
+/// Panics if `bins` is 0 or if `strategy` fails to generate a value.
+pub fn histogram<S: Strategy + ?Sized>(
+    strategy: &S,
+    samples: usize,
+    bins: usize,
+    project: impl Fn(&S::Value) -> f64,
+) -> Histogram {
+    Histogram::of_numbers(strategy.sample(samples).iter().map(project), bins)
+}
+
+/// Generates `samples` values from `strategy` and renders a histogram of the
+/// keys `key` maps them to, such as the variant of an enum as returned by
+/// `variant_name()`.
+///
+/// ```
+/// use proptest::prelude::*;
+/// use proptest::strategy::{histogram_by_key, variant_name};
+///
+/// let options = prop_oneof![
+///     10 => Just(None),
+///     1 => any::<u8>().prop_map(Some),
+/// ];
+/// println!("{}", histogram_by_key(&options, 1000, variant_name));
+/// ```
+///
+/// ## Panics
+///
+/// Panics if `strategy` fails to generate a value.
+pub fn histogram_by_key<S: Strategy + ?Sized, K: Ord + fmt::Display>(
+    strategy: &S,
+    samples: usize,
+    key: impl Fn(&S::Value) -> K,
+) -> Histogram {
+    Histogram::of_keys(strategy.sample(samples).iter().map(key))
+}
+
+/// Returns the name of the enum variant `value` is, as the start of its
+/// `Debug` representation up to the first parenthesis, brace or space.
+pub fn variant_name<T: fmt::Debug + ?Sized>(value: &T) -> String {
+    let debug = format!("{:?}", value);
+    let end = debug
+        .find(|c: char| '(' == c || '{' == c || c.is_whitespace())
+        .unwrap_or(debug.len());
+    debug[..end].to_owned()
+}
+
+/// How a step of a `ShrinkTrace` reached its value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShrinkAction {
+    /// The value was generated from the seed.
+    Generate,
+    /// The value was reached by `ValueTree::simplify()`.
+    Simplify,
+    /// The value was reached by `ValueTree::complicate()`.
+    Complicate,
+}
+
+impl fmt::Display for ShrinkAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match *self {
+            ShrinkAction::Generate => "generate",
+            ShrinkAction::Simplify => "simplify",
+            ShrinkAction::Complicate => "complicate",
+        })
+    }
+}
+
+/// A value visited while shrinking, as recorded by `trace_shrink()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShrinkStep<T> {
+    /// How the value was reached.
+    pub action: ShrinkAction,
+    /// The value itself.
+    pub value: T,
+    /// Whether the test failed for this value.
+    pub failed: bool,
+}
+
+/// The values visited by shrinking a generated value, as returned by
+/// `trace_shrink()`.
+///
+/// Its `Display` implementation renders one line per step.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShrinkTrace<T> {
+    steps: Vec<ShrinkStep<T>>,
+}
+
+impl<T> ShrinkTrace<T> {
+    /// Returns the values visited, starting with the generated one.
+    pub fn steps(&self) -> &[ShrinkStep<T>] {
+        &self.steps
+    }
+
+    /// Returns the last value for which the test failed, which is the one
+    /// the test runner would report, or `None` if the generated value passed.
+    pub fn minimal(&self) -> Option<&T> {
+        self.steps
+            .iter()
+            .rev()
+            .find(|step| step.failed)
+            .map(|step| &step.value)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Display for ShrinkTrace<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (ix, step) in self.steps.iter().enumerate() {
+            let outcome = if step.failed { "FAIL" } else { "pass" };
+            writeln!(
+                f,
+                "{:>4} {:<10} {} {:?}",
+                ix, step.action, outcome, step.value
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Generates a value from `strategy` with the RNG determined by `seed`, as
+/// `Strategy::sample_with_seed()` does, and records every value shrinking
+/// visits while looking for the minimal value for which `test` fails.
+///
+/// Shrinking proceeds as in the test runner: the value is simplified while
+/// the test fails and complicated while it passes (rejections count as
+/// passes), up to `max_shrink_iters` of the default configuration. Unlike in
+/// the runner, `test` is called directly, so it should report failures by
+/// returning errors, as `prop_assert!` does, rather than by panicking.
+///
+/// ```
+/// use proptest::prelude::*;
+/// use proptest::strategy::trace_shrink;
+///
+/// let trace = trace_shrink(&(0..1000), 42, |v| {
+///     prop_assert!(v < 100);
+///     Ok(())
+/// });
+/// println!("{}", trace);
+/// assert!(trace.minimal().map_or(true, |&v| 100 == v));
+/// ```
+///
+/// ## Panics
+///
+/// Panics if `strategy` fails to generate a value.
+pub fn trace_shrink<S: Strategy + ?Sized>(
+    strategy: &S,
+    seed: u64,
+    test: impl Fn(S::Value) -> TestCaseResult,
+) -> ShrinkTrace<S::Value> {
+    let mut runner = seeded_runner(seed);
+    let mut case = match strategy.new_tree(&mut runner) {
+        Ok(tree) => tree,
+        Err(why) => panic!("Failed to generate a value: {}", why),
+    };
+
+    let mut steps = Vec::new();
+    let mut record = |action, case: &S::Tree| {
+        let failed = match test(case.current()) {
+            Ok(()) | Err(TestCaseError::Reject(..)) => false,
+            Err(TestCaseError::Fail(..)) => true,
+        };
+        steps.push(ShrinkStep {
+            action,
+            value: case.current(),
+            failed,
+        });
+        failed
+    };
+
+    let mut failed = record(ShrinkAction::Generate, &case);
+    let max_iters = runner.config().max_shrink_iters();
+    let mut iters = 0;
+    if failed && case.simplify() {
+        loop {
+            if iters >= max_iters {
+                break;
+            }
+            iters += 1;
+
+            let action = if failed {
+                ShrinkAction::Simplify
+            } else {
+                ShrinkAction::Complicate
+            };
+            failed = record(action, &case);
+            let moved = if failed {
+                case.simplify()
+            } else {
+                case.complicate()
+            };
+            if !moved {
+                break;
+            }
+        }
+    }
+
+    ShrinkTrace { steps }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::std_facade::vec;
+    use crate::strategy::{Just, Union};
+
+    #[test]
+    fn samples_are_reproducible() {
+        let strategy = (0u32..1000, crate::bool::ANY);
+        assert_eq!(
+            strategy.sample_with_seed(20, 7),
+            strategy.sample_with_seed(20, 7)
+        );
+        assert_ne!(
+            strategy.sample_with_seed(20, 7),
+            strategy.sample_with_seed(20, 8)
+        );
+        assert_eq!(
+            strategy.sample_with_seed(20, 7),
+            crate::generate::values(&strategy, 7)
+                .take(20)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(5, strategy.sample(5).len());
+    }
+
+    #[test]
+    fn counts_keys() {
+        let histogram = Histogram::of_keys(vec!['b', 'a', 'b', 'b']);
+        assert_eq!(
+            &[("a".to_owned(), 1), ("b".to_owned(), 3)],
+            histogram.rows()
+        );
+        assert_eq!(4, histogram.total());
+
+        let rendered = format!("{}", histogram);
+        let lines = rendered.lines().collect::<Vec<_>>();
+        assert_eq!(2, lines.len());
+        assert!(lines[0].starts_with("a |##########"));
+        assert!(lines[0].ends_with("| 1 (25.0%)"));
+        assert!(lines[1].starts_with(&format!("b |{}|", "#".repeat(40))));
+    }
+
+    #[test]
+    fn bins_numbers() {
+        let histogram = Histogram::of_numbers(
+            vec![0.0, 1.0, 2.5, 9.0, 10.0, 10.0, f64::NAN],
+            2,
+        );
+        assert_eq!(
+            &[
+                ("[0, 5)".to_owned(), 3),
+                ("[5, 10]".to_owned(), 3),
+                ("NaN".to_owned(), 1),
+            ],
+            histogram.rows()
+        );
+
+        let histogram = Histogram::of_numbers(vec![3.0, 3.0], 4);
+        assert_eq!(&[("3".to_owned(), 2)], histogram.rows());
+    }
+
+    #[test]
+    fn histograms_of_strategies() {
+        let histogram = histogram(&(0..10), 100, 5, |&v| f64::from(v));
+        assert_eq!(100, histogram.total());
+        assert_eq!(5, histogram.rows().len());
+
+        let union = Union::new(vec![Just(None), Just(Some(1))]);
+        let histogram = histogram_by_key(&union, 100, variant_name);
+        assert_eq!(
+            vec!["None", "Some"],
+            histogram
+                .rows()
+                .iter()
+                .map(|row| row.0.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn names_variants() {
+        assert_eq!("Some", variant_name(&Some(1)));
+        assert_eq!("None", variant_name(&None::<u8>));
+        assert_eq!("Ok", variant_name(&Ok::<_, ()>(vec![1])));
+    }
+
+    #[test]
+    fn traces_shrinking() {
+        let trace = trace_shrink(&(100..1000), 1, |v| {
+            if v >= 100 {
+                Err(TestCaseError::fail("too big"))
+            } else {
+                Ok(())
+            }
+        });
+        let steps = trace.steps();
+        assert_eq!(ShrinkAction::Generate, steps[0].action);
+        assert!(steps[0].failed);
+        assert_eq!(Some(&100), trace.minimal());
+        for pair in steps.windows(2) {
+            let expected = if pair[0].failed {
+                ShrinkAction::Simplify
+            } else {
+                ShrinkAction::Complicate
+            };
+            assert_eq!(expected, pair[1].action);
+        }
+        assert_eq!(steps.len(), format!("{}", trace).lines().count());
+
+        let trace = trace_shrink(&Just(1), 1, |_| Ok(()));
+        assert_eq!(1, trace.steps().len());
+        assert_eq!(None, trace.minimal());
+    }
+}
@@ -7,7 +7,8 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use crate::std_facade::{fmt, Arc, Box, Vec};
+use crate::std_facade::{fmt, Arc, Box, Rc, Vec, Weak};
+use core::cell::RefCell;
 
 use crate::strategy::traits::*;
 use crate::strategy::unions::float_to_weight;
@@ -109,16 +110,35 @@ impl<
         // with levels which are always pure branches, which further
         // underestimates size.
 
+        // The desired size is scaled according to the size of the runner, so
+        // that values grow along with it.
+        let desired_size = (u64::from(self.desired_size)
+            * u64::from(runner.size())
+            + u64::from(MAX_SIZE)
+            - 1)
+            / u64::from(MAX_SIZE);
+
+        // A desired size of zero only allows leaves.
+        let depth = if 0 == desired_size { 0 } else { self.depth };
+
         let mut branch_probabilities = Vec::new();
         let mut k2 = u64::from(self.expected_branch_size) * 2;
-        for _ in 0..self.depth {
-            branch_probabilities.push(f64::from(self.desired_size) / k2 as f64);
+        for _ in 0..depth {
+            branch_probabilities.push(desired_size as f64 / k2 as f64);
             k2 = k2.saturating_mul(u64::from(self.expected_branch_size) * 2);
         }
 
+        let nodes = Rc::new(RefCell::new(Vec::new()));
+
         let mut strat = self.base.clone();
         while let Some(branch_probability) = branch_probabilities.pop() {
-            let recursed = (self.recurse)(strat.clone());
+            let recursed = (self.recurse)(
+                Recording {
+                    inner: strat.clone(),
+                    nodes: Rc::downgrade(&nodes),
+                }
+                .boxed(),
+            );
             let recursive_choice = recursed.boxed();
             let non_recursive_choice = strat;
             // Clamp the maximum branch probability to 0.9 to ensure we can
@@ -133,7 +153,191 @@ impl<
             strat = branch.boxed();
         }
 
-        strat.new_tree(runner)
+        let root = strat.new_tree(runner)?;
+        let end = nodes.borrow().len();
+        Ok(Box::new(RecursiveValueTree {
+            root,
+            nodes,
+            current: None,
+            next: 0,
+            end,
+            trying: None,
+            replacing: true,
+        }))
+    }
+}
+
+/// A value tree shared between its parent and the `RecursiveValueTree`.
+struct SharedTree<T>(Rc<RefCell<Box<dyn ValueTree<Value = T>>>>);
+
+impl<T> Clone for SharedTree<T> {
+    fn clone(&self) -> Self {
+        SharedTree(Rc::clone(&self.0))
+    }
+}
+
+impl<T: fmt::Debug> ValueTree for SharedTree<T> {
+    type Value = T;
+
+    fn current(&self) -> T {
+        self.0.borrow().current()
+    }
+
+    fn simplify(&mut self) -> bool {
+        self.0.borrow_mut().simplify()
+    }
+
+    fn complicate(&mut self) -> bool {
+        self.0.borrow_mut().complicate()
+    }
+}
+
+/// A subtree of a recursive value, as recorded by `Recording`.
+struct Node<T> {
+    tree: SharedTree<T>,
+    /// The index after the last node generated as part of this subtree.
+    end: usize,
+}
+
+/// The subtrees generated for a recursive value, in the order their
+/// generation started; i.e., parents come before their descendants. `None`
+/// marks subtrees still being generated or which failed to generate.
+type Nodes<T> = Rc<RefCell<Vec<Option<Node<T>>>>>;
+
+/// Strategy which records all the trees generated by `inner` in `nodes`, so
+/// that the `RecursiveValueTree` can replace the whole value with one of its
+/// subtrees.
+struct Recording<T> {
+    inner: BoxedStrategy<T>,
+    // The strategy ends up being referenced by the trees in `nodes` (through
+    // lazily initialised unions), so a strong reference would be a cycle.
+    nodes: Weak<RefCell<Vec<Option<Node<T>>>>>,
+}
+
+impl<T: fmt::Debug> fmt::Debug for Recording<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Recording")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<T: fmt::Debug + 'static> Strategy for Recording<T> {
+    type Tree = SharedTree<T>;
+    type Value = T;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let nodes = self.nodes.upgrade();
+        let ix = nodes.as_ref().map(|nodes| {
+            let mut nodes = nodes.borrow_mut();
+            nodes.push(None);
+            nodes.len() - 1
+        });
+
+        let tree =
+            SharedTree(Rc::new(RefCell::new(self.inner.new_tree(runner)?)));
+        if let (Some(nodes), Some(ix)) = (nodes, ix) {
+            let mut nodes = nodes.borrow_mut();
+            let end = nodes.len();
+            nodes[ix] = Some(Node {
+                tree: tree.clone(),
+                end,
+            });
+        }
+        Ok(tree)
+    }
+}
+
+/// `ValueTree` for `Recursive`.
+///
+/// This first tries to replace the whole value with each of its subtrees in
+/// turn, starting with the outermost ones. Once a replacement is accepted, the
+/// search continues among the subtrees of the replacement. When no
+/// replacement is accepted anymore, the remaining value shrinks as usual.
+struct RecursiveValueTree<T> {
+    root: Box<dyn ValueTree<Value = T>>,
+    nodes: Nodes<T>,
+    /// The index of the node which replaced `root`, if any.
+    current: Option<usize>,
+    /// The range of indices of the nodes which could replace the current
+    /// value.
+    next: usize,
+    end: usize,
+    /// The index of the node tried by the last call to `simplify()`.
+    trying: Option<usize>,
+    /// Whether to still try replacing the current value with subtrees.
+    replacing: bool,
+}
+
+impl<T: fmt::Debug> RecursiveValueTree<T> {
+    fn with_active<R>(
+        &mut self,
+        f: impl FnOnce(&mut dyn ValueTree<Value = T>) -> R,
+    ) -> R {
+        match self.current {
+            Some(ix) => {
+                let mut tree = self.node(ix);
+                f(&mut tree)
+            }
+            None => f(&mut *self.root),
+        }
+    }
+
+    fn node(&self, ix: usize) -> SharedTree<T> {
+        self.nodes.borrow()[ix]
+            .as_ref()
+            .expect("RecursiveValueTree using unrecorded node")
+            .tree
+            .clone()
+    }
+}
+
+impl<T: fmt::Debug> ValueTree for RecursiveValueTree<T> {
+    type Value = T;
+
+    fn current(&self) -> T {
+        match self.trying.or(self.current) {
+            Some(ix) => self.node(ix).current(),
+            None => self.root.current(),
+        }
+    }
+
+    fn simplify(&mut self) -> bool {
+        if self.replacing {
+            // The last replacement was not undone, so continue from there.
+            if let Some(ix) = self.trying.take() {
+                self.current = Some(ix);
+                self.next = ix + 1;
+                self.end = self.nodes.borrow()[ix]
+                    .as_ref()
+                    .map_or(ix + 1, |node| node.end);
+            }
+
+            while self.next < self.end {
+                let ix = self.next;
+                self.next += 1;
+                if self.nodes.borrow()[ix].is_some() {
+                    self.trying = Some(ix);
+                    return true;
+                }
+            }
+
+            self.replacing = false;
+        }
+
+        self.with_active(|tree| tree.simplify())
+    }
+
+    fn complicate(&mut self) -> bool {
+        if self.trying.take().is_some() {
+            return true;
+        }
+
+        if self.replacing {
+            false
+        } else {
+            self.with_active(|tree| tree.complicate())
+        }
     }
 }
 
@@ -192,6 +396,22 @@ mod test {
         assert!(max_count > 48, "Only got max count {}", max_count);
     }
 
+    #[test]
+    fn grows_with_runner_size() {
+        let strat = Just(Tree::Leaf).prop_recursive(4, 64, 16, |element| {
+            crate::collection::vec(element, 8..16).prop_map(Tree::Branch)
+        });
+
+        let mut runner = TestRunner::deterministic();
+        runner.set_size(0);
+        for _ in 0..256 {
+            assert_eq!(
+                Tree::Leaf,
+                strat.new_tree(&mut runner).unwrap().current()
+            );
+        }
+    }
+
     #[test]
     fn simplifies_to_non_recursive() {
         let strat = Just(Tree::Leaf).prop_recursive(4, 64, 16, |element| {
@@ -206,4 +426,92 @@ mod test {
             assert_eq!(Tree::Leaf, value.current());
         }
     }
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Expr {
+        Lit(u32),
+        Add(Box<Expr>, Box<Expr>),
+        Mul(Box<Expr>, Box<Expr>),
+    }
+
+    impl Expr {
+        fn max_lit(&self) -> u32 {
+            match *self {
+                Expr::Lit(n) => n,
+                Expr::Add(ref a, ref b) | Expr::Mul(ref a, ref b) => {
+                    max(a.max_lit(), b.max_lit())
+                }
+            }
+        }
+    }
+
+    fn exprs() -> BoxedStrategy<Expr> {
+        (0..1000u32)
+            .prop_map(Expr::Lit)
+            .prop_recursive(6, 64, 2, |inner| {
+                prop_oneof![
+                    (inner.clone(), inner.clone()).prop_map(|(a, b)| {
+                        Expr::Add(Box::new(a), Box::new(b))
+                    }),
+                    (inner.clone(), inner)
+                        .prop_map(|(a, b)| Expr::Mul(Box::new(a), Box::new(b))),
+                ]
+            })
+            .boxed()
+    }
+
+    #[test]
+    fn shrinks_to_failing_subtree() {
+        for seed in 0..64u8 {
+            let mut runner = TestRunner::new_with_rng(
+                Config::default(),
+                crate::test_runner::TestRng::from_seed(
+                    crate::test_runner::RngAlgorithm::ChaCha,
+                    &[seed; 32],
+                ),
+            );
+            let result = runner.run(&exprs(), |expr| {
+                prop_assert!(expr.max_lit() < 500);
+                Ok(())
+            });
+
+            match result {
+                Err(TestError::Fail(_, expr)) => {
+                    assert_eq!(Expr::Lit(500), expr)
+                }
+                result => panic!("Unexpected result: {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn replaces_with_descendant_in_one_step() {
+        let strat = exprs();
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..256 {
+            let mut tree = strat.new_tree(&mut runner).unwrap();
+            if let Expr::Lit(_) = tree.current() {
+                continue;
+            }
+
+            // The first simplification replaces the whole value with a
+            // subtree, keeping the values of the subtree intact.
+            let before = tree.current();
+            assert!(tree.simplify());
+            let after = tree.current();
+            let mut found = false;
+            let mut stack = vec![before];
+            while let Some(expr) = stack.pop() {
+                match expr {
+                    Expr::Lit(_) => (),
+                    Expr::Add(a, b) | Expr::Mul(a, b) => {
+                        found |= *a == after || *b == after;
+                        stack.push(*a);
+                        stack.push(*b);
+                    }
+                }
+            }
+            assert!(found, "{:?} is not a subtree", after);
+        }
+    }
 }
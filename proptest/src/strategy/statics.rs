@@ -27,6 +27,7 @@
 use crate::std_facade::fmt;
 
 use crate::strategy::traits::*;
+use crate::strategy::Enumerable;
 use crate::test_runner::*;
 
 //==============================================================================
@@ -157,6 +158,11 @@ impl<S, F> Map<S, F> {
     pub fn new(source: S, fun: F) -> Self {
         Map { source, fun }
     }
+
+    /// Returns the strategy whose values are mapped.
+    pub(crate) fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
 }
 
 impl<S: fmt::Debug, F> fmt::Debug for Map<S, F> {
@@ -178,6 +184,39 @@ impl<S: Strategy, F: Clone + MapFn<S::Value>> Strategy for Map<S, F> {
             fun: self.fun.clone(),
         })
     }
+
+    fn enumerable_domain_size(&self) -> Option<u64> {
+        self.source.enumerable_domain_size()
+    }
+
+    fn enumerable_new_tree_at(
+        &self,
+        index: u64,
+        runner: &mut TestRunner,
+    ) -> Option<NewTree<Self>> {
+        let tree = self.source.enumerable_new_tree_at(index, runner)?;
+        Some(tree.map(|v| Map {
+            source: v,
+            fun: self.fun.clone(),
+        }))
+    }
+}
+
+impl<S: Enumerable, F: Clone + MapFn<S::Value>> Enumerable for Map<S, F> {
+    fn domain_size(&self) -> Option<u64> {
+        self.source.domain_size()
+    }
+
+    fn new_tree_at(
+        &self,
+        index: u64,
+        runner: &mut TestRunner,
+    ) -> NewTree<Self> {
+        self.source.new_tree_at(index, runner).map(|v| Map {
+            source: v,
+            fun: self.fun.clone(),
+        })
+    }
 }
 
 impl<S: ValueTree, F: MapFn<S::Value>> ValueTree for Map<S, F> {
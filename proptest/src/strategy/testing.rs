@@ -0,0 +1,518 @@
+//-
+// Copyright 2021 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks for custom `Strategy` and `ValueTree` implementations.
+//!
+//! `check_strategy_sanity()` verifies that `simplify()` and `complicate()`
+//! agree with each other. The functions in this module check further
+//! properties that the test runner relies on:
+//!
+//! - `validate()` checks that generation and shrinking are deterministic for
+//!   a given seed, that shrinking terminates within a bound, and that
+//!   `current()` returns the same value every time it is called between two
+//!   shrinking steps;
+//! - `check_shrinks_smaller()` checks that every `simplify()` leads to a
+//!   value no larger than the one before under a user-provided measure;
+//! - `check_filter_respected()` checks that every value visited while
+//!   shrinking still satisfies a predicate, such as the one passed to
+//!   `prop_filter()`.
+//!
+//! Each check generates `ValidationOptions::cases` values, each from its own
+//! seed, and shrinks them the way the test runner would for a test which
+//! fails or passes at random. Rather than panicking, checks return a
+//! `Violation` naming the case and the shrinking step where the property
+//! first failed to hold.
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use proptest::strategy::testing::*;
+//!
+//! let options = ValidationOptions::default();
+//! let strategy = (0..100i32, prop::collection::vec(any::<u8>(), 0..10));
+//! validate(&strategy, options).unwrap();
+//! check_shrinks_smaller(&strategy, options, |&(n, ref v)| (n.abs(), v.len()))
+//!     .unwrap();
+//! ```
+
+use crate::std_facade::{fmt, String, Vec};
+use rand::Rng;
+
+use crate::generate::seeded_runner;
+use crate::strategy::{ShrinkAction, Strategy, ValueTree};
+
+/// Options passed to the checks of this module.
+#[derive(Clone, Copy, Debug)]
+pub struct ValidationOptions {
+    /// The number of values to generate and shrink. Defaults to 256.
+    pub cases: u32,
+    /// The number of shrinking steps after which shrinking a value is
+    /// considered not to terminate. Defaults to 65536.
+    pub max_shrink_steps: u32,
+    /// The seed from which the seed of each case is derived. Defaults to 0.
+    pub seed: u64,
+
+    // Needs to be public for FRU syntax.
+    #[allow(missing_docs)]
+    #[doc(hidden)]
+    pub _non_exhaustive: (),
+}
+
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        ValidationOptions {
+            cases: 256,
+            max_shrink_steps: 65_536,
+            seed: 0,
+            _non_exhaustive: (),
+        }
+    }
+}
+
+/// The property a `Violation` breaks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Check {
+    /// The strategy failed to generate a value 100 times in a row.
+    Generation,
+    /// Generating or shrinking from the same seed twice visited different
+    /// values.
+    Determinism,
+    /// Shrinking did not stop within `ValidationOptions::max_shrink_steps`.
+    Termination,
+    /// Two consecutive calls to `current()` returned different values.
+    StableCurrent,
+    /// `simplify()` led to a larger value than the one before.
+    ShrinksSmaller,
+    /// A value visited did not satisfy the predicate.
+    Filter,
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Check::Generation => "generation",
+            Check::Determinism => "determinism",
+            Check::Termination => "shrink termination",
+            Check::StableCurrent => "stable current()",
+            Check::ShrinksSmaller => "shrinking to smaller values",
+            Check::Filter => "filter",
+        })
+    }
+}
+
+/// A property which does not hold, as reported by the checks of this
+/// module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// The property which does not hold.
+    pub check: Check,
+    /// The index of the case, whose seed is derived from
+    /// `ValidationOptions::seed`.
+    pub case: u32,
+    /// The index of the shrinking step, 0 being the generated value.
+    pub step: usize,
+    /// How the value of the step was reached.
+    pub action: ShrinkAction,
+    /// What went wrong, with the values involved.
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} violated in case {}, step {} ({}): {}",
+            self.check, self.case, self.step, self.action, self.message
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for Violation {}
+
+/// Like `==`, but also considers two values equal if neither equals itself,
+/// so that NaNs do not cause spurious violations.
+#[allow(clippy::eq_op)]
+fn same<T: PartialEq>(a: &T, b: &T) -> bool {
+    a == b || (a != a && b != b)
+}
+
+/// Generates the value of case `case` and shrinks it like the test runner
+/// would for a test whose outcome is drawn from the RNG of the case,
+/// returning the values visited and how each was reached.
+fn walk<S: Strategy + ?Sized>(
+    strategy: &S,
+    options: ValidationOptions,
+    case: u32,
+) -> Result<Vec<(ShrinkAction, S::Value)>, Violation>
+where
+    S::Value: PartialEq,
+{
+    let violation = |check, step, action, message| Violation {
+        check,
+        case,
+        step,
+        action,
+        message,
+    };
+
+    let mut runner = seeded_runner(options.seed.wrapping_add(u64::from(case)));
+    let mut tries = 0;
+    let mut tree = loop {
+        match strategy.new_tree(&mut runner) {
+            Ok(tree) => break tree,
+            Err(why) => {
+                tries += 1;
+                if tries >= 100 {
+                    return Err(violation(
+                        Check::Generation,
+                        0,
+                        ShrinkAction::Generate,
+                        format!(
+                            "failed to generate a value 100 times in a \
+                             row; last failure reason: {}",
+                            why
+                        ),
+                    ));
+                }
+            }
+        }
+    };
+
+    let mut steps = Vec::new();
+    let mut action = ShrinkAction::Generate;
+    let mut failed = true;
+    loop {
+        let value = tree.current();
+        let again = tree.current();
+        if !same(&value, &again) {
+            return Err(violation(
+                Check::StableCurrent,
+                steps.len(),
+                action,
+                format!("current() returned {:?}, then {:?}", value, again),
+            ));
+        }
+        steps.push((action, value));
+
+        if steps.len() > options.max_shrink_steps as usize {
+            return Err(violation(
+                Check::Termination,
+                steps.len() - 1,
+                action,
+                format!(
+                    "shrinking did not stop after {} steps",
+                    options.max_shrink_steps
+                ),
+            ));
+        }
+
+        let moved = if failed {
+            action = ShrinkAction::Simplify;
+            tree.simplify()
+        } else {
+            action = ShrinkAction::Complicate;
+            tree.complicate()
+        };
+        if !moved {
+            break;
+        }
+        failed = runner.rng().gen();
+    }
+
+    Ok(steps)
+}
+
+/// Checks that generating and shrinking values from `strategy` is
+/// deterministic, terminates and has a stable `current()`.
+///
+/// Each case is generated and shrunk twice from the same seed, and must
+/// visit the same values both times.
+pub fn validate<S: Strategy + ?Sized>(
+    strategy: &S,
+    options: ValidationOptions,
+) -> Result<(), Violation>
+where
+    S::Value: PartialEq,
+{
+    for case in 0..options.cases {
+        let first = walk(strategy, options, case)?;
+        let second = walk(strategy, options, case)?;
+
+        for (step, (a, b)) in first.iter().zip(&second).enumerate() {
+            if a.0 != b.0 || !same(&a.1, &b.1) {
+                return Err(Violation {
+                    check: Check::Determinism,
+                    case,
+                    step,
+                    action: b.0,
+                    message: format!(
+                        "the same seed led to {:?} ({}) the first time \
+                         and {:?} ({}) the second time",
+                        a.1, a.0, b.1, b.0
+                    ),
+                });
+            }
+        }
+        if first.len() != second.len() {
+            let step = first.len().min(second.len());
+            return Err(Violation {
+                check: Check::Determinism,
+                case,
+                step,
+                action: first.get(step).or_else(|| second.get(step)).unwrap().0,
+                message: format!(
+                    "the same seed led to {} shrinking steps the first \
+                     time and {} the second time",
+                    first.len() - 1,
+                    second.len() - 1
+                ),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that every successful `simplify()` on values generated by
+/// `strategy` leads to a value whose `measure` is no greater than that of
+/// the value before.
+///
+/// The measure only needs to be a partial order; values whose measures are
+/// incomparable are accepted.
+pub fn check_shrinks_smaller<S: Strategy + ?Sized, M: PartialOrd + fmt::Debug>(
+    strategy: &S,
+    options: ValidationOptions,
+    measure: impl Fn(&S::Value) -> M,
+) -> Result<(), Violation>
+where
+    S::Value: PartialEq,
+{
+    for case in 0..options.cases {
+        let steps = walk(strategy, options, case)?;
+        for (step, pair) in steps.windows(2).enumerate() {
+            let (before, after) = (&pair[0].1, &pair[1].1);
+            if ShrinkAction::Simplify != pair[1].0 {
+                continue;
+            }
+
+            let (m_before, m_after) = (measure(before), measure(after));
+            if m_after > m_before {
+                return Err(Violation {
+                    check: Check::ShrinksSmaller,
+                    case,
+                    step: step + 1,
+                    action: ShrinkAction::Simplify,
+                    message: format!(
+                        "simplified {:?} (measure {:?}) to {:?} \
+                         (measure {:?})",
+                        before, m_before, after, m_after
+                    ),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that every value generated by `strategy`, and every value visited
+/// while shrinking it, satisfies `predicate`.
+///
+/// This is mainly useful to check that a strategy built with
+/// `prop_filter()`, or a hand-written strategy meant to only produce certain
+/// values, keeps doing so while shrinking.
+pub fn check_filter_respected<S: Strategy + ?Sized>(
+    strategy: &S,
+    options: ValidationOptions,
+    predicate: impl Fn(&S::Value) -> bool,
+) -> Result<(), Violation>
+where
+    S::Value: PartialEq,
+{
+    for case in 0..options.cases {
+        let steps = walk(strategy, options, case)?;
+        if let Some((step, &(action, ref value))) = steps
+            .iter()
+            .enumerate()
+            .find(|&(_, (_, value))| !predicate(value))
+        {
+            return Err(Violation {
+                check: Check::Filter,
+                case,
+                step,
+                action,
+                message: format!("{:?} does not satisfy the predicate", value),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::strategy::{Just, NewTree};
+    use crate::test_runner::TestRunner;
+    use core::cell::Cell;
+
+    const OPTIONS: ValidationOptions = ValidationOptions {
+        cases: 32,
+        max_shrink_steps: 1000,
+        seed: 0,
+        _non_exhaustive: (),
+    };
+
+    #[test]
+    fn builtin_strategies_pass() {
+        let strategy = (
+            0..1000i32,
+            crate::collection::vec(crate::bool::ANY, 0..8),
+            Just(()),
+        );
+        assert_eq!(Ok(()), validate(&strategy, OPTIONS));
+        assert_eq!(
+            Ok(()),
+            check_shrinks_smaller(&strategy, OPTIONS, |v| {
+                (v.0.abs(), v.1.len())
+            })
+        );
+
+        let big = (0..10u32).prop_filter("big", |&v| v > 3);
+        assert_eq!(Ok(()), check_filter_respected(&big, OPTIONS, |&v| v > 3));
+    }
+
+    /// A value tree which counts up when simplified, and can be made to
+    /// shrink forever or to count up whenever `current()` is called.
+    #[derive(Debug)]
+    struct Broken {
+        value: Cell<u32>,
+        unstable: bool,
+        steps_left: Option<u32>,
+    }
+
+    impl ValueTree for Broken {
+        type Value = u32;
+
+        fn current(&self) -> u32 {
+            let value = self.value.get();
+            if self.unstable {
+                self.value.set(value + 1);
+            }
+            value
+        }
+
+        fn simplify(&mut self) -> bool {
+            match self.steps_left {
+                Some(0) => return false,
+                Some(ref mut n) => *n -= 1,
+                None => (),
+            }
+            self.value.set(self.value.get() + 1);
+            true
+        }
+
+        fn complicate(&mut self) -> bool {
+            self.steps_left.is_none()
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct BrokenStrategy {
+        unstable: bool,
+        endless: bool,
+        nondeterministic: bool,
+        generated: Cell<u32>,
+    }
+
+    impl Strategy for BrokenStrategy {
+        type Tree = Broken;
+        type Value = u32;
+
+        fn new_tree(&self, _: &mut TestRunner) -> NewTree<Self> {
+            let start = if self.nondeterministic {
+                self.generated.get()
+            } else {
+                0
+            };
+            self.generated.set(self.generated.get() + 1);
+            Ok(Broken {
+                value: Cell::new(start),
+                unstable: self.unstable,
+                steps_left: Some(3).filter(|_| !self.endless),
+            })
+        }
+    }
+
+    #[test]
+    fn reports_unstable_current() {
+        let strategy = BrokenStrategy {
+            unstable: true,
+            ..BrokenStrategy::default()
+        };
+        let violation = validate(&strategy, OPTIONS).unwrap_err();
+        assert_eq!(
+            "stable current() violated in case 0, step 0 (generate): \
+             current() returned 0, then 1",
+            format!("{}", violation)
+        );
+    }
+
+    #[test]
+    fn reports_nondeterminism() {
+        let strategy = BrokenStrategy {
+            nondeterministic: true,
+            ..BrokenStrategy::default()
+        };
+        let violation = validate(&strategy, OPTIONS).unwrap_err();
+        assert_eq!(Check::Determinism, violation.check);
+        assert_eq!(
+            (0, 0, ShrinkAction::Generate),
+            (violation.case, violation.step, violation.action)
+        );
+    }
+
+    #[test]
+    fn reports_endless_shrinking() {
+        let strategy = BrokenStrategy {
+            endless: true,
+            ..BrokenStrategy::default()
+        };
+        let violation = validate(&strategy, OPTIONS).unwrap_err();
+        assert_eq!(Check::Termination, violation.check);
+        assert_eq!((0, 1000), (violation.case, violation.step));
+    }
+
+    #[test]
+    fn reports_growth() {
+        let strategy = BrokenStrategy::default();
+        assert_eq!(Ok(()), validate(&strategy, OPTIONS));
+
+        let violation =
+            check_shrinks_smaller(&strategy, OPTIONS, |&v| v).unwrap_err();
+        assert_eq!(Check::ShrinksSmaller, violation.check);
+        assert_eq!(
+            (0, 1, ShrinkAction::Simplify),
+            (violation.case, violation.step, violation.action)
+        );
+        assert_eq!(
+            "simplified 0 (measure 0) to 1 (measure 1)",
+            violation.message
+        );
+    }
+
+    #[test]
+    fn reports_filtered_values() {
+        let violation =
+            check_filter_respected(&(4..10u32), OPTIONS, |&v| v > 4)
+                .unwrap_err();
+        assert_eq!(Check::Filter, violation.check);
+        assert_eq!("4 does not satisfy the predicate", violation.message);
+    }
+}
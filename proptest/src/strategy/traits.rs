@@ -7,9 +7,10 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use crate::std_facade::{fmt, Arc, Box, Rc};
+use crate::std_facade::{fmt, Arc, Box, Rc, Vec};
 use core::cmp;
 
+use crate::strategy::preview::sample_values;
 use crate::strategy::*;
 use crate::test_runner::*;
 
@@ -59,6 +60,28 @@ pub trait Strategy: fmt::Debug {
     /// generate the test case.
     fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self>;
 
+    /// Returns `Enumerable::domain_size()` for the `Enumerable` strategies of
+    /// this crate, and combinations of them, and `None` for other strategies.
+    ///
+    /// This lets code which accepts any `Strategy`, such as
+    /// `collection::hash_set()`, find out whether it can sample a small
+    /// domain without replacement.
+    #[doc(hidden)]
+    fn enumerable_domain_size(&self) -> Option<u64> {
+        None
+    }
+
+    /// Returns `Enumerable::new_tree_at()` for the strategies whose
+    /// `enumerable_domain_size()` is not `None`, and `None` for others.
+    #[doc(hidden)]
+    fn enumerable_new_tree_at(
+        &self,
+        _index: u64,
+        _runner: &mut TestRunner,
+    ) -> Option<NewTree<Self>> {
+        None
+    }
+
     /// Returns a strategy which produces values transformed by the function
     /// `fun`.
     ///
@@ -390,6 +413,9 @@ pub trait Strategy: fmt::Debug {
     /// the desired size. Passing a too small value can result in trees vastly
     /// larger than desired.
     ///
+    /// `desired_size` is scaled down along with the size of the `TestRunner`
+    /// (see `TestRunner::size()`).
+    ///
     /// Note that `depth` only counts branches; i.e., `depth = 0` is a single
     /// leaf, and `depth = 1` is a leaf or a branch containing only leaves.
     ///
@@ -398,8 +424,15 @@ pub trait Strategy: fmt::Debug {
     /// `expected_branch_size` (though it is not a hard limit) since the
     /// underlying code underestimates probabilities.
     ///
-    /// Shrinking shrinks both the inner values and attempts switching from
-    /// recursive to non-recursive cases.
+    /// Shrinking first tries replacing the whole value with each of the
+    /// values generated by the strategies passed to `recurse` within it
+    /// (i.e., its same-typed subtrees), outermost first, so that e.g. a
+    /// failing `Add(Mul(Lit(1), Lit(2)), Lit(3))` can shrink directly to
+    /// `Lit(1)`. After that, it shrinks both the inner values and attempts
+    /// switching from recursive to non-recursive cases.
+    ///
+    /// For mutually recursive types, or to control the number of nodes more
+    /// precisely, see `Fixpoint`.
     ///
     /// ## Example
     ///
@@ -550,6 +583,30 @@ pub trait Strategy: fmt::Debug {
     {
         NoShrink(self)
     }
+
+    /// Generates `n` values from this strategy with a randomly seeded RNG,
+    /// which is handy to see what a strategy produces while designing it.
+    ///
+    /// See also `sample_with_seed()`, and `histogram()` and `trace_shrink()`
+    /// in this module.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the strategy fails to generate a value.
+    fn sample(&self, n: usize) -> Vec<Self::Value> {
+        sample_values(self, n, &mut TestRunner::default())
+    }
+
+    /// Like `sample()`, but generates the values with an RNG determined by
+    /// `seed`, regardless of the environment. These are the first `n` values
+    /// of `generate::values(self, seed)`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the strategy fails to generate a value.
+    fn sample_with_seed(&self, n: usize, seed: u64) -> Vec<Self::Value> {
+        crate::generate::values(self, seed).take(n).collect()
+    }
 }
 
 /// A generated value and its associated shrinker.
@@ -669,6 +726,18 @@ macro_rules! proxy_strategy {
             fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
                 (**self).new_tree(runner)
             }
+
+            fn enumerable_domain_size(&self) -> Option<u64> {
+                (**self).enumerable_domain_size()
+            }
+
+            fn enumerable_new_tree_at(
+                &self,
+                index: u64,
+                runner: &mut TestRunner,
+            ) -> Option<NewTree<Self>> {
+                (**self).enumerable_new_tree_at(index, runner)
+            }
         }
     };
 }
@@ -830,6 +899,9 @@ impl Default for CheckStrategySanityOptions {
 ///
 /// This can work with fallible strategies, but limits how many times it will
 /// retry failures.
+///
+/// The `strategy::testing` module has further checks, such as for
+/// determinism and termination of shrinking.
 pub fn check_strategy_sanity<S: Strategy>(
     strategy: S,
     options: Option<CheckStrategySanityOptions>,
@@ -7,7 +7,8 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use crate::std_facade::{fmt, Arc, Vec};
+use crate::std_facade::{fmt, vec, Arc, String, ToOwned, Vec};
+use core::any;
 use core::cmp::{max, min};
 use core::u32;
 
@@ -15,6 +16,9 @@ use core::u32;
 use num_traits::float::FloatCore;
 
 use crate::num::sample_uniform;
+use crate::strategy::enumerable::{
+    enumerated_tree_at, locate_index, Enumerable,
+};
 use crate::strategy::{lazy::LazyValueTree, traits::*};
 use crate::test_runner::*;
 
@@ -117,20 +121,64 @@ fn pick_weighted<I: Iterator<Item = u32>>(
         .count()
 }
 
+/// Picks the arm of `union` to generate a value from, going through every arm
+/// in turn first if `Config::union_arms` asks for it.
+fn pick_arm<U>(
+    union: &U,
+    runner: &mut TestRunner,
+    weights: &[u32],
+    describe: impl FnOnce() -> Vec<Option<String>>,
+) -> usize {
+    let id = (
+        union as *const U as usize,
+        any::type_name::<U>(),
+        weights.len(),
+    );
+    if let Some(pick) = runner.cycle_union_arm(id, describe) {
+        return pick;
+    }
+
+    let pick =
+        pick_weighted(runner, weights.iter().cloned(), weights.iter().cloned());
+    runner.union_arm_chosen(id, pick);
+    pick
+}
+
+/// Labels an arm of a union for the report of arms never chosen, or returns
+/// `None` for arms with no weight, which are never meant to be chosen.
+fn describe_arm<S: Strategy>(
+    ix: usize,
+    &(weight, ref arm): &WA<S>,
+) -> Option<String> {
+    if 0 == weight {
+        return None;
+    }
+
+    let example = arm
+        .new_tree(&mut TestRunner::deterministic())
+        .map(|tree| format!("{:?}", tree.current()))
+        .unwrap_or_else(|_| "?".to_owned());
+    Some(format!(
+        "arm {} of union of `{}` (e.g. {})",
+        ix,
+        any::type_name::<S::Value>(),
+        example
+    ))
+}
+
 impl<T: Strategy> Strategy for Union<T> {
     type Tree = UnionValueTree<T>;
     type Value = T::Value;
 
     fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
-        fn extract_weight<V>(&(w, _): &WA<V>) -> u32 {
-            w
-        }
-
-        let pick = pick_weighted(
-            runner,
-            self.options.iter().map(extract_weight::<T>),
-            self.options.iter().map(extract_weight::<T>),
-        );
+        let weights = self.options.iter().map(|&(w, _)| w).collect::<Vec<_>>();
+        let pick = pick_arm(self, runner, &weights, || {
+            self.options
+                .iter()
+                .enumerate()
+                .map(|(ix, option)| describe_arm(ix, option))
+                .collect()
+        });
 
         let mut options = Vec::with_capacity(pick);
 
@@ -142,9 +190,9 @@ impl<T: Strategy> Strategy for Union<T> {
         // Initialize the tree at pick so at least one value is available. Note
         // that if generation for the value at pick fails, the entire strategy
         // will fail. This seems like the right call.
-        options.push(LazyValueTree::new_initialized(
-            self.options[pick].1.new_tree(runner)?,
-        ));
+        let tree = self.options[pick].1.new_tree(runner);
+        runner.union_arm_generated();
+        options.push(LazyValueTree::new_initialized(tree?));
 
         Ok(UnionValueTree {
             options,
@@ -153,6 +201,88 @@ impl<T: Strategy> Strategy for Union<T> {
             prev_pick: None,
         })
     }
+
+    fn enumerable_domain_size(&self) -> Option<u64> {
+        union_domain_size(self, Strategy::enumerable_domain_size)
+    }
+
+    fn enumerable_new_tree_at(
+        &self,
+        index: u64,
+        runner: &mut TestRunner,
+    ) -> Option<NewTree<Self>> {
+        self.enumerable_domain_size()?;
+        Some(union_new_tree_at(
+            self,
+            index,
+            runner,
+            Strategy::enumerable_domain_size,
+            enumerated_tree_at,
+        ))
+    }
+}
+
+impl<T: Enumerable> Enumerable for Union<T> {
+    fn domain_size(&self) -> Option<u64> {
+        union_domain_size(self, Enumerable::domain_size)
+    }
+
+    fn new_tree_at(
+        &self,
+        index: u64,
+        runner: &mut TestRunner,
+    ) -> NewTree<Self> {
+        union_new_tree_at(
+            self,
+            index,
+            runner,
+            Enumerable::domain_size,
+            Enumerable::new_tree_at,
+        )
+    }
+}
+
+/// The number of values of `union`, given a function returning the number of
+/// values of each option.
+fn union_domain_size<T: Strategy>(
+    union: &Union<T>,
+    size: fn(&T) -> Option<u64>,
+) -> Option<u64> {
+    union
+        .options
+        .iter()
+        .try_fold(0u64, |sum, option| sum.checked_add(size(&option.1)?))
+}
+
+/// The value tree at `index` of the domain of `union`, given functions
+/// returning the number of values of each option and creating the value tree
+/// at an index of its domain.
+fn union_new_tree_at<T: Strategy>(
+    union: &Union<T>,
+    index: u64,
+    runner: &mut TestRunner,
+    size: fn(&T) -> Option<u64>,
+    new_tree_at: fn(&T, u64, &mut TestRunner) -> NewTree<T>,
+) -> NewTree<Union<T>> {
+    let (pick, index) =
+        locate_index(union.options.iter().map(|option| size(&option.1)), index);
+
+    let mut options = Vec::with_capacity(pick);
+    for option in &union.options[0..pick] {
+        options.push(LazyValueTree::new(Arc::clone(&option.1), runner));
+    }
+    options.push(LazyValueTree::new_initialized(new_tree_at(
+        &union.options[pick].1,
+        index,
+        runner,
+    )?));
+
+    Ok(UnionValueTree {
+        options,
+        pick,
+        min_pick: 0,
+        prev_pick: None,
+    })
 }
 
 macro_rules! access_vec {
@@ -355,6 +485,52 @@ impl<T> TupleUnion<T> {
     }
 }
 
+// The number of values of a `TupleUnion`, given a function returning the
+// number of values of each option.
+macro_rules! tuple_union_domain_size {
+    ($self:ident, $size:expr, $($ix:tt)*) => { (|| {
+        let size = $size(&*((($self).0).0).1);
+        $(let size = size?.checked_add($size(&*((($self).0).$ix).1)?);)*
+        size
+    })() };
+}
+
+// The value tree at `$index` of the domain of a `TupleUnion`, given functions
+// returning the number of values of each option and creating the value tree
+// at an index of its domain.
+macro_rules! tuple_union_new_tree_at {
+    ($self:ident, $index:ident, $runner:ident, $size:expr, $new_tree_at:expr,
+     $($ix:tt)*) => { {
+        let sizes = [$size(&*((($self).0).0).1),
+                     $($size(&*((($self).0).$ix).1)),*];
+        let (pick, index) = locate_index(sizes.iter().cloned(), $index);
+
+        let mut options = || -> Result<_, Reason> { Ok((
+            if 0 == pick {
+                LazyValueTree::new_initialized(
+                    $new_tree_at(&*((($self).0).0).1, index, $runner)?)
+            } else {
+                LazyValueTree::new(Arc::clone(&((($self).0).0).1), $runner)
+            },
+            $(
+            if $ix == pick {
+                Some(LazyValueTree::new_initialized(
+                     $new_tree_at(&*((($self).0).$ix).1, index, $runner)?))
+            } else if $ix < pick {
+                Some(LazyValueTree::new(
+                        Arc::clone(&((($self).0).$ix).1), $runner))
+            } else {
+                None
+            }),*)) };
+        options().map(|options| TupleUnionValueTree {
+            options,
+            pick,
+            min_pick: 0,
+            prev_pick: None,
+        })
+    } };
+}
+
 macro_rules! tuple_union {
     ($($gen:ident $ix:tt)*) => {
         impl<A : Strategy, $($gen: Strategy<Value = A::Value>),*>
@@ -365,33 +541,70 @@ macro_rules! tuple_union {
 
             fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
                 let weights = [((self.0).0).0, $(((self.0).$ix).0),*];
-                let pick = pick_weighted(runner, weights.iter().cloned(),
-                                         weights.iter().cloned());
+                let pick = pick_arm(self, runner, &weights, || vec![
+                    describe_arm(0, &(self.0).0),
+                    $(describe_arm($ix, &(self.0).$ix)),*
+                ]);
+
+                let mut options = || Ok((
+                    if 0 == pick {
+                        LazyValueTree::new_initialized(
+                            ((self.0).0).1.new_tree(runner)?)
+                    } else {
+                        LazyValueTree::new(
+                            Arc::clone(&((self.0).0).1), runner)
+                    },
+                    $(
+                    if $ix == pick {
+                        Some(LazyValueTree::new_initialized(
+                             ((self.0).$ix).1.new_tree(runner)?))
+                    } else if $ix < pick {
+                        Some(LazyValueTree::new(
+                                Arc::clone(&((self.0).$ix).1), runner))
+                    } else {
+                        None
+                    }),*));
+                let options: Result<_, Reason> = options();
+                runner.union_arm_generated();
 
                 Ok(TupleUnionValueTree {
-                    options: (
-                        if 0 == pick {
-                            LazyValueTree::new_initialized(
-                                ((self.0).0).1.new_tree(runner)?)
-                        } else {
-                            LazyValueTree::new(
-                                Arc::clone(&((self.0).0).1), runner)
-                        },
-                        $(
-                        if $ix == pick {
-                            Some(LazyValueTree::new_initialized(
-                                 ((self.0).$ix).1.new_tree(runner)?))
-                        } else if $ix < pick {
-                            Some(LazyValueTree::new(
-                                    Arc::clone(&((self.0).$ix).1), runner))
-                        } else {
-                            None
-                        }),*),
-                    pick: pick,
+                    options: options?,
+                    pick,
                     min_pick: 0,
                     prev_pick: None,
                 })
             }
+
+            fn enumerable_domain_size(&self) -> Option<u64> {
+                tuple_union_domain_size!(
+                    self, Strategy::enumerable_domain_size, $($ix)*)
+            }
+
+            fn enumerable_new_tree_at(
+                &self,
+                index: u64,
+                runner: &mut TestRunner,
+            ) -> Option<NewTree<Self>> {
+                self.enumerable_domain_size()?;
+                Some(tuple_union_new_tree_at!(
+                    self, index, runner, Strategy::enumerable_domain_size,
+                    enumerated_tree_at, $($ix)*))
+            }
+        }
+
+        impl<A : Enumerable, $($gen: Enumerable<Value = A::Value>),*>
+        Enumerable for TupleUnion<(WA<A>, $(WA<$gen>),*)> {
+            fn domain_size(&self) -> Option<u64> {
+                tuple_union_domain_size!(
+                    self, Enumerable::domain_size, $($ix)*)
+            }
+
+            fn new_tree_at(&self, index: u64, runner: &mut TestRunner)
+                           -> NewTree<Self> {
+                tuple_union_new_tree_at!(
+                    self, index, runner, Enumerable::domain_size,
+                    Enumerable::new_tree_at, $($ix)*)
+            }
         }
     }
 }
@@ -684,6 +897,139 @@ mod test {
         );
     }
 
+    #[test]
+    fn enumerates_every_option() {
+        let mut runner = TestRunner::deterministic();
+
+        let union = Union::new_weighted(vec![(1, 0..2), (100, 10..13)]);
+        assert_eq!(Some(5), union.domain_size());
+        let values = (0..5)
+            .map(|ix| union.new_tree_at(ix, &mut runner).unwrap().current())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![0, 1, 10, 11, 12], values);
+
+        let union = TupleUnion::new((
+            (1, Arc::new(Just(-1))),
+            (1, Arc::new(3..5)),
+            (1, Arc::new(Just(7))),
+        ));
+        assert_eq!(Some(4), union.domain_size());
+        let values = (0..4)
+            .map(|ix| union.new_tree_at(ix, &mut runner).unwrap().current())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![-1, 3, 4, 7], values);
+
+        // Enumerated values still shrink into earlier options.
+        let mut tree = union.new_tree_at(3, &mut runner).unwrap();
+        assert!(tree.simplify());
+        assert!((3..5).contains(&tree.current()));
+    }
+
+    fn run_union_arms<S: Strategy<Value = i32>>(
+        union_arms: UnionArmCoverage,
+        cases: u32,
+        strategy: S,
+    ) -> (Result<(), TestError<i32>>, Vec<i32>) {
+        let mut runner = TestRunner::new(Config {
+            cases,
+            union_arms,
+            failure_persistence: None,
+            ..Config::default()
+        });
+        let values = core::cell::RefCell::new(Vec::new());
+        let result = runner.run(&strategy, |v| {
+            values.borrow_mut().push(v);
+            Ok(())
+        });
+        (result, values.into_inner())
+    }
+
+    #[test]
+    fn cycles_through_every_arm_first() {
+        let union = Union::new_weighted(vec![
+            (1000, Just(0)),
+            (1, Just(1)),
+            (1, Just(2)),
+        ]);
+        let (result, values) =
+            run_union_arms(UnionArmCoverage::Strict, 3, union);
+        assert!(result.is_ok());
+        assert_eq!(vec![0, 1, 2], values);
+
+        // Nested unions, like those of derived enums, are cycled through as
+        // soon as they are reached, while arms with no weight are left out.
+        let union = TupleUnion::new((
+            (1000, Arc::new(Just(0))),
+            (
+                1,
+                Arc::new(TupleUnion::new((
+                    (1000, Arc::new(Just(1))),
+                    (0, Arc::new(Just(2))),
+                    (1, Arc::new(Just(3))),
+                ))),
+            ),
+        ));
+        let (result, values) =
+            run_union_arms(UnionArmCoverage::Strict, 3, union);
+        assert!(result.is_ok());
+        assert_eq!(vec![0, 1, 3], values);
+    }
+
+    #[test]
+    fn reports_arms_never_chosen() {
+        let union = || {
+            TupleUnion::new((
+                (1, Arc::new(Just(0))),
+                (1, Arc::new(Just(1))),
+                (1, Arc::new(Just(2))),
+            ))
+        };
+
+        let (result, _) = run_union_arms(UnionArmCoverage::Cycle, 2, union());
+        assert!(result.is_ok());
+
+        match run_union_arms(UnionArmCoverage::Strict, 2, union()).0 {
+            Err(TestError::Abort(reason)) => {
+                let message = reason.message();
+                assert!(message.contains("1 union arm(s) never chosen"));
+                assert!(message.contains("arm 2 of union of `i32` (e.g. 2)"));
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn unions_built_for_every_case_pass_strict() {
+        // Keeping every union alive gives each case a union at a new
+        // address.
+        let unions = core::cell::RefCell::new(Vec::new());
+        let strategy = Just(()).prop_flat_map(|()| {
+            let union = Arc::new(TupleUnion::new((
+                (1, Arc::new(Just(0))),
+                (1, Arc::new(Just(1))),
+                (1, Arc::new(Just(2))),
+            )));
+            unions.borrow_mut().push(Arc::clone(&union));
+            union
+        });
+        let (result, mut values) =
+            run_union_arms(UnionArmCoverage::Strict, 64, strategy);
+        assert!(result.is_ok(), "{:?}", result);
+        values.sort();
+        values.dedup();
+        assert_eq!(vec![0, 1, 2], values);
+    }
+
+    #[test]
+    fn random_arms_are_not_tracked() {
+        let union =
+            Union::new_weighted(vec![(1_000_000, Just(0)), (1, Just(1))]);
+        let (result, values) =
+            run_union_arms(UnionArmCoverage::Random, 8, union);
+        assert!(result.is_ok());
+        assert_eq!(vec![0; 8], values);
+    }
+
     fn filter_sanity_options() -> CheckStrategySanityOptions {
         CheckStrategySanityOptions {
             // Due to internal rejection sampling, `simplify()` can
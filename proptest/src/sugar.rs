@@ -9,6 +9,9 @@
 
 use crate::std_facade::fmt;
 
+use crate::strategy::{Enumerable, Strategy};
+use crate::test_runner::{TestCaseResult, TestError, TestRunner};
+
 /// Easily define `proptest` tests.
 ///
 /// Within `proptest!`, define one or more functions without return type
@@ -274,6 +277,88 @@ macro_rules! prop_assume {
     };
 }
 
+/// Attaches a context note to the error of a `Result<_, TestCaseError>`.
+///
+/// This is invoked as `prop_context!(result, format, args...)`. If `result` is
+/// an `Err`, the formatted message, prefixed by the point of invocation, is
+/// attached to the error's `Reason` as a context note; otherwise `result` is
+/// returned unchanged. The message is only formatted if there is an error.
+///
+/// Context notes are printed along with the minimal failing input, which makes
+/// it possible to tell where and why a failure returned from a helper function
+/// happened. See also `TestCaseResultExt`.
+///
+/// ## Example
+///
+/// ```
+/// use proptest::prelude::*;
+///
+/// fn check_shard(shard: &[u32]) -> Result<(), TestCaseError> {
+///   prop_assert!(shard.iter().all(|&v| v < 1000));
+///   Ok(())
+/// }
+///
+/// proptest! {
+///   # /*
+///   #[test]
+///   # */
+///   fn all_shards_valid(shards in prop::collection::vec(
+///       prop::collection::vec(0u32..1000, 0..10), 0..10)) {
+///     for (i, shard) in shards.iter().enumerate() {
+///       prop_context!(check_shard(shard), "checking shard {}", i)?;
+///     }
+///   }
+/// }
+/// #
+/// # fn main() { all_shards_valid(); }
+/// ```
+#[macro_export]
+macro_rules! prop_context {
+    ($result:expr, $fmt:tt $(, $fmt_arg:expr)* $(,)?) => {
+        $crate::test_runner::TestCaseResultExt::with_context(
+            $result,
+            || format!(concat!("{}:{}:{}: ", $fmt),
+                       file!(), line!(), column!()
+                       $(, $fmt_arg)*))
+    };
+}
+
+/// Records a note about the current test case, which is only reported if the
+/// case turns out to be the final minimal failing case.
+///
+/// This is invoked like `format!`. Unlike printing, which happens for every
+/// case run while shrinking, notes are discarded for all cases but the one
+/// finally reported, where they are attached to the failure's `Reason` and
+/// shown after the minimal failing input.
+///
+/// Calling this outside a test case run by a `TestRunner` has no effect. See
+/// also `Config::capture_output` for capturing printed output in the same way.
+///
+/// ## Example
+///
+/// ```
+/// use proptest::prelude::*;
+///
+/// proptest! {
+///   # /*
+///   #[test]
+///   # */
+///   fn test_sum(v in prop::collection::vec(0u32..100, 0..10)) {
+///     let sum: u32 = v.iter().sum();
+///     prop_note!("sum = {}", sum);
+///     prop_assert!(sum <= 1000);
+///   }
+/// }
+/// #
+/// # fn main() { test_sum(); }
+/// ```
+#[macro_export]
+macro_rules! prop_note {
+    ($($fmt:tt)*) => {
+        $crate::test_runner::note(format!($($fmt)*))
+    };
+}
+
 /// Produce a strategy which picks one of the listed choices.
 ///
 /// This is conceptually equivalent to calling `prop_union` on the first two
@@ -840,9 +925,231 @@ macro_rules! prop_assert_ne {
     }};
 }
 
+/// Similar to `assert!(matches!(..))`, but returns a test failure instead of
+/// panicking if the value does not match the pattern.
+///
+/// The value is printed with `{:?}` on failure, so it must implement `Debug`.
+/// Like `matches!`, the pattern may be followed by an `if` guard. A custom
+/// message may be given after the pattern, which is added after the normal
+/// assertion message.
+///
+/// See `prop_assert!` for a more in-depth discussion.
+///
+/// ## Example
+///
+/// ```
+/// use proptest::prelude::*;
+///
+/// proptest! {
+///   # /*
+///   #[test]
+///   # */
+///   fn parse_digit(c in "[0-9]") {
+///     // Use with default message
+///     prop_assert_matches!(c.parse::<u8>(), Ok(0..=9));
+///     // Guards and custom messages are also supported
+///     prop_assert_matches!(c.parse::<u8>(), Ok(n) if n < 10,
+///                          "c = {:?}", c);
+///   }
+/// }
+/// #
+/// # fn main() { parse_digit(); }
+/// ```
+#[macro_export]
+macro_rules! prop_assert_matches {
+    ($value:expr, $($pattern:pat)|+ $(if $guard:expr)? $(,)?) => {
+        match $value {
+            $($pattern)|+ $(if $guard)? => (),
+            ref value => {
+                let message = format!(
+                    "assertion failed: `{:?}` does not match `{}`",
+                    value, stringify!($($pattern)|+ $(if $guard)?));
+                let message = format!(
+                    "{} at {}:{}", message, file!(), line!());
+                return ::core::result::Result::Err(
+                    $crate::test_runner::TestCaseError::fail(message));
+            }
+        }
+    };
+
+    ($value:expr, $($pattern:pat)|+ $(if $guard:expr)?,
+     $fmt:tt $($args:tt)*) => {
+        match $value {
+            $($pattern)|+ $(if $guard)? => (),
+            ref value => {
+                let message = format!(
+                    concat!("assertion failed: `{:?}` does not match `{}`: ",
+                            $fmt),
+                    value, stringify!($($pattern)|+ $(if $guard)?)
+                    $($args)*);
+                let message = format!(
+                    "{} at {}:{}", message, file!(), line!());
+                return ::core::result::Result::Err(
+                    $crate::test_runner::TestCaseError::fail(message));
+            }
+        }
+    };
+}
+
+/// Asserts that two floating-point values are approximately equal, returning
+/// a test failure instead of panicking if they are not.
+///
+/// This works with `f32` and `f64`. The tolerance is given after the two
+/// values as one of the following:
+///
+/// - `abs = tolerance`: the absolute difference between the values must be at
+///   most `tolerance`.
+///
+/// - `rel = tolerance`: the absolute difference between the values must be at
+///   most `tolerance` times the larger of their magnitudes.
+///
+/// - `ulps = n`: the values must be at most `n` representable values apart.
+///   Positive and negative zero are considered to be the same value.
+///
+/// Two NaNs are considered equal to each other, so that comparing the results
+/// of two computations which both produce NaN passes. A NaN is never equal to
+/// a non-NaN value. Infinities are only equal to themselves.
+///
+/// As with `prop_assert_eq!`, a custom message may be given after the
+/// tolerance.
+///
+/// See `prop_assert!` for a more in-depth discussion.
+///
+/// ## Example
+///
+/// ```
+/// use proptest::prelude::*;
+///
+/// proptest! {
+///   # /*
+///   #[test]
+///   # */
+///   fn sqrt_squared(a in 0.0f64..1e6) {
+///     prop_assert_approx_eq!(a.sqrt() * a.sqrt(), a, rel = 1e-12);
+///     prop_assert_approx_eq!(a.sqrt() * a.sqrt(), a, ulps = 4,
+///                            "a = {}", a);
+///     prop_assert_approx_eq!((a + 1.0) - 1.0, a, abs = 1e-9);
+///   }
+/// }
+/// #
+/// # fn main() { sqrt_squared(); }
+/// ```
+#[macro_export]
+macro_rules! prop_assert_approx_eq {
+    ($left:expr, $right:expr, $kind:ident = $tol:expr $(,)?) => {{
+        let left = $left;
+        let right = $right;
+        let tolerance = $tol;
+        $crate::prop_assert!(
+            $crate::sugar::ApproxEq::approx_eq(
+                left, right, $crate::sugar::Tolerance::$kind(tolerance)),
+            "assertion failed: `(left ~= right)` ({} = {:?}) \
+             \n  left: `{:?}`,\n right: `{:?}`",
+            stringify!($kind), tolerance, left, right);
+    }};
+
+    ($left:expr, $right:expr, $kind:ident = $tol:expr,
+     $fmt:tt $($args:tt)*) => {{
+        let left = $left;
+        let right = $right;
+        let tolerance = $tol;
+        $crate::prop_assert!(
+            $crate::sugar::ApproxEq::approx_eq(
+                left, right, $crate::sugar::Tolerance::$kind(tolerance)),
+            concat!(
+                "assertion failed: `(left ~= right)` ({} = {:?}) \
+                 \n  left: `{:?}`,\n right: `{:?}`: ", $fmt),
+            stringify!($kind), tolerance, left, right $($args)*);
+    }};
+}
+
+/// Similar to `assert!(left < right)`, but returns a test failure instead of
+/// panicking if the condition fails, and prints both operands.
+///
+/// As with `prop_assert_eq!`, a custom message may be given after the
+/// operands. See `prop_assert!` for a more in-depth discussion.
+///
+/// ## Example
+///
+/// ```
+/// use proptest::prelude::*;
+///
+/// proptest! {
+///   # /*
+///   #[test]
+///   # */
+///   fn test_ordering(a in 0i32..100i32, b in 1i32..100i32) {
+///     prop_assert_lt!(a, a + b);
+///     prop_assert_le!(a, a + b - 1, "a = {}, b = {}", a, b);
+///     prop_assert_gt!(a + b, a);
+///     prop_assert_ge!(a + b - 1, a);
+///   }
+/// }
+/// #
+/// # fn main() { test_ordering(); }
+/// ```
+#[macro_export]
+macro_rules! prop_assert_lt {
+    ($($args:tt)*) => {
+        $crate::proptest_helper!(@_CMP < $($args)*)
+    };
+}
+
+/// Similar to `assert!(left <= right)`, but returns a test failure instead of
+/// panicking if the condition fails, and prints both operands.
+///
+/// See `prop_assert_lt!` for details.
+#[macro_export]
+macro_rules! prop_assert_le {
+    ($($args:tt)*) => {
+        $crate::proptest_helper!(@_CMP <= $($args)*)
+    };
+}
+
+/// Similar to `assert!(left > right)`, but returns a test failure instead of
+/// panicking if the condition fails, and prints both operands.
+///
+/// See `prop_assert_lt!` for details.
+#[macro_export]
+macro_rules! prop_assert_gt {
+    ($($args:tt)*) => {
+        $crate::proptest_helper!(@_CMP > $($args)*)
+    };
+}
+
+/// Similar to `assert!(left >= right)`, but returns a test failure instead of
+/// panicking if the condition fails, and prints both operands.
+///
+/// See `prop_assert_lt!` for details.
+#[macro_export]
+macro_rules! prop_assert_ge {
+    ($($args:tt)*) => {
+        $crate::proptest_helper!(@_CMP >= $($args)*)
+    };
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! proptest_helper {
+    // The shared implementation of `prop_assert_lt!` and friends.
+    (@_CMP $op:tt $left:expr, $right:expr $(,)?) => {{
+        let left = $left;
+        let right = $right;
+        $crate::prop_assert!(
+            left $op right,
+            concat!("assertion failed: `(left ", stringify!($op), " right)`\
+                     \n  left: `{:?}`,\n right: `{:?}`"),
+            left, right);
+    }};
+    (@_CMP $op:tt $left:expr, $right:expr, $fmt:tt $($args:tt)*) => {{
+        let left = $left;
+        let right = $right;
+        $crate::prop_assert!(
+            left $op right,
+            concat!("assertion failed: `(left ", stringify!($op), " right)`\
+                     \n  left: `{:?}`,\n right: `{:?}`: ", $fmt),
+            left, right $($args)*);
+    }};
     (@_WRAP ($a:tt)) => { $a };
     (@_WRAP ($a0:tt $a1:tt)) => { ($a0, $a1) };
     (@_WRAP ($a0:tt $a1:tt $a2:tt)) => { ($a0, $a1, $a2) };
@@ -948,10 +1255,14 @@ macro_rules! proptest_helper {
         $config.source_file = Some(file!());
         let mut runner = $crate::test_runner::TestRunner::new($config);
         let names = $crate::proptest_helper!(@_WRAPSTR ($($parm),*));
-        match runner.run(
+        #[allow(unused_imports)]
+        use $crate::sugar::{RunEnumerable as _, RunStrategy as _};
+        match (&$crate::sugar::RunCases(
             &$crate::strategy::Strategy::prop_map(
                 $crate::proptest_helper!(@_WRAP ($($strategy)*)),
                 |values| $crate::sugar::NamedArguments(names, values)),
+        )).run_cases(
+            &mut runner,
             $($mod)* |$crate::sugar::NamedArguments(
                 _, $crate::proptest_helper!(@_WRAPPAT ($($parm),*)))|
             {
@@ -968,10 +1279,14 @@ macro_rules! proptest_helper {
         $config.source_file = Some(file!());
         let mut runner = $crate::test_runner::TestRunner::new($config);
         let names = $crate::proptest_helper!(@_EXT _STR ($($arg)*));
-        match runner.run(
+        #[allow(unused_imports)]
+        use $crate::sugar::{RunEnumerable as _, RunStrategy as _};
+        match (&$crate::sugar::RunCases(
             &$crate::strategy::Strategy::prop_map(
                 $crate::proptest_helper!(@_EXT _STRAT ($($arg)*)),
                 |values| $crate::sugar::NamedArguments(names, values)),
+        )).run_cases(
+            &mut runner,
             $($mod)* |$crate::sugar::NamedArguments(
                 _, $crate::proptest_helper!(@_EXT _PAT ($($arg)*)))|
             {
@@ -1051,6 +1366,69 @@ macro_rules! proptest_helper {
     };
 }
 
+/// Runs the cases of a test defined with `proptest!`.
+///
+/// Whether the strategy is `Enumerable` is decided by method resolution in
+/// the expansion of the macro, where the type of the strategy is known:
+/// `RunEnumerable` is implemented for `RunCases` itself, so it takes
+/// precedence over `RunStrategy`, which is implemented for references to
+/// it, whenever the strategy is `Enumerable`.
+#[doc(hidden)]
+pub struct RunCases<'a, S>(pub &'a S);
+
+#[doc(hidden)]
+pub trait RunEnumerable {
+    type Strategy: Strategy;
+
+    /// Runs every value of the strategy if `Config::exhaustive` is set, and
+    /// random cases otherwise.
+    fn run_cases(
+        &self,
+        runner: &mut TestRunner,
+        test: impl Fn(<Self::Strategy as Strategy>::Value) -> TestCaseResult,
+    ) -> Result<(), TestError<<Self::Strategy as Strategy>::Value>>;
+}
+
+impl<'a, S: Enumerable> RunEnumerable for RunCases<'a, S> {
+    type Strategy = S;
+
+    fn run_cases(
+        &self,
+        runner: &mut TestRunner,
+        test: impl Fn(S::Value) -> TestCaseResult,
+    ) -> Result<(), TestError<S::Value>> {
+        if runner.config().exhaustive {
+            runner.run_exhaustive(self.0, test)
+        } else {
+            runner.run(self.0, test)
+        }
+    }
+}
+
+#[doc(hidden)]
+pub trait RunStrategy {
+    type Strategy: Strategy;
+
+    /// Runs random cases.
+    fn run_cases(
+        &self,
+        runner: &mut TestRunner,
+        test: impl Fn(<Self::Strategy as Strategy>::Value) -> TestCaseResult,
+    ) -> Result<(), TestError<<Self::Strategy as Strategy>::Value>>;
+}
+
+impl<'a, 'b, S: Strategy> RunStrategy for &'b RunCases<'a, S> {
+    type Strategy = S;
+
+    fn run_cases(
+        &self,
+        runner: &mut TestRunner,
+        test: impl Fn(S::Value) -> TestCaseResult,
+    ) -> Result<(), TestError<S::Value>> {
+        runner.run(self.0, test)
+    }
+}
+
 #[doc(hidden)]
 #[derive(Clone, Copy)]
 pub struct NamedArguments<N, V>(#[doc(hidden)] pub N, #[doc(hidden)] pub V);
@@ -1121,6 +1499,89 @@ named_arguments_tuple!(0 AN AV 1 BN BV 2 CN CV 3 DN DV 4 EN EV
 named_arguments_tuple!(0 AN AV 1 BN BV 2 CN CV 3 DN DV 4 EN EV
                        5 FN FV 6 GN GV 7 HN HV 8 IN IV 9 JN JV);
 
+/// The tolerance used by `prop_assert_approx_eq!`.
+#[doc(hidden)]
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug)]
+pub enum Tolerance<T> {
+    abs(T),
+    rel(T),
+    ulps(u64),
+}
+
+/// Floating-point types which can be compared by `prop_assert_approx_eq!`.
+#[doc(hidden)]
+pub trait ApproxEq: Copy + fmt::Debug {
+    /// Returns whether `self` and `other` are equal within `tolerance`.
+    fn approx_eq(self, other: Self, tolerance: Tolerance<Self>) -> bool;
+}
+
+macro_rules! approx_eq_float {
+    ($typ:ty, $bits:ty, $ordered:ty) => {
+        impl ApproxEq for $typ {
+            fn approx_eq(
+                self,
+                other: Self,
+                tolerance: Tolerance<Self>,
+            ) -> bool {
+                // Like `check_strategy_sanity`, treat two NaNs as the same.
+                if self != self || other != other {
+                    return self != self && other != other;
+                }
+                // This also handles infinities, which would otherwise
+                // produce NaN differences.
+                if self == other {
+                    return true;
+                }
+
+                fn abs(v: $typ) -> $typ {
+                    if v < 0.0 {
+                        -v
+                    } else {
+                        v
+                    }
+                }
+
+                if abs(self) == <$typ>::INFINITY
+                    || abs(other) == <$typ>::INFINITY
+                {
+                    return false;
+                }
+
+                // Maps the bits of a float to an integer which is ordered the
+                // same way as the float, with both zeroes mapping to 0.
+                fn ordered(v: $typ) -> $ordered {
+                    const SIGN: $bits = 1 << (<$bits>::MAX.count_ones() - 1);
+                    let bits = v.to_bits();
+                    if 0 != bits & SIGN {
+                        -((bits & !SIGN) as $ordered)
+                    } else {
+                        bits as $ordered
+                    }
+                }
+
+                let diff = abs(self - other);
+                match tolerance {
+                    Tolerance::abs(tol) => diff <= tol,
+                    Tolerance::rel(tol) => {
+                        let (a, b) = (abs(self), abs(other));
+                        diff <= tol * if a > b { a } else { b }
+                    }
+                    Tolerance::ulps(ulps) => {
+                        let distance = ordered(self) - ordered(other);
+                        let distance =
+                            if distance < 0 { -distance } else { distance };
+                        distance as u128 <= ulps as u128
+                    }
+                }
+            }
+        }
+    };
+}
+
+approx_eq_float!(f32, u32, i64);
+approx_eq_float!(f64, u64, i128);
+
 #[cfg(feature = "std")]
 #[doc(hidden)]
 pub fn force_no_fork(config: &mut crate::test_runner::Config) {
@@ -1326,6 +1787,104 @@ mod test {
         );
     }
 
+    #[test]
+    fn extra_assertions_pass_and_fail() {
+        use crate::std_facade::String;
+        use crate::test_runner::{TestCaseError, TestCaseResult};
+
+        fn check(f: impl Fn() -> TestCaseResult) -> Option<String> {
+            match f() {
+                Ok(()) => None,
+                Err(TestCaseError::Fail(why)) => {
+                    Some(String::from(why.message()))
+                }
+                Err(e) => panic!("Unexpected result: {:?}", e),
+            }
+        }
+
+        assert_eq!(
+            None,
+            check(|| {
+                prop_assert_matches!(Some(3), Some(1..=3));
+                prop_assert_matches!(Some(3), None | Some(1) | Some(3));
+                prop_assert_matches!(Some(3), Some(x) if x > 2, "x");
+                Ok(())
+            })
+        );
+        let message = check(|| {
+            prop_assert_matches!(Some(4), Some(x) if x < 4, "extra {}", 42);
+            Ok(())
+        })
+        .unwrap();
+        assert!(message.contains("`Some(4)` does not match `Some(x) if x < 4`"));
+        assert!(message.contains("extra 42"));
+        assert!(message.contains("sugar.rs"));
+
+        assert_eq!(
+            None,
+            check(|| {
+                prop_assert_lt!(1, 2);
+                prop_assert_le!(2, 2);
+                prop_assert_gt!(3, 2, "msg");
+                prop_assert_ge!(2, 2, "msg {}", 1);
+                Ok(())
+            })
+        );
+        let message = check(|| {
+            prop_assert_le!(3, 2, "extra {}", 42);
+            Ok(())
+        })
+        .unwrap();
+        assert!(message.contains("`(left <= right)`"));
+        assert!(message.contains("left: `3`"));
+        assert!(message.contains("right: `2`"));
+        assert!(message.contains("extra 42"));
+        assert!(check(|| {
+            prop_assert_gt!("a", "b");
+            Ok(())
+        })
+        .is_some());
+
+        assert_eq!(
+            None,
+            check(|| {
+                prop_assert_approx_eq!(1.0f64, 1.0 + 1e-10, abs = 1e-9);
+                prop_assert_approx_eq!(1e10f64, 1e10 + 1.0, rel = 1e-9);
+                prop_assert_approx_eq!(0.1f32 + 0.2, 0.3, ulps = 1);
+                prop_assert_approx_eq!(0.0f64, -0.0, ulps = 0);
+                prop_assert_approx_eq!(
+                    -f64::MIN_POSITIVE,
+                    f64::MIN_POSITIVE,
+                    ulps = 2 * (1 << 52)
+                );
+                prop_assert_approx_eq!(f64::NAN, f64::NAN, abs = 0.0);
+                prop_assert_approx_eq!(f32::INFINITY, f32::INFINITY, rel = 0.0);
+                Ok(())
+            })
+        );
+        assert!(check(|| {
+            prop_assert_approx_eq!(1.0f64, 1.1, abs = 0.01, "extra");
+            Ok(())
+        })
+        .unwrap()
+        .contains("(abs = 0.01)"));
+        assert!(check(|| {
+            prop_assert_approx_eq!(1.0f64, f64::NAN, abs = f64::INFINITY);
+            Ok(())
+        })
+        .is_some());
+        assert!(check(|| {
+            prop_assert_approx_eq!(f32::INFINITY, f32::MAX, rel = 1.0);
+            Ok(())
+        })
+        .is_some());
+        assert!(check(|| {
+            prop_assert_approx_eq!(1.0f64, 1.0 + 2.0 * f64::EPSILON, ulps = 1);
+            Ok(())
+        })
+        .is_some());
+    }
+
     #[test]
     fn oneof_all_counts() {
         use crate::strategy::{Just as J, Strategy, TupleUnion, Union};
@@ -1467,6 +2026,45 @@ mod test_timeout {
     }
 }
 
+#[cfg(all(test, feature = "std"))]
+mod exhaustive_test {
+    use crate::std_facade::{BTreeSet, Vec};
+    use crate::test_runner::Config;
+    use core::cell::RefCell;
+
+    thread_local! {
+        static SEEN: RefCell<Vec<(u8, bool)>> = RefCell::new(Vec::new());
+    }
+
+    proptest! {
+        #![proptest_config(Config {
+            cases: 256,
+            exhaustive: true,
+            failure_persistence: None,
+            .. Config::default()
+        })]
+
+        fn record_inputs(a in 0u8..4, b: bool) {
+            SEEN.with(|seen| seen.borrow_mut().push((a, b)));
+        }
+
+        #[test]
+        fn exhaustive_accepts_inputs_which_are_not_enumerable(
+            s in "[a-z]", _b: bool
+        ) {
+            prop_assert_eq!(1, s.len());
+        }
+    }
+
+    #[test]
+    fn exhaustive_runs_every_input_once() {
+        record_inputs();
+        let seen = SEEN.with(|seen| seen.borrow().clone());
+        assert_eq!(8, seen.len());
+        assert_eq!(8, seen.iter().collect::<BTreeSet<_>>().len());
+    }
+}
+
 #[cfg(test)]
 mod another_test {
     use crate::sugar;
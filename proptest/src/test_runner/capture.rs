@@ -0,0 +1,174 @@
+//-
+// Copyright 2021 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for capturing notes and output produced by individual test cases.
+
+#[cfg(feature = "std")]
+use crate::std_facade::Vec;
+use crate::std_facade::{fmt, Box, String};
+use core::any::Any;
+#[cfg(feature = "std")]
+use std::cell::RefCell;
+#[cfg(feature = "fork")]
+use std::fs;
+
+/// A hook which captures the output produced while a single test case runs.
+///
+/// When `Config::capture_output` is enabled and tests are run in-process, the
+/// `TestRunner` clones the hook in `Config::output_capture`, then calls
+/// `begin()` immediately before running each test case and `end()`
+/// immediately after it. The output returned for the final minimal failing
+/// case is attached to the failure's `Reason`; the output of all other cases
+/// is discarded.
+///
+/// Proptest does not provide an implementation of this trait, since the
+/// standard library has no stable way to redirect `print!` and friends.
+pub trait OutputCapture: Send + Sync + fmt::Debug {
+    /// Start capturing output.
+    fn begin(&mut self);
+
+    /// Stop capturing output and return what was captured since the last call
+    /// to `begin()`, or `None` if this hook is unable to capture anything.
+    fn end(&mut self) -> Option<String>;
+
+    /// Delegate method for producing a trait object usable with `Clone`
+    fn box_clone(&self) -> Box<dyn OutputCapture>;
+
+    /// Equality testing delegate required due to constraints of trait objects.
+    fn eq(&self, other: &dyn OutputCapture) -> bool;
+
+    /// Assistant method for trait object comparison.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<'a, 'b> PartialEq<dyn OutputCapture + 'b> for dyn OutputCapture + 'a {
+    fn eq(&self, other: &(dyn OutputCapture + 'b)) -> bool {
+        OutputCapture::eq(self, other)
+    }
+}
+
+impl Clone for Box<dyn OutputCapture> {
+    fn clone(&self) -> Box<dyn OutputCapture> {
+        self.box_clone()
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+struct Notes {
+    notes: Vec<String>,
+    /// In a forked child process, the replay file each note is also written
+    /// to as soon as it is recorded, so that the notes of a test case which
+    /// crashes the child are not lost.
+    #[cfg(feature = "fork")]
+    echo: Option<fs::File>,
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    static NOTES: RefCell<Option<Notes>> = RefCell::new(None);
+}
+
+/// Record a note about the test case currently running on this thread.
+///
+/// Notes are discarded unless the test case turns out to be the final minimal
+/// failing case, in which case they are attached to the failure's `Reason`.
+/// Calling this outside of a test case run by a `TestRunner` has no effect.
+///
+/// This is usually called through `prop_note!`. Without the `std` feature,
+/// this does nothing.
+pub fn note(message: impl Into<String>) {
+    #[cfg(feature = "std")]
+    NOTES.with(|notes| {
+        if let Some(ref mut notes) = *notes.borrow_mut() {
+            let message = message.into();
+            #[cfg(feature = "fork")]
+            {
+                if let Some(ref mut echo) = notes.echo {
+                    crate::test_runner::replay::append_note(echo, &message)
+                        .expect("Failed to append to replay file");
+                }
+            }
+            notes.notes.push(message);
+        }
+    });
+    #[cfg(not(feature = "std"))]
+    let _ = message;
+}
+
+/// Collects the notes recorded while it is alive.
+///
+/// Collectors nest, so a test case which itself runs a `TestRunner` does not
+/// lose its own notes.
+#[cfg(feature = "std")]
+pub(crate) struct NoteCollector {
+    outer: Option<Option<Notes>>,
+}
+
+#[cfg(feature = "std")]
+impl NoteCollector {
+    pub(crate) fn begin() -> Self {
+        Self::begin_with(Notes::default())
+    }
+
+    /// Like `begin()`, but also appends each note to the replay file `echo`
+    /// as soon as it is recorded.
+    #[cfg(feature = "fork")]
+    pub(crate) fn begin_echoing(echo: fs::File) -> Self {
+        Self::begin_with(Notes {
+            echo: Some(echo),
+            ..Notes::default()
+        })
+    }
+
+    fn begin_with(notes: Notes) -> Self {
+        NoteCollector {
+            outer: Some(NOTES.with(|cell| cell.replace(Some(notes)))),
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> Vec<String> {
+        let outer = self.outer.take().unwrap_or(None);
+        NOTES
+            .with(|notes| notes.replace(outer))
+            .map(|notes| notes.notes)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for NoteCollector {
+    fn drop(&mut self) {
+        if let Some(outer) = self.outer.take() {
+            NOTES.with(|notes| *notes.borrow_mut() = outer);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn notes_only_collected_while_collecting() {
+        note("dropped");
+        let outer = NoteCollector::begin();
+        note("outer 1");
+        let inner = NoteCollector::begin();
+        note("inner");
+        assert_eq!(vec![String::from("inner")], inner.finish());
+        note("outer 2");
+        assert_eq!(
+            vec![String::from("outer 1"), String::from("outer 2")],
+            outer.finish()
+        );
+        note("dropped");
+        assert!(NOTES.with(|notes| notes.borrow().is_none()));
+    }
+}
@@ -19,8 +19,11 @@ use std::fmt;
 #[cfg(feature = "std")]
 use std::str::FromStr;
 
+use crate::test_runner::capture::OutputCapture;
+use crate::test_runner::fingerprint::FailureFingerprint;
 use crate::test_runner::result_cache::{noop_result_cache, ResultCache};
 use crate::test_runner::rng::RngAlgorithm;
+use crate::test_runner::union_arms::UnionArmCoverage;
 use crate::test_runner::FailurePersistence;
 #[cfg(feature = "std")]
 use crate::test_runner::FileFailurePersistence;
@@ -28,6 +31,10 @@ use crate::test_runner::FileFailurePersistence;
 #[cfg(feature = "std")]
 const CASES: &str = "PROPTEST_CASES";
 #[cfg(feature = "std")]
+const GROW_SIZE: &str = "PROPTEST_GROW_SIZE";
+#[cfg(feature = "std")]
+const EXHAUSTIVE: &str = "PROPTEST_EXHAUSTIVE";
+#[cfg(feature = "std")]
 const MAX_LOCAL_REJECTS: &str = "PROPTEST_MAX_LOCAL_REJECTS";
 #[cfg(feature = "std")]
 const MAX_GLOBAL_REJECTS: &str = "PROPTEST_MAX_GLOBAL_REJECTS";
@@ -37,13 +44,19 @@ const MAX_FLAT_MAP_REGENS: &str = "PROPTEST_MAX_FLAT_MAP_REGENS";
 const MAX_SHRINK_TIME: &str = "PROPTEST_MAX_SHRINK_TIME";
 #[cfg(feature = "std")]
 const MAX_SHRINK_ITERS: &str = "PROPTEST_MAX_SHRINK_ITERS";
+#[cfg(feature = "std")]
+const FLAKY_RERUNS: &str = "PROPTEST_FLAKY_RERUNS";
 #[cfg(feature = "fork")]
 const FORK: &str = "PROPTEST_FORK";
 #[cfg(feature = "timeout")]
 const TIMEOUT: &str = "PROPTEST_TIMEOUT";
 #[cfg(feature = "std")]
 const VERBOSE: &str = "PROPTEST_VERBOSE";
+#[cfg(feature = "std")]
+const CAPTURE_OUTPUT: &str = "PROPTEST_CAPTURE_OUTPUT";
 const RNG_ALGORITHM: &str = "PROPTEST_RNG_ALGORITHM";
+#[cfg(feature = "std")]
+const UNION_ARMS: &str = "PROPTEST_UNION_ARMS";
 
 #[cfg(feature = "std")]
 fn contextualize_config(mut result: Config) -> Config {
@@ -79,6 +92,15 @@ fn contextualize_config(mut result: Config) -> Config {
     {
         match var.as_str() {
             CASES => parse_or_warn(&value, &mut result.cases, "u32", CASES),
+            GROW_SIZE => {
+                parse_or_warn(&value, &mut result.grow_size, "bool", GROW_SIZE)
+            }
+            EXHAUSTIVE => parse_or_warn(
+                &value,
+                &mut result.exhaustive,
+                "bool",
+                EXHAUSTIVE,
+            ),
             MAX_LOCAL_REJECTS => parse_or_warn(
                 &value,
                 &mut result.max_local_rejects,
@@ -115,15 +137,33 @@ fn contextualize_config(mut result: Config) -> Config {
                 "u32",
                 MAX_SHRINK_ITERS,
             ),
+            FLAKY_RERUNS => parse_or_warn(
+                &value,
+                &mut result.flaky_reruns,
+                "u32",
+                FLAKY_RERUNS,
+            ),
             VERBOSE => {
                 parse_or_warn(&value, &mut result.verbose, "u32", VERBOSE)
             }
+            CAPTURE_OUTPUT => parse_or_warn(
+                &value,
+                &mut result.capture_output,
+                "bool",
+                CAPTURE_OUTPUT,
+            ),
             RNG_ALGORITHM => parse_or_warn(
                 &value,
                 &mut result.rng_algorithm,
                 "RngAlgorithm",
                 RNG_ALGORITHM,
             ),
+            UNION_ARMS => parse_or_warn(
+                &value,
+                &mut result.union_arms,
+                "UnionArmCoverage",
+                UNION_ARMS,
+            ),
 
             _ => {
                 if var.starts_with("PROPTEST_") {
@@ -141,9 +181,11 @@ fn contextualize_config(result: Config) -> Config {
     result
 }
 
-fn default_default_config() -> Config {
+pub(crate) fn default_default_config() -> Config {
     Config {
         cases: 256,
+        grow_size: false,
+        exhaustive: false,
         max_local_rejects: 65_536,
         max_global_rejects: 1024,
         max_flat_map_regens: 1_000_000,
@@ -157,10 +199,16 @@ fn default_default_config() -> Config {
         #[cfg(feature = "std")]
         max_shrink_time: 0,
         max_shrink_iters: u32::MAX,
+        flaky_reruns: 0,
+        failure_fingerprint: FailureFingerprint::Any,
         result_cache: noop_result_cache,
         #[cfg(feature = "std")]
         verbose: 0,
+        #[cfg(feature = "std")]
+        capture_output: false,
+        output_capture: None,
         rng_algorithm: RngAlgorithm::default(),
+        union_arms: UnionArmCoverage::Random,
         _non_exhaustive: (),
     }
 }
@@ -185,6 +233,30 @@ pub struct Config {
     /// `PROPTEST_CASES` environment variable.
     pub cases: u32,
 
+    /// If true, the size of the `TestRunner` (see `TestRunner::size()`)
+    /// grows linearly from small to `MAX_SIZE` over the `cases` of a run, so
+    /// that early cases generate small values (e.g., short collections and
+    /// strings, and shallow recursive values) and later cases explore values
+    /// up to the full size the strategies allow.
+    ///
+    /// The sizes of failing cases are persisted along with their seeds, so
+    /// that they are replayed with the same size.
+    ///
+    /// The default is `false`, which can be overridden by setting the
+    /// `PROPTEST_GROW_SIZE` environment variable.
+    pub grow_size: bool,
+
+    /// If true, tests defined with the `proptest!` macro whose inputs all
+    /// come from `Enumerable` strategies are run with
+    /// `TestRunner::run_exhaustive()`, so every combination of inputs is
+    /// tested if there are no more of them than `cases`. Tests with other
+    /// inputs, tests which fork, and tests run with `TestRunner::run()`, are
+    /// unaffected.
+    ///
+    /// The default is `false`, which can be overridden by setting the
+    /// `PROPTEST_EXHAUSTIVE` environment variable.
+    pub exhaustive: bool,
+
     /// The maximum number of individual inputs that may be rejected before the
     /// test as a whole aborts.
     ///
@@ -303,6 +375,32 @@ pub struct Config {
     /// `PROPTEST_MAX_SHRINK_ITERS` environment variable.
     pub max_shrink_iters: u32,
 
+    /// The number of times to re-run the minimal failing input found by
+    /// shrinking to check that it fails consistently.
+    ///
+    /// If any re-run does not fail, the test is reported as
+    /// `TestError::Flaky`, with the number of re-runs which passed and
+    /// failed, instead of `TestError::Fail`. Flaky failures are not persisted,
+    /// since their seeds could not be relied upon to reproduce the failure.
+    ///
+    /// Re-runs bypass the result cache.
+    ///
+    /// The default is `0` (i.e., no re-runs), which can be overridden by
+    /// setting the `PROPTEST_FLAKY_RERUNS` environment variable.
+    pub flaky_reruns: u32,
+
+    /// How to tell whether a failure found while shrinking is the same bug as
+    /// the failure being shrunk.
+    ///
+    /// Shrinking only accepts simplifications which fail with the same
+    /// fingerprint as the original failure; failures with other fingerprints
+    /// are recorded and reported separately (see
+    /// `TestRunner::distinct_failures()`). This has no effect when forking.
+    ///
+    /// The default is `FailureFingerprint::Any`, which treats all failures as
+    /// the same bug.
+    pub failure_fingerprint: FailureFingerprint,
+
     /// A function to create new result caches.
     ///
     /// The default is to do no caching. The easiest way to enable caching is
@@ -339,6 +437,34 @@ pub struct Config {
     #[cfg(feature = "std")]
     pub verbose: u32,
 
+    /// If true, the output of each test case is captured, and only the output
+    /// of the final minimal failing case is reported, as part of the `Reason`
+    /// of the failure.
+    ///
+    /// When forking, everything the child process writes to its standard
+    /// output and standard error is captured. Otherwise, output is captured
+    /// with the `output_capture` hook, if there is one; without one, nothing
+    /// is captured and the runner prints a warning.
+    ///
+    /// Notes recorded with `prop_note!` are always reported for the final
+    /// minimal failing case, in `Reason::notes()`, regardless of this setting
+    /// and of whether the test is run in a forked process.
+    ///
+    /// This is only available with the `std` feature (enabled by default).
+    ///
+    /// The default is `false`, which can be overridden by setting the
+    /// `PROPTEST_CAPTURE_OUTPUT` environment variable.
+    #[cfg(feature = "std")]
+    pub capture_output: bool,
+
+    /// The hook used to capture the output of test cases run in-process when
+    /// `capture_output` is enabled. Each test case is run with its own clone
+    /// of the hook.
+    ///
+    /// The default is `None`, in which case only forked test cases have their
+    /// output captured.
+    pub output_capture: Option<Box<dyn OutputCapture>>,
+
     /// The RNG algorithm to use when not using a user-provided RNG.
     ///
     /// The default is `RngAlgorithm::default()`, which can be overridden by
@@ -348,6 +474,24 @@ pub struct Config {
     /// - `cc` — `RngAlgorithm::ChaCha`
     pub rng_algorithm: RngAlgorithm,
 
+    /// How the arms of unions, such as those created by `prop_oneof!` or
+    /// derived `Arbitrary` implementations for enums, are chosen.
+    ///
+    /// With `Cycle` or `Strict`, the first cases of the run go through every
+    /// arm of each union in turn, regardless of its weight, and arms which
+    /// were still never chosen when the run ends are reported: `Cycle` warns
+    /// about them on stderr, while `Strict` fails the test. Unions are told
+    /// apart by where they are stored, so unions built anew for every case,
+    /// such as within `prop_flat_map`, are each tracked on their own.
+    ///
+    /// Cases whose unions were cycled are not reproduced from their persisted
+    /// seeds, since the arms they chose did not come from the RNG.
+    ///
+    /// The default is `UnionArmCoverage::Random`, which can be overridden by
+    /// setting the `PROPTEST_UNION_ARMS` environment variable to `random`,
+    /// `cycle` or `strict`.
+    pub union_arms: UnionArmCoverage,
+
     // Needs to be public so FRU syntax can be used.
     #[doc(hidden)]
     pub _non_exhaustive: (),
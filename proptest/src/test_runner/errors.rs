@@ -7,10 +7,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use crate::std_facade::fmt;
-
-#[cfg(feature = "std")]
-use std::string::ToString;
+use crate::std_facade::{fmt, Cow};
 
 use crate::test_runner::Reason;
 
@@ -22,7 +19,12 @@ use crate::test_runner::Reason;
 /// provided.
 ///
 /// Any `Error` can be converted to a `TestCaseError`, which places
-/// `Error::display()` into the `Fail` case.
+/// `Error::display()` into the `Fail` case, retaining the `Display` forms of
+/// the error's `source()` chain as the causes of the `Reason`.
+///
+/// Context notes can be attached to failures as they propagate out of helper
+/// functions with `context()`, `TestCaseResultExt`, or `prop_context!`. Both
+/// the causes and the notes are printed along with the minimal failing input.
 #[derive(Debug, Clone)]
 pub enum TestCaseError {
     /// The input was not valid for the test case. This does not count as a
@@ -54,6 +56,69 @@ impl TestCaseError {
     pub fn fail(reason: impl Into<Reason>) -> Self {
         TestCaseError::Fail(reason.into())
     }
+
+    /// Attach a context note to the `Reason` of this error.
+    ///
+    /// Notes are shown in the order they were added, so helper functions
+    /// deeper in the call stack come first.
+    pub fn context(self, note: impl Into<Cow<'static, str>>) -> Self {
+        match self {
+            TestCaseError::Reject(whence) => {
+                TestCaseError::Reject(whence.with_context(note))
+            }
+            TestCaseError::Fail(why) => {
+                TestCaseError::Fail(why.with_context(note))
+            }
+        }
+    }
+}
+
+/// Extension methods for attaching context notes to a `TestCaseResult` or
+/// any other `Result` whose error is a `TestCaseError`.
+///
+/// ## Example
+///
+/// ```
+/// use proptest::prelude::*;
+///
+/// fn check_shard(shard: &[u32]) -> Result<(), TestCaseError> {
+///   prop_assert!(shard.len() < 100);
+///   Ok(())
+/// }
+///
+/// fn check_all(shards: &[Vec<u32>]) -> Result<(), TestCaseError> {
+///   for (i, shard) in shards.iter().enumerate() {
+///     check_shard(shard)
+///       .with_context(|| format!("checking shard {}", i))?;
+///   }
+///   Ok(())
+/// }
+/// #
+/// # fn main() { check_all(&[vec![1, 2, 3]]).unwrap(); }
+/// ```
+pub trait TestCaseResultExt: Sized {
+    /// Attach `note` to the error, if any.
+    fn context(self, note: impl Into<Cow<'static, str>>) -> Self;
+
+    /// Attach the note produced by `note` to the error, if any. `note` is only
+    /// called if there is an error.
+    fn with_context<N: Into<Cow<'static, str>>>(
+        self,
+        note: impl FnOnce() -> N,
+    ) -> Self;
+}
+
+impl<T> TestCaseResultExt for Result<T, TestCaseError> {
+    fn context(self, note: impl Into<Cow<'static, str>>) -> Self {
+        self.map_err(|e| e.context(note))
+    }
+
+    fn with_context<N: Into<Cow<'static, str>>>(
+        self,
+        note: impl FnOnce() -> N,
+    ) -> Self {
+        self.map_err(|e| e.context(note()))
+    }
 }
 
 impl fmt::Display for TestCaseError {
@@ -70,7 +135,7 @@ impl fmt::Display for TestCaseError {
 #[cfg(feature = "std")]
 impl<E: ::std::error::Error> From<E> for TestCaseError {
     fn from(cause: E) -> Self {
-        TestCaseError::fail(cause.to_string())
+        TestCaseError::Fail(Reason::from_error(&cause))
     }
 }
 
@@ -84,17 +149,51 @@ pub enum TestError<T> {
     /// the test failed. The `T` is the minimal input found to reproduce the
     /// failure.
     Fail(Reason, T),
+    /// A failing test case was found and shrunk, but the minimal input did
+    /// not fail consistently when it was re-run (see `Config::flaky_reruns`),
+    /// which indicates the test is nondeterministic.
+    Flaky {
+        /// Where and/or why the minimal input failed during shrinking.
+        reason: Reason,
+        /// The minimal input found during shrinking.
+        value: T,
+        /// The number of re-runs of `value` which passed.
+        passes: u32,
+        /// The number of re-runs of `value` which failed.
+        failures: u32,
+    },
 }
 
 impl<T: fmt::Debug> fmt::Display for TestError<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             TestError::Abort(ref why) => write!(f, "Test aborted: {}", why),
-            TestError::Fail(ref why, ref what) => write!(
-                f,
-                "Test failed: {}; minimal failing input: {:?}",
-                why, what
-            ),
+            TestError::Fail(ref why, ref what) => {
+                write!(
+                    f,
+                    "Test failed: {}; minimal failing input: {:?}",
+                    why.message(),
+                    what
+                )?;
+                why.fmt_details(f)
+            }
+            TestError::Flaky {
+                ref reason,
+                ref value,
+                passes,
+                failures,
+            } => {
+                write!(
+                    f,
+                    "Test is flaky: {}; minimal failing input: {:?}; \
+                     passed {} of {} re-runs",
+                    reason.message(),
+                    value,
+                    passes,
+                    passes + failures
+                )?;
+                reason.fmt_details(f)
+            }
         }
     }
 }
@@ -106,6 +205,112 @@ impl<T: fmt::Debug> ::std::error::Error for TestError<T> {
         match *self {
             TestError::Abort(..) => "Abort",
             TestError::Fail(..) => "Fail",
+            TestError::Flaky { .. } => "Flaky",
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use std::error::Error;
+    use std::fmt;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct Outer(Inner);
+    #[derive(Debug)]
+    struct Inner;
+
+    impl fmt::Display for Outer {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "outer failed")
+        }
+    }
+
+    impl fmt::Display for Inner {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "inner failed")
+        }
+    }
+
+    impl Error for Outer {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    impl Error for Inner {}
+
+    fn helper() -> Result<(), TestCaseError> {
+        Err(Outer(Inner))?;
+        Ok(())
+    }
+
+    #[test]
+    fn from_error_keeps_source_chain() {
+        match helper() {
+            Err(TestCaseError::Fail(why)) => {
+                assert_eq!("outer failed", why.message());
+                assert_eq!(
+                    vec!["inner failed"],
+                    why.causes().collect::<Vec<_>>()
+                );
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn context_is_stacked_and_displayed() {
+        let result = helper()
+            .context("inner note")
+            .with_context(|| format!("outer note {}", 42));
+        let why = match result {
+            Err(TestCaseError::Fail(why)) => why,
+            r => panic!("Unexpected result: {:?}", r),
+        };
+        assert_eq!(
+            vec!["inner note", "outer note 42"],
+            why.context().collect::<Vec<_>>()
+        );
+
+        let error = TestError::Fail(why, 5);
+        assert_eq!(
+            "Test failed: outer failed; minimal failing input: 5\n    \
+             caused by: inner failed\n    \
+             context: inner note\n    \
+             context: outer note 42",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn context_applies_to_rejections() {
+        let result: TestCaseResult = Err(TestCaseError::reject("nope"));
+        match result.context("note") {
+            Err(TestCaseError::Reject(whence)) => {
+                assert_eq!("nope\n    context: note", whence.to_string())
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn prop_context_adds_location() {
+        fn check() -> TestCaseResult {
+            prop_context!(helper(), "checking {}", "shard")
+        }
+
+        match check() {
+            Err(TestCaseError::Fail(why)) => {
+                let note = why.context().next().unwrap();
+                assert!(note.contains("errors.rs"), "{}", note);
+                assert!(note.ends_with(": checking shard"), "{}", note);
+            }
+            r => panic!("Unexpected result: {:?}", r),
         }
     }
 }
@@ -251,16 +251,13 @@ fn absolutize_source_file_with_cwd<'a>(
 }
 
 fn parse_seed_line(
-    mut line: String,
+    line: String,
     path: &Path,
     lineno: usize,
 ) -> Option<PersistedSeed> {
-    // Remove anything after and including '#':
-    if let Some(comment_start) = line.find('#') {
-        line.truncate(comment_start);
-    }
-
-    if line.len() > 0 {
+    // Ignore lines which are only comments. Comments after the seed are
+    // left to `PersistedSeed`, which stores the size in one.
+    if line.find('#').unwrap_or(line.len()) > 0 {
         let ret = line.parse::<PersistedSeed>().ok();
         if !ret.is_some() {
             eprintln!(
@@ -416,6 +413,8 @@ lazy_static! {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_runner::failure_persistence::tests::INC_SEED;
+    use crate::test_runner::Seed;
 
     struct TestPaths {
         crate_root: &'static Path,
@@ -533,4 +532,26 @@ mod tests {
             .unwrap()
         );
     }
+
+    #[test]
+    fn sized_seed_lines_load_in_older_versions() {
+        let sized = PersistedSeed(INC_SEED.0, Some(12));
+        let mut buf = Vec::new();
+        write_seed_line(&mut buf, &sized, &"value").unwrap();
+        let line = String::from_utf8(buf).unwrap();
+        assert!(line.contains("# size=12 # shrinks to"));
+
+        // Versions of proptest without sizes strip the comment and parse
+        // what remains as the seed.
+        let old_line = &line[..line.find('#').unwrap()];
+        assert_eq!(Some(INC_SEED.0), Seed::from_persistence(old_line));
+
+        let path = Path::new("test.txt");
+        assert_eq!(Some(sized), parse_seed_line(line, path, 0));
+        assert_eq!(
+            Some(INC_SEED),
+            parse_seed_line(format!("{} # shrinks to 1", INC_SEED), path, 0)
+        );
+        assert_eq!(None, parse_seed_line("# comment".to_owned(), path, 0));
+    }
 }
@@ -29,12 +29,23 @@ use crate::test_runner::Seed;
 ///
 /// The `Display` and `FromStr` implementations go to and from the format
 /// Proptest uses for its persistence file.
+///
+/// Besides the seed, this records the size of the `TestRunner` (see
+/// `TestRunner::size()`) if the failure was found while growing the size.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub struct PersistedSeed(pub(crate) Seed);
+pub struct PersistedSeed(pub(crate) Seed, pub(crate) Option<u32>);
+
+// The size is written as a comment, which versions of proptest that do not
+// know about sizes strip before parsing the seed.
+const SIZE_PREFIX: &str = "# size=";
 
 impl Display for PersistedSeed {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0.to_persistence())
+        write!(f, "{}", self.0.to_persistence())?;
+        if let Some(size) = self.1 {
+            write!(f, " {}{}", SIZE_PREFIX, size)?;
+        }
+        Ok(())
     }
 }
 
@@ -42,7 +53,22 @@ impl FromStr for PersistedSeed {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, ()> {
-        Seed::from_persistence(s).map(PersistedSeed).ok_or(())
+        let (seed, size) = match s.find('#') {
+            Some(ix) => (&s[..ix], s[ix..].strip_prefix(SIZE_PREFIX)),
+            None => (s, None),
+        };
+        let size = match size {
+            Some(size) => Some(
+                size.split(char::is_whitespace)
+                    .next()
+                    .and_then(|size| size.parse().ok())
+                    .ok_or(())?,
+            ),
+            None => None,
+        };
+        Seed::from_persistence(seed)
+            .map(|seed| PersistedSeed(seed, size))
+            .ok_or(())
     }
 }
 
@@ -67,7 +93,7 @@ pub trait FailurePersistence: Send + Sync + fmt::Debug {
     ) -> Vec<PersistedSeed> {
         self.load_persisted_failures(source_file)
             .into_iter()
-            .map(|seed| PersistedSeed(Seed::XorShift(seed)))
+            .map(|seed| PersistedSeed(Seed::XorShift(seed), None))
             .collect()
     }
 
@@ -147,9 +173,10 @@ mod tests {
     use super::PersistedSeed;
     use crate::test_runner::rng::Seed;
 
-    pub const INC_SEED: PersistedSeed = PersistedSeed(Seed::XorShift([
-        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
-    ]));
+    pub const INC_SEED: PersistedSeed = PersistedSeed(
+        Seed::XorShift([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]),
+        None,
+    );
 
     pub const HI_PATH: Option<&str> = Some("hi");
     pub const UNREL_PATH: Option<&str> = Some("unrelated");
@@ -0,0 +1,235 @@
+//-
+// Copyright 2021 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for telling failures caused by different bugs apart.
+
+use crate::std_facade::{fmt, String};
+#[cfg(feature = "std")]
+use std::cell::RefCell;
+
+use crate::test_runner::Reason;
+
+/// How a `TestRunner` decides whether two failures are caused by the same
+/// bug.
+///
+/// By default, shrinking accepts any failure as "still failing", so a minimal
+/// case for one bug can slip into a different bug while shrinking. With any
+/// other setting, the runner computes a fingerprint of the first failure and
+/// only accepts simplifications which fail with the same fingerprint. Failures
+/// with other fingerprints are treated as passes for the purpose of shrinking,
+/// and are reported by `TestRunner::distinct_failures()`.
+///
+/// Fingerprinting is not supported when forking, since the reasons for
+/// failures are not passed from the child processes to the parent; it is
+/// ignored with a warning in that case.
+#[derive(Clone, Copy)]
+pub enum FailureFingerprint {
+    /// All failures are considered to be the same bug. This is the default.
+    Any,
+    /// Failures are the same bug if their messages are the same after
+    /// replacing every run of decimal digits with `N`, so that failures only
+    /// differing in the values involved are considered the same.
+    Message,
+    /// Panics are the same bug if they occurred at the same source location.
+    /// So are failures of `prop_assert!` and related macros, whose location
+    /// is taken from the end of their message. Other failures are compared
+    /// as with `Message`.
+    ///
+    /// This installs a panic hook (which defers to the previously installed
+    /// one) to capture the locations of panics.
+    PanicLocation,
+    /// Failures are the same bug if the given function returns the same
+    /// string for both.
+    ///
+    /// The first field names the function. Since function pointers cannot be
+    /// compared reliably, two `Custom` fingerprints are equal if they have
+    /// the same name.
+    Custom(&'static str, fn(&Reason) -> String),
+}
+
+impl fmt::Debug for FailureFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FailureFingerprint::Any => f.write_str("Any"),
+            FailureFingerprint::Message => f.write_str("Message"),
+            FailureFingerprint::PanicLocation => f.write_str("PanicLocation"),
+            FailureFingerprint::Custom(name, _) => {
+                f.debug_tuple("Custom").field(&name).finish()
+            }
+        }
+    }
+}
+
+impl PartialEq for FailureFingerprint {
+    fn eq(&self, other: &Self) -> bool {
+        match (*self, *other) {
+            (FailureFingerprint::Any, FailureFingerprint::Any)
+            | (FailureFingerprint::Message, FailureFingerprint::Message)
+            | (
+                FailureFingerprint::PanicLocation,
+                FailureFingerprint::PanicLocation,
+            ) => true,
+            (
+                FailureFingerprint::Custom(a, _),
+                FailureFingerprint::Custom(b, _),
+            ) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl FailureFingerprint {
+    /// Compute the fingerprint of the failure described by `reason`.
+    pub fn of(&self, reason: &Reason) -> String {
+        match *self {
+            FailureFingerprint::Any => String::new(),
+            FailureFingerprint::Message => strip_numbers(reason.message()),
+            FailureFingerprint::PanicLocation => match reason.location() {
+                Some(location) => location.into(),
+                None => match assertion_location(reason.message()) {
+                    Some(location) => location.into(),
+                    None => strip_numbers(reason.message()),
+                },
+            },
+            FailureFingerprint::Custom(_, f) => f(reason),
+        }
+    }
+}
+
+/// Extract the `file:line` which `prop_assert!` and related macros append to
+/// their messages after ` at `.
+fn assertion_location(message: &str) -> Option<&str> {
+    let location = &message[message.rfind(" at ")? + 4..];
+    let colon = location.rfind(':')?;
+    let line = &location[colon + 1..];
+    if colon > 0 && !line.is_empty() && line.bytes().all(|b| b.is_ascii_digit())
+    {
+        Some(location)
+    } else {
+        None
+    }
+}
+
+fn strip_numbers(message: &str) -> String {
+    let mut stripped = String::with_capacity(message.len());
+    let mut in_number = false;
+    for ch in message.chars() {
+        if ch.is_ascii_digit() {
+            if !in_number {
+                stripped.push('N');
+            }
+            in_number = true;
+        } else {
+            stripped.push(ch);
+            in_number = false;
+        }
+    }
+    stripped
+}
+
+/// A failure encountered while shrinking which did not have the same
+/// fingerprint as the failure being shrunk.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DistinctFailure {
+    /// The fingerprint of this failure.
+    pub fingerprint: String,
+    /// Why the test failed.
+    pub reason: Reason,
+    /// The `Debug` representation of the input which caused the failure.
+    pub input: String,
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    static PANIC_LOCATION: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// Install a panic hook which records the location of panics on the current
+/// thread for `take_panic_location()`, then defers to the previous hook.
+#[cfg(feature = "std")]
+pub(crate) fn install_panic_location_hook() {
+    use std::panic;
+    use std::string::ToString;
+    use std::sync::Once;
+
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(std::boxed::Box::new(move |info| {
+            let location = info.location().map(|l| l.to_string());
+            // The hook may run while the thread-local is being destroyed.
+            let _ = PANIC_LOCATION.try_with(|l| *l.borrow_mut() = location);
+            previous(info);
+        }));
+    });
+}
+
+/// Take the location of the last panic on this thread, if it was recorded.
+#[cfg(feature = "std")]
+pub(crate) fn take_panic_location() -> Option<String> {
+    PANIC_LOCATION.with(|l| l.borrow_mut().take())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn message_fingerprint_ignores_numbers() {
+        let fp = FailureFingerprint::Message;
+        assert_eq!(
+            fp.of(&"index 12 out of range for len 3".into()),
+            fp.of(&"index 4 out of range for len 100".into())
+        );
+        assert_ne!(
+            fp.of(&"index 12 out of range for len 3".into()),
+            fp.of(&"attempt to add with overflow".into())
+        );
+        assert_eq!("aNbN", fp.of(&"a12b3".into()));
+    }
+
+    #[test]
+    fn panic_location_fingerprint_uses_assertion_location() {
+        let fp = FailureFingerprint::PanicLocation;
+        assert_eq!(
+            "src/lib.rs:12",
+            fp.of(&"assertion failed: x < 3 at src/lib.rs:12".into())
+        );
+        assert_ne!(
+            fp.of(&"assertion failed: x < 3 at src/lib.rs:12".into()),
+            fp.of(&"assertion failed: x < 3 at src/lib.rs:13".into())
+        );
+        assert_eq!(
+            fp.of(&"bad value 12 at midnight".into()),
+            fp.of(&"bad value 4 at midnight".into())
+        );
+    }
+
+    #[test]
+    fn custom_fingerprints_compare_by_name() {
+        fn first_word(reason: &Reason) -> String {
+            reason.message().split(' ').next().unwrap_or("").into()
+        }
+        fn last_word(reason: &Reason) -> String {
+            reason.message().split(' ').last().unwrap_or("").into()
+        }
+
+        let fp = FailureFingerprint::Custom("first_word", first_word);
+        assert_eq!("bad", fp.of(&"bad value".into()));
+        assert_eq!(fp, FailureFingerprint::Custom("first_word", first_word));
+        assert_ne!(fp, FailureFingerprint::Custom("last_word", last_word));
+        assert_eq!("Custom(\"first_word\")", format!("{:?}", fp));
+    }
+
+    #[test]
+    fn any_fingerprint_is_constant() {
+        let fp = FailureFingerprint::Any;
+        assert_eq!(fp.of(&"foo".into()), fp.of(&"bar".into()));
+    }
+}
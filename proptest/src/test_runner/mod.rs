@@ -12,20 +12,26 @@
 //! You do not normally need to access things in this module directly except
 //! when implementing new low-level strategies.
 
+mod capture;
 mod config;
 mod errors;
 mod failure_persistence;
+mod fingerprint;
 mod reason;
 #[cfg(feature = "fork")]
 mod replay;
 mod result_cache;
 mod rng;
 mod runner;
+mod union_arms;
 
+pub use self::capture::*;
 pub use self::config::*;
 pub use self::errors::*;
 pub use self::failure_persistence::*;
+pub use self::fingerprint::*;
 pub use self::reason::*;
 pub use self::result_cache::*;
 pub use self::rng::*;
 pub use self::runner::*;
+pub use self::union_arms::*;
@@ -7,48 +7,198 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use crate::std_facade::{fmt, Box, Cow, String};
+use crate::std_facade::{fmt, Box, Cow, String, Vec};
 
 /// The reason for why something, such as a generated value, was rejected.
 ///
-/// Currently this is merely a wrapper around a message, but more properties
-/// may be added in the future.
+/// At its core this is a message, but a `Reason` may additionally carry the
+/// chain of underlying causes of an error it was converted from (see
+/// `Reason::from_error()`) and any number of context notes added as the
+/// failure propagated outwards (see `with_context()`). When a `TestRunner`
+/// reports a failing test case, the `Reason` also carries the notes recorded
+/// with `prop_note!` and any output captured while the case ran.
 ///
 /// This is constructed via `.into()` on a `String`, `&'static str`, or
 /// `Box<str>`.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Reason(Cow<'static, str>);
+pub struct Reason {
+    message: Cow<'static, str>,
+    // Everything but the message is boxed so that `Result<_, Reason>` stays
+    // small in the common case where there are no details.
+    details: Option<Box<Details>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Details {
+    causes: Vec<Cow<'static, str>>,
+    context: Vec<Cow<'static, str>>,
+    notes: Vec<Cow<'static, str>>,
+    output: Option<String>,
+    location: Option<String>,
+}
 
 impl Reason {
+    fn new(message: Cow<'static, str>) -> Self {
+        Reason {
+            message,
+            details: None,
+        }
+    }
+
+    fn details_mut(&mut self) -> &mut Details {
+        self.details.get_or_insert_with(Default::default)
+    }
+
+    /// Create a `Reason` from an `Error`.
+    ///
+    /// The message is the `Display` form of `error`, and the `Display` form
+    /// of each error in its `source()` chain is retained as a cause.
+    #[cfg(feature = "std")]
+    pub fn from_error(error: &dyn ::std::error::Error) -> Self {
+        use std::string::ToString;
+
+        let mut reason = Reason::new(error.to_string().into());
+        let mut source = error.source();
+        while let Some(cause) = source {
+            reason.details_mut().causes.push(cause.to_string().into());
+            source = cause.source();
+        }
+        reason
+    }
+
     /// Return the message for this `Reason`.
     ///
     /// The message is intended for human consumption, and is not guaranteed to
     /// have any format in particular.
+    ///
+    /// This does not include any causes or context notes.
     pub fn message(&self) -> &str {
-        &*self.0
+        &*self.message
+    }
+
+    /// Return the messages of the underlying causes of this `Reason`, from
+    /// the most immediate cause to the root cause.
+    pub fn causes(&self) -> impl Iterator<Item = &str> {
+        self.details
+            .iter()
+            .flat_map(|d| d.causes.iter())
+            .map(|c| &**c)
+    }
+
+    /// Return the context notes attached to this `Reason`, in the order they
+    /// were added; i.e., innermost first.
+    pub fn context(&self) -> impl Iterator<Item = &str> {
+        self.details
+            .iter()
+            .flat_map(|d| d.context.iter())
+            .map(|c| &**c)
+    }
+
+    /// Attach a context note to this `Reason`.
+    ///
+    /// This is usually called indirectly through `TestCaseError::context()`,
+    /// `TestCaseResultExt`, or `prop_context!`.
+    pub fn with_context(mut self, note: impl Into<Cow<'static, str>>) -> Self {
+        self.details_mut().context.push(note.into());
+        self
+    }
+
+    /// Return the notes recorded with `prop_note!` while the test case which
+    /// produced this `Reason` was running.
+    pub fn notes(&self) -> impl Iterator<Item = &str> {
+        self.details
+            .iter()
+            .flat_map(|d| d.notes.iter())
+            .map(|n| &**n)
+    }
+
+    /// Return the output captured while the test case which produced this
+    /// `Reason` was running, if output capture was enabled and possible.
+    ///
+    /// See `Config::capture_output`.
+    pub fn output(&self) -> Option<&str> {
+        self.details.as_ref().and_then(|d| d.output.as_deref())
+    }
+
+    /// Return the source location of the panic which caused this failure, if
+    /// the failure was a panic and its location was recorded.
+    ///
+    /// Locations are only recorded when `Config::failure_fingerprint` is
+    /// `FailureFingerprint::PanicLocation`.
+    pub fn location(&self) -> Option<&str> {
+        self.details.as_ref().and_then(|d| d.location.as_deref())
+    }
+
+    pub(crate) fn with_location(mut self, location: Option<String>) -> Self {
+        if location.is_some() || self.location().is_some() {
+            self.details_mut().location = location;
+        }
+        self
+    }
+
+    pub(crate) fn with_notes(mut self, notes: Vec<String>) -> Self {
+        if !notes.is_empty() {
+            self.details_mut()
+                .notes
+                .extend(notes.into_iter().map(Cow::Owned));
+        }
+        self
+    }
+
+    pub(crate) fn with_output(mut self, output: Option<String>) -> Self {
+        if output.is_some() {
+            self.details_mut().output = output;
+        }
+        self
+    }
+
+    /// Write the panic location, causes, context notes, notes, and captured
+    /// output of this `Reason`, each on its own line, but not the message
+    /// itself.
+    pub(crate) fn fmt_details(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(location) = self.location() {
+            write!(f, "\n    panicked at: {}", location)?;
+        }
+        for cause in self.causes() {
+            write!(f, "\n    caused by: {}", cause)?;
+        }
+        for note in self.context() {
+            write!(f, "\n    context: {}", note)?;
+        }
+        for note in self.notes() {
+            write!(f, "\n    note: {}", note)?;
+        }
+        if let Some(output) = self.output() {
+            write!(f, "\n    captured output:")?;
+            for line in output.lines() {
+                write!(f, "\n    | {}", line)?;
+            }
+        }
+        Ok(())
     }
 }
 
 impl From<&'static str> for Reason {
     fn from(s: &'static str) -> Self {
-        Reason(s.into())
+        Reason::new(s.into())
     }
 }
 
 impl From<String> for Reason {
     fn from(s: String) -> Self {
-        Reason(s.into())
+        Reason::new(s.into())
     }
 }
 
 impl From<Box<str>> for Reason {
     fn from(s: Box<str>) -> Self {
-        Reason(String::from(s).into())
+        Reason::new(String::from(s).into())
     }
 }
 
 impl fmt::Display for Reason {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Display::fmt(self.message(), f)
+        fmt::Display::fmt(self.message(), f)?;
+        self.fmt_details(f)
     }
 }
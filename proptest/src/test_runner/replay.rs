@@ -15,7 +15,7 @@ use std::path::Path;
 use std::string::String;
 use std::vec::Vec;
 
-use crate::test_runner::{Seed, TestCaseError, TestCaseResult};
+use crate::test_runner::{Reason, Seed, TestCaseError, TestCaseResult};
 
 const SENTINEL: &'static str = "proptest-forkfile";
 
@@ -39,6 +39,11 @@ const SENTINEL: &'static str = "proptest-forkfile";
 /// signal. This format makes it easy for the child process to blindly append
 /// to the file without having to worry about the possibility of appends being
 /// non-atomic.
+///
+/// Notes recorded with `prop_note!` are appended as soon as they are recorded,
+/// each enclosed in `"` with `"` and `\` escaped by a `\`, and belong to the
+/// next step, which only keeps them if it is a failure. Each note is written
+/// with a single append, so it is either in the file or not at all.
 #[derive(Clone, Debug)]
 pub(crate) struct Replay {
     /// The seed of the RNG used to start running the test cases.
@@ -87,6 +92,17 @@ fn step_to_char(step: &TestCaseResult) -> char {
     }
 }
 
+fn push_note(data: &mut String, note: &str) {
+    data.push('"');
+    for ch in note.chars() {
+        if '"' == ch || '\\' == ch {
+            data.push('\\');
+        }
+        data.push(ch);
+    }
+    data.push('"');
+}
+
 /// Append the given step to the given output.
 pub(crate) fn append(
     mut file: impl Write,
@@ -95,6 +111,13 @@ pub(crate) fn append(
     write!(file, "{}", step_to_char(step))
 }
 
+/// Append a note for the next step to the given output.
+pub(crate) fn append_note(mut file: impl Write, note: &str) -> io::Result<()> {
+    let mut data = String::new();
+    push_note(&mut data, note);
+    file.write_all(data.as_bytes())
+}
+
 /// Append a no-op step to the given output.
 pub(crate) fn ping(mut file: impl Write) -> io::Result<()> {
     write!(file, " ")
@@ -111,12 +134,17 @@ impl Replay {
         writeln!(file, "{}", SENTINEL)?;
         writeln!(file, "{}", self.seed.to_persistence())?;
 
-        let mut step_data = Vec::<u8>::new();
+        let mut step_data = String::new();
         for step in &self.steps {
-            step_data.push(step_to_char(step) as u8);
+            if let Err(TestCaseError::Fail(ref why)) = *step {
+                for note in why.notes() {
+                    push_note(&mut step_data, note);
+                }
+            }
+            step_data.push(step_to_char(step));
         }
 
-        file.write_all(&step_data)?;
+        file.write_all(step_data.as_bytes())?;
 
         Ok(())
     }
@@ -162,17 +190,39 @@ impl Replay {
         };
 
         line.clear();
-        reader.read_line(&mut line)?;
+        reader.read_to_string(&mut line)?;
 
         let mut steps = Vec::new();
-        for ch in line.chars() {
+        let mut notes = Vec::new();
+        let mut chars = line.chars();
+        while let Some(ch) = chars.next() {
             match ch {
-                '+' => steps.push(Ok(())),
-                '-' => steps
-                    .push(Err(TestCaseError::fail("failed in other process"))),
-                '!' => steps.push(Err(TestCaseError::reject(
-                    "rejected in other process",
+                '+' => {
+                    notes.clear();
+                    steps.push(Ok(()))
+                }
+                '-' => steps.push(Err(TestCaseError::Fail(
+                    Reason::from("failed in other process")
+                        .with_notes(notes.split_off(0)),
                 ))),
+                '!' => {
+                    notes.clear();
+                    steps.push(Err(TestCaseError::reject(
+                        "rejected in other process",
+                    )))
+                }
+                '"' => {
+                    let mut note = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some('\\') => note.extend(chars.next()),
+                            Some(ch) => note.push(ch),
+                            None => return Ok(ReplayFileStatus::Corrupt),
+                        }
+                    }
+                    notes.push(note);
+                }
                 '.' => {
                     return Ok(ReplayFileStatus::Terminated(Replay {
                         seed,
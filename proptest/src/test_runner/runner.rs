@@ -26,14 +26,22 @@ use std::fs;
 use tempfile;
 
 use crate::strategy::*;
+#[cfg(feature = "std")]
+use crate::test_runner::capture::NoteCollector;
 use crate::test_runner::config::*;
 use crate::test_runner::errors::*;
 use crate::test_runner::failure_persistence::PersistedSeed;
+#[cfg(feature = "std")]
+use crate::test_runner::fingerprint::{
+    install_panic_location_hook, take_panic_location,
+};
+use crate::test_runner::fingerprint::{DistinctFailure, FailureFingerprint};
 use crate::test_runner::reason::*;
 #[cfg(feature = "fork")]
 use crate::test_runner::replay;
 use crate::test_runner::result_cache::*;
 use crate::test_runner::rng::TestRng;
+use crate::test_runner::union_arms::{UnionArmCoverage, UnionArms, UnionId};
 
 #[cfg(feature = "fork")]
 const ENV_FORK_FILE: &'static str = "_PROPTEST_FORKFILE";
@@ -64,6 +72,9 @@ macro_rules! verbose_message {
 
 type RejectionDetail = BTreeMap<Reason, u32>;
 
+/// The largest size a `TestRunner` can have; see `TestRunner::size()`.
+pub const MAX_SIZE: u32 = 100;
+
 /// State used when running a proptest test.
 #[derive(Clone)]
 pub struct TestRunner {
@@ -72,10 +83,18 @@ pub struct TestRunner {
     local_rejects: u32,
     global_rejects: u32,
     rng: TestRng,
+    size: u32,
     flat_map_regens: Arc<AtomicUsize>,
 
     local_reject_detail: RejectionDetail,
     global_reject_detail: RejectionDetail,
+    distinct_failures: Vec<DistinctFailure>,
+    union_arms: Option<UnionArms>,
+    /// The index of the case being generated among the new cases of the
+    /// current run, if any.
+    case_index: Option<u32>,
+    /// Whether the case being generated depends on more than its seed.
+    case_unseeded: bool,
 }
 
 impl fmt::Debug for TestRunner {
@@ -86,9 +105,14 @@ impl fmt::Debug for TestRunner {
             .field("local_rejects", &self.local_rejects)
             .field("global_rejects", &self.global_rejects)
             .field("rng", &"<TestRng>")
+            .field("size", &self.size)
             .field("flat_map_regens", &self.flat_map_regens)
             .field("local_reject_detail", &self.local_reject_detail)
             .field("global_reject_detail", &self.global_reject_detail)
+            .field("distinct_failures", &self.distinct_failures)
+            .field("union_arms", &self.union_arms)
+            .field("case_index", &self.case_index)
+            .field("case_unseeded", &self.case_unseeded)
             .finish()
     }
 }
@@ -108,6 +132,20 @@ impl fmt::Display for TestRunner {
         for (whence, count) in &self.global_reject_detail {
             writeln!(f, "\t\t{} times at {}", count, whence)?;
         }
+        if !self.distinct_failures.is_empty() {
+            writeln!(
+                f,
+                "\tdistinct failures found while shrinking: {}",
+                self.distinct_failures.len()
+            )?;
+            for failure in &self.distinct_failures {
+                writeln!(
+                    f,
+                    "\t\t{} for input {}",
+                    failure.reason, failure.input
+                )?;
+            }
+        }
 
         Ok(())
     }
@@ -120,21 +158,77 @@ impl Default for TestRunner {
     }
 }
 
+#[cfg(feature = "fork")]
+const OUTPUT_MARKER: &'static str = "\u{1e}proptest-case";
+
 #[cfg(feature = "fork")]
 #[derive(Debug)]
 struct ForkOutput {
     file: Option<fs::File>,
+    /// Whether to delimit the output of each test case so that the parent can
+    /// tell which output belongs to which step of the replay.
+    delimit_output: bool,
+    /// The number of steps replayed or appended so far.
+    steps: usize,
+    /// The output captured from the child processes for each step, if any.
+    captured: Vec<Option<String>>,
 }
 
 #[cfg(feature = "fork")]
 impl ForkOutput {
     fn append(&mut self, result: &TestCaseResult) {
+        self.steps += 1;
         if let Some(ref mut file) = self.file {
             replay::append(file, result)
                 .expect("Failed to append to replay file");
         }
     }
 
+    fn replayed(&mut self, result: TestCaseResult) -> TestCaseResult {
+        let step = self.steps;
+        self.steps += 1;
+        match result {
+            Err(TestCaseError::Fail(why)) => {
+                let output = self.captured.get_mut(step).and_then(Option::take);
+                Err(TestCaseError::Fail(why.with_output(output)))
+            }
+            result => result,
+        }
+    }
+
+    fn begin_case(&mut self) {
+        use std::io::Write;
+
+        if self.delimit_output {
+            let stdout = std::io::stdout();
+            let mut stdout = stdout.lock();
+            let _ = writeln!(stdout, "{}:{}", OUTPUT_MARKER, self.steps);
+            let _ = stdout.flush();
+        }
+    }
+
+    fn end_case(&mut self) {
+        use std::io::Write;
+
+        if self.delimit_output {
+            let stdout = std::io::stdout();
+            let mut stdout = stdout.lock();
+            let _ = writeln!(stdout, "{}-end", OUTPUT_MARKER);
+            let _ = stdout.flush();
+        }
+    }
+
+    /// Starts collecting the notes of a test case. In a child process, the
+    /// notes are also sent to the parent through the replay file.
+    fn collect_notes(&self) -> NoteCollector {
+        match self.file {
+            Some(ref file) => NoteCollector::begin_echoing(
+                file.try_clone().expect("Failed to reopen replay file"),
+            ),
+            None => NoteCollector::begin(),
+        }
+    }
+
     fn ping(&mut self) {
         if let Some(ref mut file) = self.file {
             replay::ping(file).expect("Failed to append to replay file");
@@ -148,7 +242,12 @@ impl ForkOutput {
     }
 
     fn empty() -> Self {
-        ForkOutput { file: None }
+        ForkOutput {
+            file: None,
+            delimit_output: false,
+            steps: 0,
+            captured: vec![],
+        }
     }
 
     fn is_in_fork(&self) -> bool {
@@ -156,6 +255,47 @@ impl ForkOutput {
     }
 }
 
+/// Split the output captured from child processes into the output of each
+/// replay step, as delimited by `ForkOutput`.
+#[cfg(feature = "fork")]
+fn split_captured_output(mut output: &str) -> Vec<Option<String>> {
+    let mut captured = Vec::<Option<String>>::new();
+    let mut current: Option<usize> = None;
+
+    loop {
+        let (text, rest) = match output.find(OUTPUT_MARKER) {
+            Some(ix) => (&output[..ix], Some(&output[ix..])),
+            None => (output, None),
+        };
+
+        if let Some(step) = current {
+            captured[step]
+                .get_or_insert_with(String::new)
+                .push_str(text);
+        }
+
+        let rest = match rest {
+            Some(rest) => &rest[OUTPUT_MARKER.len()..],
+            None => break,
+        };
+        let (marker, rest) =
+            rest.split_at(rest.find('\n').unwrap_or(rest.len()));
+        output = rest.get(1..).unwrap_or("");
+
+        current = marker.strip_prefix(':').and_then(|s| s.parse().ok());
+        if let Some(step) = current {
+            if captured.len() <= step {
+                captured.resize(step + 1, None);
+            }
+            // A step can be run more than once if a child process crashed
+            // before recording its result; only the last run counts.
+            captured[step] = Some(String::new());
+        }
+    }
+
+    captured
+}
+
 #[cfg(not(feature = "fork"))]
 #[derive(Debug)]
 struct ForkOutput;
@@ -163,6 +303,17 @@ struct ForkOutput;
 #[cfg(not(feature = "fork"))]
 impl ForkOutput {
     fn append(&mut self, _result: &TestCaseResult) {}
+    fn replayed(&mut self, result: TestCaseResult) -> TestCaseResult {
+        result
+    }
+    #[cfg(feature = "std")]
+    fn begin_case(&mut self) {}
+    #[cfg(feature = "std")]
+    fn end_case(&mut self) {}
+    #[cfg(feature = "std")]
+    fn collect_notes(&self) -> NoteCollector {
+        NoteCollector::begin()
+    }
     fn ping(&mut self) {}
     fn terminate(&mut self) {}
     fn empty() -> Self {
@@ -180,7 +331,7 @@ fn call_test<V, F, R>(
     test: &F,
     replay: &mut R,
     result_cache: &mut dyn ResultCache,
-    _: &mut ForkOutput,
+    fork_output: &mut ForkOutput,
 ) -> TestCaseResult
 where
     V: fmt::Debug,
@@ -188,7 +339,7 @@ where
     R: Iterator<Item = TestCaseResult>,
 {
     if let Some(result) = replay.next() {
-        return result;
+        return fork_output.replayed(result);
     }
 
     let cache_key = result_cache.key(&ResultCacheKey::new(&case));
@@ -220,7 +371,7 @@ where
     let timeout = runner.config.timeout();
 
     if let Some(result) = replay.next() {
-        return result;
+        return fork_output.replayed(result);
     }
 
     // Now that we're about to start a new test (as far as the replay system is
@@ -240,15 +391,47 @@ where
         return result.clone();
     }
 
+    // When forking, the parent captures everything the child outputs, so
+    // there is no need to capture anything here.
+    let mut output_capture =
+        if runner.config.capture_output && !fork_output.is_in_fork() {
+            runner.config.output_capture.clone()
+        } else {
+            None
+        };
+    let notes = fork_output.collect_notes();
+    fork_output.begin_case();
+    if let Some(ref mut output_capture) = output_capture {
+        output_capture.begin();
+    }
+
+    // The panic hook is global, so locations are only used if this runner
+    // asked for them.
+    let record_location = matches!(
+        runner.config.failure_fingerprint,
+        FailureFingerprint::PanicLocation
+    );
+    if record_location {
+        install_panic_location_hook();
+    }
+    // Discard any location left over from a panic outside of a test case.
+    let _ = take_panic_location();
+
     let time_start = time::Instant::now();
 
     let mut result = unwrap_or!(
         panic::catch_unwind(AssertUnwindSafe(|| test(case))),
         what => Err(TestCaseError::Fail(
-            what.downcast::<&'static str>().map(|s| (*s).into())
+            what.downcast::<&'static str>().map(|s| Reason::from(*s))
                 .or_else(|what| what.downcast::<String>().map(|b| (*b).into()))
                 .or_else(|what| what.downcast::<Box<str>>().map(|b| (*b).into()))
-                .unwrap_or_else(|_| "<unknown panic value>".into()))));
+                .unwrap_or_else(|_| "<unknown panic value>".into())
+                .with_location(
+                    take_panic_location().filter(|_| record_location)))));
+
+    let output = output_capture.as_mut().and_then(|c| c.end());
+    let notes = notes.finish();
+    fork_output.end_case();
 
     // If there is a timeout and we exceeded it, fail the test here so we get
     // consistent behaviour. (The parent process cannot precisely time the test
@@ -266,6 +449,12 @@ where
         }
     }
 
+    if let Err(TestCaseError::Fail(why)) = result {
+        result = Err(TestCaseError::Fail(
+            why.with_notes(notes).with_output(output),
+        ));
+    }
+
     result_cache.put(cache_key, &result);
     fork_output.append(&result);
 
@@ -333,9 +522,14 @@ impl TestRunner {
             local_rejects: 0,
             global_rejects: 0,
             rng: rng,
+            size: MAX_SIZE,
             flat_map_regens: Arc::new(AtomicUsize::new(0)),
             local_reject_detail: BTreeMap::new(),
             global_reject_detail: BTreeMap::new(),
+            distinct_failures: Vec::new(),
+            union_arms: None,
+            case_index: None,
+            case_unseeded: false,
         }
     }
 
@@ -349,9 +543,14 @@ impl TestRunner {
             local_rejects: 0,
             global_rejects: 0,
             rng: self.new_rng(),
+            size: self.size,
             flat_map_regens: Arc::clone(&self.flat_map_regens),
             local_reject_detail: BTreeMap::new(),
             global_reject_detail: BTreeMap::new(),
+            distinct_failures: Vec::new(),
+            union_arms: None,
+            case_index: None,
+            case_unseeded: false,
         }
     }
 
@@ -366,6 +565,25 @@ impl TestRunner {
         self.rng.gen_rng()
     }
 
+    /// Returns the current size, between 0 and `MAX_SIZE`.
+    ///
+    /// Strategies generating values of variable size should generate smaller
+    /// values for smaller sizes. The size is `MAX_SIZE` unless
+    /// `Config::grow_size` is set, in which case `run()` grows it over the
+    /// course of the run. `SizeRange::scaled()` is a convenient way to take
+    /// the size into account.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Set the current size, which is clamped to `MAX_SIZE`.
+    ///
+    /// This is mostly useful for testing strategies, since `run()` sets the
+    /// size of each test case itself.
+    pub fn set_size(&mut self, size: u32) {
+        self.size = size.min(MAX_SIZE);
+    }
+
     /// Returns the configuration of this runner.
     pub fn config(&self) -> &Config {
         &self.config
@@ -404,6 +622,57 @@ impl TestRunner {
         }
     }
 
+    /// Run test cases against `f`, trying every value of `strategy` if there
+    /// are no more of them than `cases`.
+    ///
+    /// Small domains, such as tuples of `bool`s and short ranges, can be
+    /// covered completely with fewer cases than random sampling would spend
+    /// on them, while random sampling may still miss some combinations. If
+    /// the domain of `strategy` is larger than `Config::cases`, this is
+    /// equivalent to `run()`.
+    ///
+    /// If `Config::fork()` is set, this is also equivalent to `run()`, so
+    /// that the cases keep running in a separate process. Otherwise, the
+    /// values are run in order, in process, and each failure is shrunk as
+    /// usual, but not persisted.
+    ///
+    /// Tests defined with the `proptest!` macro are run this way when
+    /// `Config::exhaustive` is set.
+    pub fn run_exhaustive<S: Enumerable>(
+        &mut self,
+        strategy: &S,
+        test: impl Fn(S::Value) -> TestCaseResult,
+    ) -> TestRunResult<S> {
+        if self.config.fork() {
+            return self.run(strategy, test);
+        }
+
+        let size = match strategy.domain_size() {
+            Some(size) if 0 < size && size <= u64::from(self.config.cases) => {
+                size
+            }
+            _ => return self.run(strategy, test),
+        };
+
+        let mut result_cache = self.new_cache();
+        for index in 0..size {
+            let case = unwrap_or!(strategy.new_tree_at(index, self), msg =>
+                    return Err(TestError::Abort(msg)));
+
+            if self.run_one_with_replay(
+                case,
+                &test,
+                &mut iter::empty::<TestCaseResult>().fuse(),
+                &mut *result_cache,
+                &mut ForkOutput::empty(),
+            )? {
+                self.successes += 1;
+            }
+        }
+
+        Ok(())
+    }
+
     #[cfg(not(feature = "fork"))]
     fn run_in_fork<S: Strategy>(
         &mut self,
@@ -428,6 +697,16 @@ impl TestRunner {
         );
         let forkfile: RefCell<Option<tempfile::NamedTempFile>> =
             RefCell::new(None);
+        // If capturing output, all child processes write their output here
+        // instead of having it passed through by `rusty_fork`.
+        let output_file: Option<fs::File> = if self.config.capture_output {
+            Some(
+                tempfile::tempfile()
+                    .expect("Failed to create temporary file for output"),
+            )
+        } else {
+            None
+        };
         let init_forkfile_size = Cell::new(0u64);
         let seed = self.rng.new_rng_seed();
         let mut replay = replay::Replay {
@@ -462,6 +741,14 @@ impl TestRunner {
                     init_forkfile_size.set(forkfile_size(&forkfile));
 
                     cmd.env(ENV_FORK_FILE, forkfile.as_ref().unwrap().path());
+                    if let Some(ref output_file) = output_file {
+                        cmd.stdout(output_file.try_clone().expect(
+                            "Failed to redirect output of child process",
+                        ));
+                        cmd.stderr(output_file.try_clone().expect(
+                            "Failed to redirect output of child process",
+                        ));
+                    }
                 },
                 |child, _| {
                     await_child(
@@ -480,6 +767,15 @@ impl TestRunner {
             )
             .expect("Fork failed");
 
+            // If the child crashed in the middle of a test case, it never
+            // marked the end of the output of that case, so do it for it.
+            if let Some(ref output_file) = output_file {
+                use std::io::Write;
+
+                writeln!(&*output_file, "{}-end", OUTPUT_MARKER)
+                    .expect("Failed to write to output file");
+            }
+
             let parsed = replay::Replay::parse_from(
                 &mut forkfile.borrow_mut().as_mut().unwrap(),
             )
@@ -539,6 +835,19 @@ impl TestRunner {
             }
         }
 
+        let mut fork_output = ForkOutput::empty();
+        if let Some(mut output_file) = output_file {
+            use std::io::{Read, Seek, SeekFrom};
+
+            let mut output = Vec::new();
+            output_file
+                .seek(SeekFrom::Start(0))
+                .and_then(|_| output_file.read_to_end(&mut output))
+                .expect("Failed to read output of child process");
+            fork_output.captured =
+                split_captured_output(&String::from_utf8_lossy(&output));
+        }
+
         // Run through the steps in-process (without ever running the actual
         // tests) to produce the shrunken value and update the persistence
         // file.
@@ -547,7 +856,7 @@ impl TestRunner {
             strategy,
             |_| panic!("Ran past the end of the replay"),
             replay.steps.into_iter(),
-            ForkOutput::empty(),
+            fork_output,
         )
     }
 
@@ -556,7 +865,18 @@ impl TestRunner {
         strategy: &S,
         test: impl Fn(S::Value) -> TestCaseResult,
     ) -> TestRunResult<S> {
-        let (replay_steps, fork_output) = init_replay(&mut self.rng);
+        #[cfg(feature = "std")]
+        if self.config.capture_output && self.config.output_capture.is_none() {
+            verbose_message!(
+                self,
+                ALWAYS,
+                "Not capturing output since capture_output is set without \
+                 forking or an output_capture hook"
+            );
+        }
+
+        let (replay_steps, fork_output) =
+            init_replay(&mut self.rng, &self.config);
         self.run_in_process_with_replay(
             strategy,
             test,
@@ -573,6 +893,7 @@ impl TestRunner {
         mut fork_output: ForkOutput,
     ) -> TestRunResult<S> {
         let old_rng = self.rng.clone();
+        let old_size = self.size;
 
         let persisted_failure_seeds: Vec<PersistedSeed> = self
             .config
@@ -583,8 +904,11 @@ impl TestRunner {
 
         let mut result_cache = self.new_cache();
 
-        for PersistedSeed(persisted_seed) in persisted_failure_seeds {
+        for PersistedSeed(persisted_seed, persisted_size) in
+            persisted_failure_seeds
+        {
             self.rng.set_seed(persisted_seed);
+            self.size = persisted_size.unwrap_or(MAX_SIZE).min(MAX_SIZE);
             self.gen_and_run_case(
                 strategy,
                 &test,
@@ -594,11 +918,28 @@ impl TestRunner {
             )?;
         }
         self.rng = old_rng;
+        self.size = old_size;
+
+        if UnionArmCoverage::Random != self.config.union_arms {
+            self.union_arms = Some(UnionArms::default());
+        }
 
+        let mut case_index = 0;
         while self.successes < self.config.cases {
             // Generate a new seed and make an RNG from that so that we know
             // what seed to persist if this case fails.
             let seed = self.rng.gen_get_seed();
+            self.case_index = Some(case_index);
+            self.case_unseeded = false;
+            case_index += 1;
+            if self.config.grow_size {
+                // Grow linearly so that the last case has the full size.
+                self.size = ((u64::from(self.successes) + 1)
+                    * u64::from(MAX_SIZE)
+                    / u64::from(self.config.cases.max(1)))
+                .max(1)
+                .min(u64::from(MAX_SIZE)) as u32;
+            }
             let result = self.gen_and_run_case(
                 strategy,
                 &test,
@@ -606,7 +947,9 @@ impl TestRunner {
                 &mut *result_cache,
                 &mut fork_output,
             );
+            self.case_index = None;
             if let Err(TestError::Fail(_, ref value)) = result {
+                let size = Some(self.size).filter(|_| self.config.grow_size);
                 if let Some(ref mut failure_persistence) =
                     self.config.failure_persistence
                 {
@@ -614,11 +957,14 @@ impl TestRunner {
 
                     // Don't update the persistence file if we're a child
                     // process. The parent relies on it remaining consistent
-                    // and will take care of updating it itself.
-                    if !fork_output.is_in_fork() {
+                    // and will take care of updating it itself. Cases
+                    // which do not depend only on their seeds are not
+                    // persisted either, since their seeds could not be
+                    // relied upon to reproduce them.
+                    if !fork_output.is_in_fork() && !self.case_unseeded {
                         failure_persistence.save_persisted_failure2(
                             *source_file,
-                            PersistedSeed(seed),
+                            PersistedSeed(seed, size),
                             value,
                         );
                     }
@@ -626,13 +972,91 @@ impl TestRunner {
             }
 
             if let Err(e) = result {
+                self.union_arms = None;
                 fork_output.terminate();
                 return Err(e.into());
             }
         }
 
         fork_output.terminate();
-        Ok(())
+        self.check_union_arms()
+    }
+
+    /// Reports the union arms which were never chosen during the run, if
+    /// `Config::union_arms` asked for them to be tracked.
+    fn check_union_arms<T>(&mut self) -> Result<(), TestError<T>> {
+        let union_arms = match self.union_arms.take() {
+            Some(union_arms) => union_arms,
+            None => return Ok(()),
+        };
+        let never_chosen = union_arms.never_chosen();
+        if never_chosen.is_empty() {
+            return Ok(());
+        }
+
+        let mut message = format!(
+            "{} union arm(s) never chosen in {} cases:",
+            never_chosen.len(),
+            self.successes
+        );
+        for label in never_chosen {
+            message.push_str("\n    ");
+            message.push_str(label);
+        }
+
+        if UnionArmCoverage::Strict == self.config.union_arms {
+            Err(TestError::Abort(message.into()))
+        } else {
+            verbose_message!(self, ALWAYS, "{}", message);
+            Ok(())
+        }
+    }
+
+    /// If `Config::union_arms` is not `Random` and a run is in progress,
+    /// returns the first arm of the union identified by `id` which has not
+    /// been chosen yet, or under which some union has arms not chosen yet.
+    /// `describe` labels each arm for the report at the end of the run,
+    /// leaving unlabelled those which must never be chosen.
+    ///
+    /// `union_arm_generated()` must be called once the value of an arm
+    /// returned by this function or passed to `union_arm_chosen()` has been
+    /// generated.
+    pub(crate) fn cycle_union_arm(
+        &mut self,
+        id: UnionId,
+        describe: impl FnOnce() -> Vec<Option<String>>,
+    ) -> Option<usize> {
+        self.union_arms.as_mut()?.cycle(id, describe)
+    }
+
+    /// Returns the index of the case being generated among the new cases of
+    /// the current `run()`, counting cases which are rejected, or `None` if
+    /// no such case is being generated, e.g. while persisted failures are
+    /// being replayed.
+    ///
+    /// Strategies which use the index must call `case_unseeded()`.
+    pub(crate) fn case_index(&self) -> Option<u32> {
+        self.case_index
+    }
+
+    /// Records that the case being generated does not depend only on the
+    /// seed of the RNG, so that its seed is not persisted if it fails.
+    pub(crate) fn case_unseeded(&mut self) {
+        self.case_unseeded = true;
+    }
+
+    /// Records that the union identified by `id` chose arm `pick` at random.
+    pub(crate) fn union_arm_chosen(&mut self, id: UnionId, pick: usize) {
+        if let Some(ref mut union_arms) = self.union_arms {
+            union_arms.chosen(id, pick);
+        }
+    }
+
+    /// Notes that the value of the union arm last chosen has been generated.
+    pub(crate) fn union_arm_generated(&mut self) {
+        if let Some(ref mut union_arms) = self.union_arms {
+            union_arms.leave();
+        }
     }
 
     fn gen_and_run_case<S: Strategy>(
@@ -643,7 +1067,14 @@ impl TestRunner {
         result_cache: &mut dyn ResultCache,
         fork_output: &mut ForkOutput,
     ) -> TestRunResult<S> {
-        let case = unwrap_or!(strategy.new_tree(self), msg =>
+        if let Some(ref mut union_arms) = self.union_arms {
+            union_arms.start_case();
+        }
+        let case = strategy.new_tree(self);
+        if let Some(ref mut union_arms) = self.union_arms {
+            union_arms.end_case();
+        }
+        let case = unwrap_or!(case, msg =>
                 return Err(TestError::Abort(msg)));
 
         if self.run_one_with_replay(
@@ -703,9 +1134,16 @@ impl TestRunner {
             Ok(_) => Ok(true),
             Err(TestCaseError::Fail(why)) => {
                 let why = self
-                    .shrink(&mut case, test, replay, result_cache, fork_output)
+                    .shrink(
+                        &mut case,
+                        &why,
+                        &test,
+                        replay,
+                        result_cache,
+                        fork_output,
+                    )
                     .unwrap_or(why);
-                Err(TestError::Fail(why, case.current()))
+                Err(self.rerun_failure(&case, why, test, replay, fork_output))
             }
             Err(TestCaseError::Reject(whence)) => {
                 self.reject_global(whence)?;
@@ -717,6 +1155,7 @@ impl TestRunner {
     fn shrink<V: ValueTree>(
         &mut self,
         case: &mut V,
+        original: &Reason,
         test: impl Fn(V::Value) -> TestCaseResult,
         replay: &mut impl Iterator<Item = TestCaseResult>,
         result_cache: &mut dyn ResultCache,
@@ -725,6 +1164,24 @@ impl TestRunner {
         #[cfg(feature = "std")]
         use std::time;
 
+        // The parent of forked processes only sees that a case failed and not
+        // why, so it could not make the same decisions as the child.
+        let fingerprint = if self.config.fork() {
+            if FailureFingerprint::Any != self.config.failure_fingerprint {
+                verbose_message!(
+                    self,
+                    ALWAYS,
+                    "Ignoring failure_fingerprint = {:?} since failure \
+                     fingerprints are not supported when forking",
+                    self.config.failure_fingerprint
+                );
+            }
+            FailureFingerprint::Any
+        } else {
+            self.config.failure_fingerprint
+        };
+        let original_fingerprint = fingerprint.of(original);
+
         let mut last_failure = None;
         let mut iterations = 0;
         #[cfg(feature = "std")]
@@ -823,9 +1280,29 @@ impl TestRunner {
                         }
                     }
                     Err(TestCaseError::Fail(why)) => {
-                        last_failure = Some(why);
-                        if !case.simplify() {
-                            break;
+                        let this_fingerprint = fingerprint.of(&why);
+                        if this_fingerprint == original_fingerprint {
+                            last_failure = Some(why);
+                            if !case.simplify() {
+                                break;
+                            }
+                        } else {
+                            // A different bug; keep shrinking the original
+                            // one, but remember this one for the report.
+                            verbose_message!(
+                                self,
+                                SHOW_FALURES,
+                                "Distinct failure found while shrinking: {}",
+                                why
+                            );
+                            self.record_distinct_failure(
+                                this_fingerprint,
+                                why,
+                                &case.current(),
+                            );
+                            if !case.complicate() {
+                                break;
+                            }
                         }
                     }
                 }
@@ -835,6 +1312,81 @@ impl TestRunner {
         last_failure
     }
 
+    fn record_distinct_failure(
+        &mut self,
+        fingerprint: String,
+        reason: Reason,
+        input: &dyn fmt::Debug,
+    ) {
+        if self
+            .distinct_failures
+            .iter()
+            .all(|f| f.fingerprint != fingerprint)
+        {
+            self.distinct_failures.push(DistinctFailure {
+                fingerprint,
+                reason,
+                input: format!("{:?}", input),
+            });
+        }
+    }
+
+    /// Return the failures encountered while shrinking which were considered
+    /// to be different bugs than the one being shrunk, one per fingerprint.
+    ///
+    /// This is always empty unless `Config::failure_fingerprint` is set.
+    pub fn distinct_failures(&self) -> &[DistinctFailure] {
+        &self.distinct_failures
+    }
+
+    /// Re-run the minimal failing case `case` as many times as configured to
+    /// check that it fails consistently, and produce the resulting error.
+    fn rerun_failure<V: ValueTree>(
+        &mut self,
+        case: &V,
+        why: Reason,
+        test: impl Fn(V::Value) -> TestCaseResult,
+        replay: &mut impl Iterator<Item = TestCaseResult>,
+        fork_output: &mut ForkOutput,
+    ) -> TestError<V::Value> {
+        let mut passes = 0;
+        let mut failures = 0;
+        for _ in 0..self.config.flaky_reruns {
+            // A cached result would be no evidence at all.
+            let result = call_test(
+                self,
+                case.current(),
+                &test,
+                replay,
+                &mut *noop_result_cache(),
+                fork_output,
+            );
+            match result {
+                Err(TestCaseError::Fail(_)) => failures += 1,
+                Ok(_) | Err(TestCaseError::Reject(..)) => passes += 1,
+            }
+        }
+
+        if passes > 0 {
+            verbose_message!(
+                self,
+                ALWAYS,
+                "Minimal failing input passed {} of {} re-runs; \
+                 the test is flaky",
+                passes,
+                passes + failures
+            );
+            TestError::Flaky {
+                reason: why,
+                value: case.current(),
+                passes,
+                failures,
+            }
+        } else {
+            TestError::Fail(why, case.current())
+        }
+    }
+
     /// Update the state to account for a local rejection from `whence`, and
     /// return `Ok` if the caller should keep going or `Err` to abort.
     pub fn reject_local(
@@ -885,7 +1437,10 @@ impl TestRunner {
 }
 
 #[cfg(feature = "fork")]
-fn init_replay(rng: &mut TestRng) -> (Vec<TestCaseResult>, ForkOutput) {
+fn init_replay(
+    rng: &mut TestRng,
+    config: &Config,
+) -> (Vec<TestCaseResult>, ForkOutput) {
     use crate::test_runner::replay::{open_file, Replay, ReplayFileStatus::*};
 
     if let Some(path) = env::var_os(ENV_FORK_FILE) {
@@ -895,7 +1450,14 @@ fn init_replay(rng: &mut TestRng) -> (Vec<TestCaseResult>, ForkOutput) {
         match loaded {
             InProgress(replay) => {
                 rng.set_seed(replay.seed);
-                (replay.steps, ForkOutput { file: Some(file) })
+                (
+                    replay.steps,
+                    ForkOutput {
+                        file: Some(file),
+                        delimit_output: config.capture_output,
+                        ..ForkOutput::empty()
+                    },
+                )
             }
 
             Terminated(_) => {
@@ -912,6 +1474,7 @@ fn init_replay(rng: &mut TestRng) -> (Vec<TestCaseResult>, ForkOutput) {
 #[cfg(not(feature = "fork"))]
 fn init_replay(
     _rng: &mut TestRng,
+    _config: &Config,
 ) -> (iter::Empty<TestCaseResult>, ForkOutput) {
     (iter::empty(), ForkOutput::empty())
 }
@@ -1006,12 +1569,16 @@ fn await_child(
 
 #[cfg(test)]
 mod test {
+    use core::any::Any;
     use std::cell::Cell;
     use std::fs;
 
     use super::*;
     use crate::strategy::Strategy;
-    use crate::test_runner::{FileFailurePersistence, RngAlgorithm, TestRng};
+    use crate::test_runner::{
+        FileFailurePersistence, MapFailurePersistence, OutputCapture,
+        RngAlgorithm, TestRng,
+    };
 
     #[test]
     fn gives_up_after_too_many_rejections() {
@@ -1273,6 +1840,362 @@ mod test {
         }
     }
 
+    #[test]
+    fn consistent_failure_not_flaky() {
+        let mut runner = TestRunner::new(Config {
+            failure_persistence: None,
+            flaky_reruns: 3,
+            ..Config::default()
+        });
+        let result = runner.run(&(0u32..1000), |v| {
+            prop_assert!(v < 500);
+            Ok(())
+        });
+
+        match result {
+            Err(TestError::Fail(_, 500)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn nondeterministic_failure_reported_as_flaky() {
+        let mut runner = TestRunner::new(Config {
+            failure_persistence: Some(Box::new(
+                MapFailurePersistence::default(),
+            )),
+            flaky_reruns: 4,
+            ..Config::default()
+        });
+        let calls = Cell::new(0);
+        let result = runner.run(&(0u32..1000), |v| {
+            calls.set(calls.get() + 1);
+            prop_assert!(v < 500 || calls.get() % 2 == 0);
+            Ok(())
+        });
+
+        match result {
+            Err(TestError::Flaky {
+                passes, failures, ..
+            }) => {
+                assert_eq!(4, passes + failures);
+                assert!(passes > 0);
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert!(runner
+            .config()
+            .failure_persistence
+            .as_ref()
+            .unwrap()
+            .load_persisted_failures2(None)
+            .is_empty());
+    }
+
+    #[test]
+    fn grow_size_grows_collections_over_run() {
+        use std::cell::RefCell;
+
+        let mut runner = TestRunner::new(Config {
+            cases: 100,
+            grow_size: true,
+            failure_persistence: None,
+            ..Config::default()
+        });
+        let lens = RefCell::new(Vec::new());
+        runner
+            .run(&crate::collection::vec(0u8..10, 0..=1000), |v| {
+                lens.borrow_mut().push(v.len());
+                Ok(())
+            })
+            .unwrap();
+
+        let lens = lens.into_inner();
+        assert_eq!(100, lens.len());
+        for (ix, &len) in lens.iter().enumerate() {
+            assert!(len <= (ix + 1) * 10, "case {} had length {}", ix, len);
+        }
+        assert!(lens[90..].iter().any(|&len| len > 500));
+        assert_eq!(MAX_SIZE, runner.size());
+    }
+
+    #[test]
+    fn grow_size_failures_replayed_with_same_size() {
+        use std::cell::RefCell;
+
+        let config = Config {
+            grow_size: true,
+            failure_persistence: Some(Box::new(
+                MapFailurePersistence::default(),
+            )),
+            source_file: Some("grow_size"),
+            ..Config::default()
+        };
+        let strategy = crate::collection::vec(0u8..10, 0..=1000);
+        let inputs = RefCell::new(Vec::new());
+        let test = |v: Vec<u8>| {
+            inputs.borrow_mut().push(v.clone());
+            prop_assert!(v.len() < 50);
+            Ok(())
+        };
+
+        let mut runner = TestRunner::new(config);
+        assert!(runner.run(&strategy, test).is_err());
+        let failing = inputs.borrow().iter().find(|v| v.len() >= 50).cloned();
+        let persisted = runner
+            .config()
+            .failure_persistence
+            .as_ref()
+            .unwrap()
+            .load_persisted_failures2(Some("grow_size"));
+        assert_eq!(1, persisted.len());
+        assert!(persisted[0].1.unwrap() < MAX_SIZE);
+        assert_eq!(
+            Ok(persisted[0].clone()),
+            format!("{}", persisted[0]).parse()
+        );
+
+        inputs.borrow_mut().clear();
+        let mut runner = TestRunner::new(runner.config().clone());
+        assert!(runner.run(&strategy, test).is_err());
+        assert_eq!(failing, inputs.borrow().first().cloned());
+    }
+
+    #[test]
+    fn shrinking_without_fingerprint_switches_bugs() {
+        let mut runner = TestRunner::default();
+        let result =
+            runner.run_one(crate::num::u32::BinarySearch::new(800), |v| {
+                prop_assert!(v < 500, "too big");
+                prop_assert!(v < 300, "too big, but not by much");
+                Ok(())
+            });
+
+        match result {
+            Err(TestError::Fail(ref why, 300)) => {
+                assert!(why.message().starts_with("too big, but"));
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert!(runner.distinct_failures().is_empty());
+    }
+
+    #[test]
+    fn shrinking_with_fingerprint_stays_on_same_bug() {
+        let mut runner = TestRunner::new(Config {
+            failure_fingerprint: FailureFingerprint::Message,
+            ..Config::default()
+        });
+        let result =
+            runner.run_one(crate::num::u32::BinarySearch::new(800), |v| {
+                prop_assert!(v < 500, "too big");
+                prop_assert!(v < 300, "too big, but not by much");
+                Ok(())
+            });
+
+        match result {
+            Err(TestError::Fail(ref why, 500)) => {
+                assert!(why.message().starts_with("too big"));
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        let distinct = runner.distinct_failures();
+        assert_eq!(1, distinct.len());
+        assert!(distinct[0].reason.message().starts_with("too big, but"));
+        assert_eq!("400", distinct[0].input);
+        assert!(format!("{}", runner).contains("distinct failures"));
+    }
+
+    #[test]
+    fn panic_location_fingerprint_distinguishes_panics() {
+        let mut runner = TestRunner::new(Config {
+            failure_fingerprint: FailureFingerprint::PanicLocation,
+            ..Config::default()
+        });
+        let result =
+            runner.run_one(crate::num::u32::BinarySearch::new(800), |v| {
+                assert!(v < 500, "bad value {}", v);
+                assert!(v < 300, "bad value {}", v);
+                Ok(())
+            });
+
+        match result {
+            Err(TestError::Fail(ref why, 500)) => {
+                assert_eq!("bad value 500", why.message());
+                assert!(why.location().unwrap().contains("runner.rs"));
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert_eq!(1, runner.distinct_failures().len());
+    }
+
+    #[test]
+    fn notes_reported_only_for_minimal_case() {
+        let mut runner = TestRunner::new(Config {
+            failure_persistence: None,
+            ..Config::default()
+        });
+        let result = runner.run(&(0u32..1000), |v| {
+            prop_note!("v = {}", v);
+            prop_assert!(v < 500);
+            Ok(())
+        });
+
+        match result {
+            Err(TestError::Fail(why, 500)) => {
+                assert_eq!(vec!["v = 500"], why.notes().collect::<Vec<_>>());
+                assert_eq!(None, why.output());
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct CaseCountCapture(u32);
+
+    impl OutputCapture for CaseCountCapture {
+        fn begin(&mut self) {
+            self.0 += 1;
+        }
+
+        fn end(&mut self) -> Option<String> {
+            Some(format!("case {}", self.0))
+        }
+
+        fn box_clone(&self) -> Box<dyn OutputCapture> {
+            Box::new(self.clone())
+        }
+
+        fn eq(&self, other: &dyn OutputCapture) -> bool {
+            other.as_any().downcast_ref::<Self>() == Some(self)
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[test]
+    fn output_captured_in_process_for_minimal_case() {
+        let config = Config {
+            failure_persistence: None,
+            output_capture: Some(Box::new(CaseCountCapture(0))),
+            ..Config::default()
+        };
+        let test = |v: u32| {
+            prop_assert!(v < 500);
+            Ok(())
+        };
+
+        match TestRunner::new(config.clone()).run(&(0u32..1000), test) {
+            Err(TestError::Fail(why, 500)) => assert_eq!(None, why.output()),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        let mut runner = TestRunner::new(Config {
+            capture_output: true,
+            ..config
+        });
+        match runner.run(&(0u32..1000), test) {
+            // Each case starts from a fresh clone of the hook.
+            Err(TestError::Fail(why, 500)) => {
+                assert_eq!(Some("case 1"), why.output())
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[cfg(feature = "fork")]
+    #[test]
+    fn output_captured_in_fork_for_minimal_case() {
+        let mut runner = TestRunner::new(Config {
+            fork: true,
+            capture_output: true,
+            failure_persistence: None,
+            test_name: Some(concat!(
+                module_path!(),
+                "::output_captured_in_fork_for_minimal_case"
+            )),
+            ..Config::default()
+        });
+
+        let failure = runner
+            .run(&(0u32..1000), |v| {
+                println!("v = {}", v);
+                prop_note!("note {}", v);
+                if v >= 500 {
+                    eprintln!("crashing");
+                    ::std::process::exit(1);
+                }
+                Ok(())
+            })
+            .err()
+            .unwrap();
+
+        match failure {
+            TestError::Fail(why, 500) => {
+                assert_eq!(Some("v = 500\ncrashing\n"), why.output());
+                assert_eq!(vec!["note 500"], why.notes().collect::<Vec<_>>());
+            }
+            failure => panic!("Unexpected failure: {:?}", failure),
+        }
+    }
+
+    #[cfg(feature = "fork")]
+    #[test]
+    fn notes_reported_from_fork_without_capture() {
+        let mut runner = TestRunner::new(Config {
+            fork: true,
+            failure_persistence: None,
+            test_name: Some(concat!(
+                module_path!(),
+                "::notes_reported_from_fork_without_capture"
+            )),
+            ..Config::default()
+        });
+
+        let failure = runner
+            .run(&(0u32..1000), |v| {
+                prop_note!("note \"{}\"\\", v);
+                if v >= 600 {
+                    ::std::process::exit(1);
+                }
+                prop_assert!(v < 500);
+                Ok(())
+            })
+            .err()
+            .unwrap();
+
+        match failure {
+            TestError::Fail(why, 500) => {
+                assert_eq!(
+                    vec!["note \"500\"\\"],
+                    why.notes().collect::<Vec<_>>()
+                );
+                assert_eq!(None, why.output());
+            }
+            failure => panic!("Unexpected failure: {:?}", failure),
+        }
+    }
+
+    #[cfg(feature = "fork")]
+    #[test]
+    fn split_captured_output_assigns_output_to_steps() {
+        let output = format!(
+            "junk\n{m}:0\nzero\n{m}-end\nmore junk\n\
+             {m}:2\ntwo\n{m}-end\n{m}:1\ncrashed\n{m}:1\none{m}-end\n",
+            m = OUTPUT_MARKER
+        );
+        assert_eq!(
+            vec![
+                Some(String::from("zero\n")),
+                Some(String::from("one")),
+                Some(String::from("two\n")),
+            ],
+            split_captured_output(&output)
+        );
+    }
+
     #[cfg(feature = "timeout")]
     #[test]
     fn long_sleep_timeout_finds_correct_failure() {
@@ -1380,6 +2303,82 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn exhaustive_runs_every_value_once() {
+        use std::cell::RefCell;
+
+        let mut runner = TestRunner::new(Config {
+            failure_persistence: None,
+            ..Config::default()
+        });
+        let seen = RefCell::new(Vec::new());
+        let result = runner.run_exhaustive(&(crate::bool::ANY, 0u8..4), |v| {
+            seen.borrow_mut().push(v);
+            Ok(())
+        });
+        assert_eq!(Ok(()), result);
+
+        let mut seen = seen.into_inner();
+        assert_eq!(8, seen.len());
+        seen.sort();
+        seen.dedup();
+        assert_eq!(8, seen.len());
+    }
+
+    #[test]
+    fn exhaustive_falls_back_to_random_for_large_domains() {
+        let mut runner = TestRunner::new(Config {
+            cases: 10,
+            failure_persistence: None,
+            ..Config::default()
+        });
+        let runs = Cell::new(0);
+        let result = runner.run_exhaustive(&(0u32..1000), |_| {
+            runs.set(runs.get() + 1);
+            Ok(())
+        });
+        assert_eq!(Ok(()), result);
+        assert_eq!(10, runs.get());
+    }
+
+    #[cfg(feature = "fork")]
+    #[test]
+    fn exhaustive_runs_in_fork_when_asked() {
+        let mut runner = TestRunner::new(Config {
+            fork: true,
+            test_name: Some(concat!(
+                module_path!(),
+                "::exhaustive_runs_in_fork_when_asked"
+            )),
+            failure_persistence: None,
+            ..Config::default()
+        });
+        let runs = Cell::new(0);
+        let result = runner.run_exhaustive(&crate::bool::ANY, |_| {
+            runs.set(runs.get() + 1);
+            Ok(())
+        });
+        assert_eq!(Ok(()), result);
+        // The cases ran in the child process.
+        assert_eq!(0, runs.get());
+    }
+
+    #[test]
+    fn exhaustive_failure_is_shrunk() {
+        let mut runner = TestRunner::new(Config {
+            failure_persistence: None,
+            ..Config::default()
+        });
+        let result = runner.run_exhaustive(&(0u8..3, 0u8..50), |(a, b)| {
+            prop_assert!(a < 2 || b < 40);
+            Ok(())
+        });
+        match result {
+            Err(TestError::Fail(_, value)) => assert_eq!((2, 40), value),
+            e => panic!("Unexpected result: {:?}", e),
+        }
+    }
 }
 
 #[cfg(all(feature = "fork", feature = "timeout", test))]
@@ -0,0 +1,395 @@
+//-
+// Copyright 2021 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::std_facade::{fmt, vec, BTreeMap, String, Vec};
+use core::str::FromStr;
+
+/// How the arms of unions, such as those created by `prop_oneof!` or derived
+/// `Arbitrary` implementations for enums, are chosen during a test run.
+///
+/// See `Config::union_arms`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnionArmCoverage {
+    /// Arms are always chosen at random according to their weights. This is
+    /// the default.
+    Random,
+    /// The first time each union generates a value, and every time after
+    /// that until all of its arms have been chosen, it chooses the first arm
+    /// it has not chosen yet; after that, arms are chosen at random. Arms
+    /// which were never chosen by the end of the run are reported as a
+    /// warning.
+    Cycle,
+    /// Like `Cycle`, but arms never chosen by the end of the run make the
+    /// test fail.
+    Strict,
+}
+
+impl Default for UnionArmCoverage {
+    fn default() -> Self {
+        UnionArmCoverage::Random
+    }
+}
+
+impl UnionArmCoverage {
+    fn key(self) -> &'static str {
+        match self {
+            UnionArmCoverage::Random => "random",
+            UnionArmCoverage::Cycle => "cycle",
+            UnionArmCoverage::Strict => "strict",
+        }
+    }
+}
+
+impl FromStr for UnionArmCoverage {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "random" => Ok(UnionArmCoverage::Random),
+            "cycle" => Ok(UnionArmCoverage::Cycle),
+            "strict" => Ok(UnionArmCoverage::Strict),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for UnionArmCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.key())
+    }
+}
+
+/// Identifies a union strategy within a run: its address, its type and its
+/// number of arms, so that a union dropped and replaced by another at the
+/// same address is only confused with it if they have the same shape.
+///
+/// A union built anew for every case, such as a `prop_oneof!` inside
+/// `prop_flat_map()`, is seen as a different union each time. Such unions
+/// stop the cycling (see `UnionArms::end_case()`), and are reported together
+/// with the other unions of the same type and arms (see
+/// `UnionArms::never_chosen()`).
+pub(crate) type UnionId = (usize, &'static str, usize);
+
+/// Which arms of each union reached during a run have been chosen.
+///
+/// Unions reached while generating the value of an arm of another union are
+/// recorded as children of that arm, so that the arm keeps being chosen while
+/// cycling until the arms of its children have all been chosen too.
+#[derive(Clone, Debug)]
+pub(crate) struct UnionArms {
+    index: BTreeMap<UnionId, usize>,
+    unions: Vec<UnionStats>,
+    /// The arms whose values are being generated, innermost last.
+    stack: Vec<(usize, usize)>,
+    /// Whether arms are still being cycled through.
+    cycling: bool,
+    /// The number of unions known when the current case started.
+    known: usize,
+    /// Whether a union known when the current case started chose an arm for
+    /// the first time during the case.
+    progress: bool,
+}
+
+/// The arms of a union, each labelled unless it has no weight and should not
+/// be chosen at all, along with how many times each was chosen and the
+/// unions first reached under it.
+#[derive(Clone, Debug)]
+struct UnionStats {
+    type_name: &'static str,
+    labels: Vec<Option<String>>,
+    chosen: Vec<u32>,
+    children: Vec<Vec<usize>>,
+}
+
+impl Default for UnionArms {
+    fn default() -> Self {
+        UnionArms {
+            index: BTreeMap::new(),
+            unions: Vec::new(),
+            stack: Vec::new(),
+            cycling: true,
+            known: 0,
+            progress: false,
+        }
+    }
+}
+
+impl UnionArms {
+    /// Notes that a new test case is about to be generated.
+    pub(crate) fn start_case(&mut self) {
+        self.stack.clear();
+        self.known = self.unions.len();
+        self.progress = false;
+    }
+
+    /// Notes that the current test case has been generated.
+    ///
+    /// Cycling stops after the first case which did not choose any new arm of
+    /// the unions known before it, since it either has nothing left to cycle
+    /// through or keeps reaching unions built anew for every case, which it
+    /// could never exhaust.
+    pub(crate) fn end_case(&mut self) {
+        if self.known > 0 && !self.progress {
+            self.cycling = false;
+        }
+    }
+
+    fn register(
+        &mut self,
+        id: UnionId,
+        describe: impl FnOnce() -> Vec<Option<String>>,
+    ) -> usize {
+        if let Some(&ix) = self.index.get(&id) {
+            return ix;
+        }
+
+        let ix = self.unions.len();
+        let labels = describe();
+        self.unions.push(UnionStats {
+            type_name: id.1,
+            chosen: vec![0; labels.len()],
+            children: vec![Vec::new(); labels.len()],
+            labels,
+        });
+        self.index.insert(id, ix);
+        if let Some(&(parent, arm)) = self.stack.last() {
+            self.unions[parent].children[arm].push(ix);
+        }
+        ix
+    }
+
+    fn arm_complete(&self, ix: usize, arm: usize) -> bool {
+        let stats = &self.unions[ix];
+        stats.labels[arm].is_none()
+            || (stats.chosen[arm] > 0
+                && stats.children[arm]
+                    .iter()
+                    .all(|&child| self.union_complete(child)))
+    }
+
+    fn union_complete(&self, ix: usize) -> bool {
+        (0..self.unions[ix].labels.len()).all(|arm| self.arm_complete(ix, arm))
+    }
+
+    fn enter(&mut self, ix: usize, arm: usize) {
+        if 0 == self.unions[ix].chosen[arm] && ix < self.known {
+            self.progress = true;
+        }
+        self.unions[ix].chosen[arm] += 1;
+        self.stack.push((ix, arm));
+    }
+
+    /// Chooses the first arm of the union `id` which has not been chosen yet,
+    /// or under which some union has arms not chosen yet, if any and if still
+    /// cycling. `describe` is called the first time the union is seen to
+    /// label each arm, or exclude it from cycling if it has no label.
+    ///
+    /// If an arm is chosen, `leave()` must be called once its value has been
+    /// generated.
+    pub(crate) fn cycle(
+        &mut self,
+        id: UnionId,
+        describe: impl FnOnce() -> Vec<Option<String>>,
+    ) -> Option<usize> {
+        let ix = self.register(id, describe);
+        if !self.cycling {
+            return None;
+        }
+
+        let arms = self.unions[ix].labels.len();
+        let pick = (0..arms).find(|&arm| !self.arm_complete(ix, arm))?;
+        self.enter(ix, pick);
+        Some(pick)
+    }
+
+    /// Records that the union `id` chose arm `pick` at random.
+    ///
+    /// `leave()` must be called once the value of the arm has been generated.
+    pub(crate) fn chosen(&mut self, id: UnionId, pick: usize) {
+        let ix = self.index[&id];
+        self.enter(ix, pick);
+    }
+
+    /// Notes that the value of the arm last chosen has been generated.
+    pub(crate) fn leave(&mut self) {
+        self.stack.pop();
+    }
+
+    /// Returns the labels of the arms which were never chosen, in the order
+    /// their unions were first reached.
+    ///
+    /// Unions of the same type whose arms have the same labels are taken to
+    /// be the same union built anew, so an arm is only reported if none of
+    /// them chose it.
+    pub(crate) fn never_chosen(&self) -> Vec<&str> {
+        let mut groups = BTreeMap::new();
+        let mut chosen: Vec<(&UnionStats, Vec<bool>)> = Vec::new();
+        for stats in &self.unions {
+            let group = *groups
+                .entry((stats.type_name, &stats.labels))
+                .or_insert_with(|| {
+                    chosen.push((stats, vec![false; stats.labels.len()]));
+                    chosen.len() - 1
+                });
+            for (seen, &count) in chosen[group].1.iter_mut().zip(&stats.chosen)
+            {
+                *seen |= count > 0;
+            }
+        }
+
+        chosen
+            .into_iter()
+            .flat_map(|(stats, seen)| {
+                stats
+                    .labels
+                    .iter()
+                    .zip(seen)
+                    .filter(|&(_, seen)| !seen)
+                    .filter_map(|(label, _)| label.as_ref())
+                    .map(String::as_str)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::std_facade::ToOwned;
+
+    fn labels() -> Vec<Option<String>> {
+        vec![
+            Some("a".to_owned()),
+            Some("b".to_owned()),
+            Some("c".to_owned()),
+        ]
+    }
+
+    #[test]
+    fn cycles_through_arms_then_defers() {
+        let mut arms = UnionArms::default();
+        let id = (1, "u", 3);
+        arms.start_case();
+        assert_eq!(Some(0), arms.cycle(id, labels));
+        arms.leave();
+        assert_eq!(vec!["b", "c"], arms.never_chosen());
+        arms.end_case();
+
+        arms.start_case();
+        assert_eq!(Some(1), arms.cycle(id, labels));
+        arms.leave();
+        arms.end_case();
+
+        arms.start_case();
+        assert_eq!(Some(2), arms.cycle(id, labels));
+        arms.leave();
+        assert_eq!(None, arms.cycle(id, labels));
+        arms.chosen(id, 0);
+        arms.leave();
+        assert!(arms.never_chosen().is_empty());
+    }
+
+    #[test]
+    fn unions_are_tracked_separately() {
+        let mut arms = UnionArms::default();
+        arms.start_case();
+        for &id in &[(1, "u", 3), (2, "u", 3), (1, "v", 3)] {
+            assert_eq!(Some(0), arms.cycle(id, labels));
+            arms.leave();
+        }
+        assert_eq!(vec!["b", "c", "b", "c"], arms.never_chosen());
+    }
+
+    #[test]
+    fn unions_built_anew_are_reported_together() {
+        let mut arms = UnionArms::default();
+        // The second case reaches no union known before it, so arms are
+        // chosen at random from the third case on.
+        for (case, &random) in [0, 0, 2, 1].iter().enumerate() {
+            let id = (case, "u", 3);
+            arms.start_case();
+            if arms.cycle(id, labels).is_none() {
+                arms.chosen(id, random);
+            }
+            arms.leave();
+            arms.end_case();
+        }
+        assert!(arms.never_chosen().is_empty());
+
+        // Unions with other arms are still reported on their own.
+        let id = (10, "u", 2);
+        assert_eq!(None, arms.cycle(id, || labels()[..2].to_vec()));
+        arms.chosen(id, 0);
+        arms.leave();
+        assert_eq!(vec!["b"], arms.never_chosen());
+    }
+
+    #[test]
+    fn unlabelled_arms_are_skipped() {
+        let mut arms = UnionArms::default();
+        let labels = || vec![None, Some("b".to_owned())];
+        assert_eq!(Some(1), arms.cycle((1, "u", 2), labels));
+        arms.leave();
+        assert_eq!(None, arms.cycle((1, "u", 2), labels));
+        assert!(arms.never_chosen().is_empty());
+    }
+
+    #[test]
+    fn revisits_arms_with_nested_arms_left() {
+        let mut arms = UnionArms::default();
+        let outer = (1, "outer", 2);
+        let inner = (2, "inner", 3);
+        let two = || vec![Some("x".to_owned()), Some("y".to_owned())];
+
+        let mut picks = Vec::new();
+        for _ in 0..5 {
+            arms.start_case();
+            let pick = arms.cycle(outer, two);
+            if Some(1) == pick {
+                picks.push(arms.cycle(inner, labels));
+                arms.leave();
+            }
+            arms.leave();
+            arms.end_case();
+        }
+        assert_eq!(vec![Some(0), Some(1), Some(2)], picks);
+        assert_eq!(None, arms.cycle(outer, two));
+    }
+
+    #[test]
+    fn stops_cycling_without_progress() {
+        let mut arms = UnionArms::default();
+        let two = || vec![Some("x".to_owned()), Some("y".to_owned())];
+
+        // A union built anew under arm 1 of the outer union for every case
+        // can never be exhausted.
+        for case in 0..4 {
+            arms.start_case();
+            if Some(1) == arms.cycle((1, "outer", 2), two) {
+                assert!(arms.cycle((10 + case, "inner", 2), two).is_some());
+                arms.leave();
+            }
+            arms.leave();
+            arms.end_case();
+        }
+        assert!(!arms.cycling);
+    }
+
+    #[test]
+    fn parses_and_displays() {
+        for &coverage in &[
+            UnionArmCoverage::Random,
+            UnionArmCoverage::Cycle,
+            UnionArmCoverage::Strict,
+        ] {
+            assert_eq!(Ok(coverage), format!("{}", coverage).parse());
+        }
+        assert!("always".parse::<UnionArmCoverage>().is_err());
+    }
+}
@@ -37,6 +37,38 @@ impl<T> TupleValueTree<T> {
     }
 }
 
+// The number of values of a tuple of strategies, given a function returning
+// the number of values of each element.
+macro_rules! tuple_domain_size {
+    ($self:ident, $size:expr, $($fld:tt)*) => { {
+        let size = Some(1u64);
+        $(let size = size?.checked_mul($size(&$self.$fld)?);)*
+        size
+    } };
+}
+
+// The value tree at `$index` of the domain of a tuple of strategies, whose
+// number of values is `$total`, given functions returning the number of
+// values of each element and creating the value tree at an index of its
+// domain.
+macro_rules! tuple_new_tree_at {
+    ($self:ident, $index:ident, $runner:ident, $total:expr, $size:expr,
+     $new_tree_at:expr, $($fld:tt)*) => { {
+        assert!($total.map_or(true, |size| $index < size),
+                "Index out of range of the domain");
+        // The first element varies fastest.
+        let mut digit = |size: Option<u64>| {
+            let size = size.expect("Domain size overflows u64");
+            let digit = $index % size;
+            $index /= size;
+            digit
+        };
+        let values = ($($new_tree_at(
+            &$self.$fld, digit($size(&$self.$fld)), $runner)?,)*);
+        Ok(TupleValueTree::new(values))
+    } };
+}
+
 macro_rules! tuple {
     ($($fld:tt : $typ:ident),*) => {
         impl<$($typ : Strategy),*> Strategy for ($($typ,)*) {
@@ -47,6 +79,37 @@ macro_rules! tuple {
                 let values = ($(self.$fld.new_tree(runner)?,)*);
                 Ok(TupleValueTree::new(values))
             }
+
+            fn enumerable_domain_size(&self) -> Option<u64> {
+                tuple_domain_size!(
+                    self, Strategy::enumerable_domain_size, $($fld)*)
+            }
+
+            fn enumerable_new_tree_at(
+                &self,
+                mut index: u64,
+                runner: &mut TestRunner,
+            ) -> Option<NewTree<Self>> {
+                let total = Some(self.enumerable_domain_size()?);
+                Some((|| tuple_new_tree_at!(
+                    self, index, runner, total,
+                    Strategy::enumerable_domain_size, enumerated_tree_at,
+                    $($fld)*))())
+            }
+        }
+
+        impl<$($typ : Enumerable),*> Enumerable for ($($typ,)*) {
+            fn domain_size(&self) -> Option<u64> {
+                tuple_domain_size!(self, Enumerable::domain_size, $($fld)*)
+            }
+
+            fn new_tree_at(&self, mut index: u64, runner: &mut TestRunner)
+                           -> NewTree<Self> {
+                tuple_new_tree_at!(
+                    self, index, runner, self.domain_size(),
+                    Enumerable::domain_size, Enumerable::new_tree_at,
+                    $($fld)*)
+            }
         }
 
         impl<$($typ : ValueTree),*> ValueTree
@@ -129,6 +192,7 @@ tuple!(
 
 #[cfg(test)]
 mod test {
+    use crate::std_facade::{vec, Vec};
     use crate::strategy::*;
 
     use super::*;
@@ -174,6 +238,29 @@ mod test {
         assert!(cases_tested > 32, "Didn't find enough test cases");
     }
 
+    #[test]
+    fn enumerates_every_combination() {
+        let strategy = (crate::bool::ANY, 0u8..3, Just('x'));
+        assert_eq!(Some(6), strategy.domain_size());
+
+        let mut runner = TestRunner::deterministic();
+        let values = (0..6)
+            .map(|ix| strategy.new_tree_at(ix, &mut runner).unwrap().current())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (false, 0, 'x'),
+                (true, 0, 'x'),
+                (false, 1, 'x'),
+                (true, 1, 'x'),
+                (false, 2, 'x'),
+                (true, 2, 'x'),
+            ],
+            values
+        );
+        assert_eq!(None, (0u64.., 0u8..2).domain_size());
+    }
+
     #[test]
     fn test_sanity() {
         check_strategy_sanity((0i32..100, 0i32..1000, 0i32..10000), None);